@@ -0,0 +1,47 @@
+use crate::soulver::{self, Line};
+use crate::totals;
+
+/// Format lines as sentences instead of aligned columns, which read better with a screen reader,
+/// e.g. `line 3: "Foo + 2" equals 3`. Blank lines and dividers are left out.
+pub fn sentences(lines: &[Line]) -> String {
+    let mut sentences = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        if line.input.trim().is_empty() || soulver::is_divider(&line.input) {
+            continue;
+        }
+        sentences.push(if let Some(heading) = totals::heading(line) {
+            format!("line {number}: heading {heading:?}")
+        } else if line.output.is_empty() {
+            format!("line {number}: {:?}", line.input.trim())
+        } else {
+            format!(
+                "line {number}: {:?} equals {}",
+                line.input.trim(),
+                line.output
+            )
+        });
+    }
+    sentences.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentences() {
+        let lines = [
+            Line::new("# Costs", ""),
+            Line::new("Foo = 1", "1"),
+            Line::new("Foo + 2", "3"),
+            Line::new("", ""),
+            Line::new("---", ""),
+            Line::new("// note", ""),
+        ];
+        assert_eq!(
+            sentences(&lines),
+            "line 1: heading \"Costs\"\nline 2: \"Foo = 1\" equals 1\nline 3: \"Foo + 2\" equals 3\nline 6: \"// note\""
+        );
+    }
+}
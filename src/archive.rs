@@ -0,0 +1,345 @@
+/// The CRC-32 of each byte value, for the checksums of zip entries.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut index = 0;
+    while index < 256 {
+        let mut crc = index as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 == 1 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[index] = crc;
+        index += 1;
+    }
+    table
+};
+
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0, |crc, byte| {
+        CRC_TABLE[((crc ^ u32::from(*byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1, 0), |(a, b), byte| {
+        let a = (a + u32::from(*byte)) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+/// The smallest lengths of the 29 length codes of DEFLATE, with their numbers of extra bits.
+const LENGTHS: [(u16, u8); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// The smallest distances of the 30 distance codes of DEFLATE, with their numbers of extra bits.
+const DISTANCES: [(u16, u8); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+const WINDOW_SIZE: usize = 32 * 1024;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash are tried for each match.
+const MAX_CHAIN: usize = 32;
+
+/// Writes bits from the least significant, as DEFLATE packs them.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    length: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, bits: u32, length: u8) {
+        self.buffer |= bits << self.length;
+        self.length += length;
+        while self.length >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.length -= 8;
+        }
+    }
+
+    /// Write a Huffman code, which is packed from its most significant bit.
+    fn write_code(&mut self, code: u32, length: u8) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+
+    /// Write a symbol of the fixed literal/length code.
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = u32::from(symbol);
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.length > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/// The index of the code for a length or distance, the last whose smallest value it reaches.
+fn code_index(codes: &[(u16, u8)], value: usize) -> usize {
+    codes.partition_point(|(smallest, _)| usize::from(*smallest) <= value) - 1
+}
+
+/// Compress data with DEFLATE in a single block of fixed Huffman codes, finding repeats through
+/// chains of earlier positions with the same hash.
+fn deflate(data: &[u8]) -> Vec<u8> {
+    const HASH_SIZE: usize = 1 << 15;
+    let hash = |position: usize| {
+        let bytes = &data[position..position + MIN_MATCH];
+        ((usize::from(bytes[0]) << 10) ^ (usize::from(bytes[1]) << 5) ^ usize::from(bytes[2]))
+            % HASH_SIZE
+    };
+    let mut head = vec![usize::MAX; HASH_SIZE];
+    let mut previous = vec![usize::MAX; data.len()];
+    let insert = |position: usize, head: &mut [usize], previous: &mut [usize]| {
+        if position + MIN_MATCH <= data.len() {
+            let hash = hash(position);
+            previous[position] = head[hash];
+            head[hash] = position;
+        }
+    };
+
+    let mut writer = BitWriter::default();
+    // The final block, with fixed Huffman codes
+    writer.write(1, 1);
+    writer.write(1, 2);
+    let mut position = 0;
+    while position < data.len() {
+        let mut best_length = 0;
+        let mut best_distance = 0;
+        if position + MIN_MATCH <= data.len() {
+            let longest = (data.len() - position).min(MAX_MATCH);
+            let mut candidate = head[hash(position)];
+            for _ in 0..MAX_CHAIN {
+                if candidate == usize::MAX || position - candidate > WINDOW_SIZE {
+                    break;
+                }
+                let length = (0..longest)
+                    .take_while(|offset| data[candidate + offset] == data[position + offset])
+                    .count();
+                if length > best_length {
+                    best_length = length;
+                    best_distance = position - candidate;
+                    if length == longest {
+                        break;
+                    }
+                }
+                candidate = previous[candidate];
+            }
+        }
+
+        if best_length >= MIN_MATCH {
+            let index = code_index(&LENGTHS, best_length);
+            let (smallest, extra_bits) = LENGTHS[index];
+            writer.write_symbol(257 + index as u16);
+            writer.write((best_length - usize::from(smallest)) as u32, extra_bits);
+            let index = code_index(&DISTANCES, best_distance);
+            let (smallest, extra_bits) = DISTANCES[index];
+            writer.write_code(index as u32, 5);
+            writer.write((best_distance - usize::from(smallest)) as u32, extra_bits);
+            for covered in position..position + best_length {
+                insert(covered, &mut head, &mut previous);
+            }
+            position += best_length;
+        } else {
+            writer.write_symbol(u16::from(data[position]));
+            insert(position, &mut head, &mut previous);
+            position += 1;
+        }
+    }
+    // The end of the block
+    writer.write_symbol(256);
+    writer.finish()
+}
+
+/// Compress data in the zlib format, as PNG images are.
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    // Deflate with a 32 KiB window, and the header's check bits
+    let mut compressed = vec![0x78, 0x01];
+    compressed.extend_from_slice(&deflate(data));
+    compressed.extend_from_slice(&adler32(data).to_be_bytes());
+    compressed
+}
+
+/// January 1, 1980, the earliest date a zip entry can have, in MS-DOS format.
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// Build an uncompressed zip archive of files, given as paths and contents.
+pub fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut central_directory = Vec::new();
+    for (name, contents) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(contents);
+        let size = contents.len() as u32;
+        let name_length = name.len() as u16;
+        // The fields shared by the local header and the central directory: the version needed to
+        // extract, flags, method (stored), time, date, checksum, sizes, and name and extra lengths
+        let mut fields = Vec::new();
+        for field in [20, 0, 0, 0, DOS_DATE] {
+            fields.extend_from_slice(&u16::to_le_bytes(field));
+        }
+        for field in [crc, size, size] {
+            fields.extend_from_slice(&field.to_le_bytes());
+        }
+        for field in [name_length, 0] {
+            fields.extend_from_slice(&field.to_le_bytes());
+        }
+
+        archive.extend_from_slice(&0x0403_4B50_u32.to_le_bytes());
+        archive.extend_from_slice(&fields);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(contents);
+
+        central_directory.extend_from_slice(&0x0201_4B50_u32.to_le_bytes());
+        // The version the archive was made by
+        central_directory.extend_from_slice(&20_u16.to_le_bytes());
+        central_directory.extend_from_slice(&fields);
+        // The comment length, disk number, and internal and external attributes
+        for field in [0_u16, 0, 0, 0, 0] {
+            central_directory.extend_from_slice(&field.to_le_bytes());
+        }
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let central_directory_offset = archive.len() as u32;
+    let entries = files.len() as u16;
+    archive.extend_from_slice(&central_directory);
+    archive.extend_from_slice(&0x0605_4B50_u32.to_le_bytes());
+    for field in [0, 0, entries, entries] {
+        archive.extend_from_slice(&u16::to_le_bytes(field));
+    }
+    archive.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&central_directory_offset.to_le_bytes());
+    // The comment length
+    archive.extend_from_slice(&0_u16.to_le_bytes());
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_adler32() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+    }
+
+    #[test]
+    fn test_code_index() {
+        assert_eq!(code_index(&LENGTHS, 3), 0);
+        assert_eq!(code_index(&LENGTHS, 12), 8);
+        assert_eq!(code_index(&LENGTHS, 258), 28);
+        assert_eq!(code_index(&DISTANCES, 24577), 29);
+        assert_eq!(code_index(&DISTANCES, 32768), 29);
+    }
+
+    #[test]
+    fn test_zlib() {
+        assert_eq!(zlib(b""), [0x78, 0x01, 0x03, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        assert_eq!(
+            zlib(b"abcabcabcabc"),
+            [
+                0x78, 0x01, 0x4B, 0x4C, 0x4A, 0x86, 0x23, 0x00, 0x1D, 0xE0, 0x04, 0x99
+            ],
+        );
+    }
+
+    #[test]
+    fn test_zip() {
+        let archive = zip(&[("a.txt", b"hello"), ("b/c.txt", b"")]);
+        assert!(archive.starts_with(b"PK\x03\x04"));
+        assert_eq!(&archive[30..35], b"a.txt");
+        assert_eq!(&archive[35..40], b"hello");
+        // All entries are listed at the end, after 2 local headers and the central directory
+        let end = &archive[archive.len() - 22..];
+        assert!(end.starts_with(b"PK\x05\x06"));
+        assert_eq!(u16::from_le_bytes([end[10], end[11]]), 2);
+        assert_eq!(
+            archive.len(),
+            (30 + 5 + 5) + (30 + 7) + (46 + 5) + (46 + 7) + 22
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use anyhow::{Result, bail};
+
+use crate::soulver;
+
+/// Marks a line as an assertion of the form `assert: <expression> == <expected>`.
+const ASSERTION_PREFIX: &str = "assert:";
+
+/// The result of checking a single line against its expected result.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Assertion {
+    /// The 1-based line number in the sheet
+    pub line_number: usize,
+    pub description: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Assertion {
+    pub fn passed(&self) -> bool {
+        self.expected == self.actual
+    }
+}
+
+fn parse_assertion(line: &str) -> Option<&str> {
+    line.trim_start()
+        .strip_prefix(ASSERTION_PREFIX)
+        .map(str::trim)
+}
+
+/// Calculate a sheet and check each `assert:` line.
+///
+/// The expression of an assertion is calculated in place of the assertion line so later lines can
+/// depend on it, and the expected value is calculated after the rest of the sheet so it can refer
+/// to any variables defined in it.
+pub fn run_assertions(file: &str) -> Result<Vec<Assertion>> {
+    let mut sheet = Vec::new();
+    let mut assertions = Vec::new();
+    for (index, line) in file.lines().enumerate() {
+        match parse_assertion(line) {
+            Some(assertion) => {
+                let Some((expression, expected)) = assertion.split_once("==") else {
+                    bail!("line {}: assertion is missing `==`", index + 1);
+                };
+                sheet.push(expression.trim());
+                assertions.push((index, assertion, expected.trim()));
+            }
+            None => sheet.push(line),
+        }
+    }
+    if assertions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let number_of_lines = sheet.len();
+    sheet.extend(assertions.iter().map(|(_, _, expected)| *expected));
+    let results = soulver::calculate_lines(&sheet.join("\n"))?;
+    let result = |index: usize| {
+        results
+            .get(index)
+            .map(|line| line.output.trim().to_owned())
+            .unwrap_or_default()
+    };
+
+    Ok(assertions
+        .iter()
+        .enumerate()
+        .map(|(number, (index, assertion, _))| Assertion {
+            line_number: index + 1,
+            description: (*assertion).to_owned(),
+            expected: result(number_of_lines + number),
+            actual: result(*index),
+        })
+        .collect())
+}
+
+/// Calculate a sheet and check each line's result against the corresponding line of `expected`.
+pub fn run_expected(file: &str, expected: &str) -> Result<Vec<Assertion>> {
+    let results = soulver::calculate_lines(file)?;
+    let expected_lines: Vec<&str> = expected.trim_end().lines().collect();
+    let number_of_lines = results.len().max(expected_lines.len());
+
+    Ok((0..number_of_lines)
+        .map(|index| Assertion {
+            line_number: index + 1,
+            description: results
+                .get(index)
+                .map(|line| line.input.clone())
+                .unwrap_or_default(),
+            expected: expected_lines
+                .get(index)
+                .map(|line| line.trim().to_owned())
+                .unwrap_or_default(),
+            actual: results
+                .get(index)
+                .map(|line| line.output.trim().to_owned())
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assertion() {
+        assert_eq!(parse_assertion("assert: 1 + 2 == 3"), Some("1 + 2 == 3"))
+    }
+
+    #[test]
+    fn test_parse_assertion_none() {
+        assert_eq!(parse_assertion("1 + 2"), None)
+    }
+
+    #[test]
+    fn test_run_assertions_missing_equals() {
+        assert!(run_assertions("assert: 1 + 2").is_err())
+    }
+
+    #[test]
+    fn test_run_assertions() {
+        let assertions = run_assertions("Foo = 1\nassert: Foo + 2 == 3\nassert: Foo == 2").unwrap();
+        assert_eq!(
+            assertions,
+            vec![
+                Assertion {
+                    line_number: 2,
+                    description: "Foo + 2 == 3".to_owned(),
+                    expected: "3".to_owned(),
+                    actual: "3".to_owned(),
+                },
+                Assertion {
+                    line_number: 3,
+                    description: "Foo == 2".to_owned(),
+                    expected: "2".to_owned(),
+                    actual: "1".to_owned(),
+                },
+            ],
+        );
+    }
+
+    #[test]
+    fn test_run_assertions_expected_uses_variables() {
+        let assertions = run_assertions("assert: 2 + 2 == Foo\nFoo = 4").unwrap();
+        assert!(assertions[0].passed());
+    }
+
+    #[test]
+    fn test_run_expected() {
+        let assertions = run_expected("# Foo\n1 + 2\n3", "\n3\n4\n").unwrap();
+        let passed: Vec<bool> = assertions.iter().map(Assertion::passed).collect();
+        assert_eq!(passed, vec![true, true, false]);
+    }
+}
@@ -0,0 +1,76 @@
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Apply `f` to each item using up to `jobs` threads, returning the results in the same order as
+/// the items.
+pub fn map<T, R, F>(items: &[T], jobs: NonZeroUsize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.get().min(items.len());
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next_index = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<R>>> = Mutex::new(items.iter().map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| {
+                loop {
+                    let index = next_index.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    let result = f(item);
+                    results.lock().unwrap()[index] = Some(result);
+                }
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|result| result.expect("every item is processed"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_preserves_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let results = map(&items, NonZeroUsize::new(8).unwrap(), |item| {
+            thread::sleep(std::time::Duration::from_millis(50 - item));
+            item * 2
+        });
+        assert_eq!(
+            results,
+            items.iter().map(|item| item * 2).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_map_single_job() {
+        assert_eq!(
+            map(&[1, 2, 3], NonZeroUsize::MIN, |item| item + 1),
+            vec![2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn test_map_empty() {
+        assert_eq!(
+            map(&[] as &[u8], NonZeroUsize::new(4).unwrap(), |item| *item),
+            vec![]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, bail};
+
+use crate::soulver;
+
+/// Statistics of how long runs of a sheet took.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub runs: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    /// The 95th percentile, using the nearest rank
+    pub p95: Duration,
+}
+
+impl Summary {
+    /// Summarise the durations of at least one run.
+    pub fn new(mut durations: Vec<Duration>) -> Self {
+        assert!(!durations.is_empty(), "at least one run is summarised");
+        durations.sort_unstable();
+        let runs = durations.len();
+        let mean = durations.iter().sum::<Duration>() / runs as u32;
+        let median = if runs.is_multiple_of(2) {
+            (durations[runs / 2 - 1] + durations[runs / 2]) / 2
+        } else {
+            durations[runs / 2]
+        };
+        let p95 = durations[(runs * 95).div_ceil(100) - 1];
+        Self {
+            runs,
+            mean,
+            median,
+            p95,
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mean {:.1?}, median {:.1?}, p95 {:.1?} ({} runs)",
+            self.mean, self.median, self.p95, self.runs,
+        )
+    }
+}
+
+/// Run a `soulver` executable with a sheet `runs` times, timing each run.
+pub fn bench(program: &Path, sheet: &str, runs: usize) -> Result<Summary> {
+    let sheet = soulver::soulver_input(&[], sheet);
+    let mut durations = Vec::with_capacity(runs);
+    for _ in 0..runs {
+        let start = Instant::now();
+        let output = Command::new(program)
+            .arg(&sheet)
+            .output()
+            .with_context(|| format!("failed to run {}", program.display()))?;
+        durations.push(start.elapsed());
+        if !output.status.success() {
+            bail!("{} exited with {}", program.display(), output.status);
+        }
+    }
+    Ok(Summary::new(durations))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let durations = (1..=20).map(Duration::from_millis).collect();
+        assert_eq!(
+            Summary::new(durations),
+            Summary {
+                runs: 20,
+                mean: Duration::from_micros(10_500),
+                median: Duration::from_micros(10_500),
+                p95: Duration::from_millis(19),
+            },
+        );
+    }
+
+    #[test]
+    fn test_summary_single_run() {
+        let summary = Summary::new(vec![Duration::from_millis(5)]);
+        assert_eq!(
+            (summary.mean, summary.median, summary.p95),
+            (
+                Duration::from_millis(5),
+                Duration::from_millis(5),
+                Duration::from_millis(5),
+            ),
+        );
+        assert_eq!(
+            summary.to_string(),
+            "mean 5.0ms, median 5.0ms, p95 5.0ms (1 runs)"
+        );
+    }
+}
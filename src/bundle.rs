@@ -0,0 +1,149 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::soulver::Line;
+use crate::{files, json};
+
+/// The extension of Soulver 3 `.sheet` bundles, which are directories containing JSON documents.
+pub const EXTENSION: &str = "sheet";
+
+/// The JSON document `export` writes a sheet to in a bundle.
+const DOCUMENT_NAME: &str = "Contents.json";
+
+/// Whether a path is a `.sheet` bundle.
+pub fn is_bundle(path: &Path) -> bool {
+    path.is_dir()
+        && path
+            .extension()
+            .is_some_and(|extension| extension == EXTENSION)
+}
+
+/// Find the text of a sheet in a JSON document of a bundle, either as a `text`, `plainText`, or
+/// `content` string, or a `lines` array of strings or objects with a `text` or `expression`.
+fn extract_text(value: &json::Value) -> Option<String> {
+    for key in ["text", "plainText", "content"] {
+        if let Some(json::Value::String(text)) = value.get(key) {
+            return Some(text.clone());
+        }
+    }
+    let Some(json::Value::Array(lines)) = value.get("lines") else {
+        return None;
+    };
+    lines
+        .iter()
+        .map(|line| match line {
+            json::Value::String(text) => Some(text.as_str()),
+            line => ["text", "expression"]
+                .iter()
+                .find_map(|key| match line.get(key) {
+                    Some(json::Value::String(text)) => Some(text.as_str()),
+                    _ => None,
+                }),
+        })
+        .collect::<Option<Vec<&str>>>()
+        .map(|lines| lines.join("\n"))
+}
+
+/// Read the plain text of a `.sheet` bundle from the first of its JSON documents, sorted by name,
+/// that contains a sheet.
+pub fn read_text(path: &Path) -> Result<String> {
+    let mut documents = fs::read_dir(path)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    documents.retain(|document| {
+        document
+            .extension()
+            .is_some_and(|extension| extension == "json")
+    });
+    documents.sort();
+    for document in documents {
+        let text = fs::read_to_string(&document)?;
+        let value = json::parse(&text)
+            .with_context(|| format!("invalid JSON in {}", document.display()))?;
+        if let Some(text) = extract_text(&value) {
+            return Ok(text);
+        }
+    }
+    bail!("{} does not contain a sheet", path.display());
+}
+
+/// The JSON document of a calculated sheet, with its text and each line's answer.
+fn document(lines: &[Line]) -> json::Value {
+    let text: Vec<&str> = lines.iter().map(|line| line.input.as_str()).collect();
+    let lines: Vec<json::Value> = lines
+        .iter()
+        .map(|line| {
+            json::Value::object([
+                ("expression", line.input.as_str()),
+                ("answer", line.output.as_str()),
+            ])
+        })
+        .collect();
+    json::Value::object([
+        ("text", json::Value::from(text.join("\n"))),
+        ("lines", json::Value::from(lines)),
+    ])
+}
+
+/// Write a calculated sheet to a `.sheet` bundle, creating it if it doesn't exist.
+pub fn write(path: &Path, lines: &[Line]) -> Result<()> {
+    if path.exists() && !is_bundle(path) {
+        bail!("{} exists and is not a .{EXTENSION} bundle", path.display());
+    }
+    fs::create_dir_all(path).with_context(|| format!("failed to create {}", path.display()))?;
+    files::write_atomic(&path.join(DOCUMENT_NAME), format!("{}\n", document(lines)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_text() {
+        let text = |json| extract_text(&json::parse(json).unwrap());
+        assert_eq!(
+            text(r#"{"version": 1, "text": "Rent = £1000\nRent * 12"}"#),
+            Some("Rent = £1000\nRent * 12".to_owned()),
+        );
+        assert_eq!(
+            text(r##"{"lines": [{"expression": "1 + 2", "answer": "3"}, "# Notes"]}"##),
+            Some("1 + 2\n# Notes".to_owned()),
+        );
+        assert_eq!(text(r#"{"lines": [1]}"#), None);
+        assert_eq!(text(r#"{"metadata": {}}"#), None);
+    }
+
+    #[test]
+    fn test_document() {
+        let lines = [
+            Line {
+                input: "# Rent".to_owned(),
+                output: String::new(),
+            },
+            Line {
+                input: "£1000 * 12".to_owned(),
+                output: "£12,000.00".to_owned(),
+            },
+        ];
+        let document = document(&lines);
+        assert_eq!(extract_text(&document).unwrap(), "# Rent\n£1000 * 12");
+        assert_eq!(
+            document.to_string(),
+            r##"{"text":"# Rent\n£1000 * 12","lines":[{"expression":"# Rent","answer":""},{"expression":"£1000 * 12","answer":"£12,000.00"}]}"##,
+        );
+    }
+
+    #[test]
+    fn test_read_text() {
+        let directory =
+            std::env::temp_dir().join(format!("soulver-bundle-{}.sheet", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("a-metadata.json"), r#"{"created": 0}"#).unwrap();
+        fs::write(directory.join("b-sheet.json"), r#"{"text": "1 + 2"}"#).unwrap();
+        assert!(is_bundle(&directory));
+        assert_eq!(read_text(&directory).unwrap(), "1 + 2");
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
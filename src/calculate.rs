@@ -0,0 +1,1393 @@
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::iter;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+
+use anyhow::{Context, Result, bail};
+use clap::{Args, ValueEnum};
+
+use crate::config::Config;
+use crate::line_range::LineRange;
+use crate::locale::Locale;
+use crate::profile::{self, Phase};
+use crate::quantity::{Quantity, RoundingMode};
+use crate::query::{self, Query};
+use crate::rates::Rates;
+use crate::regex::Regex;
+use crate::soulver::Line;
+use crate::template::Template;
+use crate::{
+    SheetArgs, accessible, check, diagnostics, files, history, hooks, json, latex, library, live,
+    output, plugin, report_sheet_error, sanitize, soulver, totals, vars, xlsx,
+};
+
+#[derive(Args)]
+pub struct CalculateArgs {
+    #[command(flatten)]
+    sheets: SheetArgs,
+
+    /// Calculate the sheet with a name in the Soulver library, or the `library` directory set in
+    /// the config file, e.g. `--sheet-name "Monthly Budget"`
+    #[arg(long = "sheet-name", value_name = "NAME", conflicts_with = "stdin0")]
+    sheet_names: Vec<String>,
+
+    /// Do not add the input to the output
+    #[arg(long)]
+    no_zip: bool,
+
+    /// Write each sheet's output to its path mirrored under a directory with a `.txt`
+    /// extension added, instead of stdout
+    #[arg(long, requires = "paths")]
+    output_dir: Option<PathBuf>,
+
+    /// Write the output to a file instead of stdout, replacing it atomically
+    #[arg(
+        short,
+        long,
+        conflicts_with = "output_dir",
+        required_if_eq("format", "xlsx")
+    )]
+    output: Option<PathBuf>,
+
+    /// Annotate each line of each sheet with its result as `line => result`, atomically, replacing
+    /// any annotations from before so the sheet can be calculated again
+    #[arg(
+        short,
+        long,
+        requires = "paths",
+        conflicts_with_all = ["output_dir", "output", "no_zip", "answer", "total", "subtotals", "skip_empty_results", "filter", "exclude", "sort_by", "line", "query", "labels", "range", "format"]
+    )]
+    in_place: bool,
+
+    /// Save a copy of each sheet with a suffix added before replacing it
+    #[arg(
+        long,
+        value_name = "SUFFIX",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = ".bak",
+        requires = "in_place"
+    )]
+    backup: Option<String>,
+
+    /// Calculate each document separated by a `---` line independently
+    #[arg(long)]
+    multi_document: bool,
+
+    /// Terminate each sheet's output with a NUL instead of separating sheets with headers
+    #[arg(long)]
+    print0: bool,
+
+    /// Only output the last non-empty result of each sheet
+    #[arg(long, conflicts_with = "no_zip")]
+    answer: bool,
+
+    /// Append a row with the total of the results in the most common unit or currency
+    #[arg(long, conflicts_with_all = ["no_zip", "answer"])]
+    total: bool,
+
+    /// Insert a row with the subtotal of each `#` heading's section, and append the total
+    #[arg(long, conflicts_with_all = ["no_zip", "answer", "total"])]
+    subtotals: bool,
+
+    /// Omit rows without a result, like headings, comments, and blank lines, from the output
+    #[arg(long, conflicts_with_all = ["no_zip", "answer"])]
+    skip_empty_results: bool,
+
+    /// Keep the sheet's trailing blank lines in the output with empty results, e.g. to use as an
+    /// editor's filter command
+    #[arg(long, conflicts_with_all = ["answer", "skip_empty_results", "line", "range"])]
+    keep_trailing: bool,
+
+    /// Only output rows whose input matches a regex, while still calculating the whole sheet
+    #[arg(long, value_name = "REGEX", conflicts_with_all = ["no_zip", "answer"])]
+    filter: Vec<Regex>,
+
+    /// Omit rows whose input matches a regex from the output, while still calculating them
+    #[arg(long, value_name = "REGEX", conflicts_with_all = ["no_zip", "answer"])]
+    exclude: Vec<Regex>,
+
+    /// Sort rows by the value of their numeric results with `result`, or `result:desc` for largest
+    /// first. Rows without a numeric result are kept in order after them
+    #[arg(long, value_name = "KEY", conflicts_with_all = ["no_zip", "answer", "subtotals"])]
+    sort_by: Option<SortBy>,
+
+    /// Only output the results of these lines, e.g. `3` or `5-7`, while still calculating the whole
+    /// sheet
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["no_zip", "answer", "total", "subtotals", "skip_empty_results", "filter", "exclude", "sort_by"]
+    )]
+    line: Vec<LineRange>,
+
+    /// Output the results of a `jq`-like query over the rows, e.g.
+    /// `.[] | select(.output != "") | .output`, where each row is an object with its `line` number,
+    /// `input`, and `output`
+    #[arg(
+        long,
+        value_name = "QUERY",
+        conflicts_with_all = ["no_zip", "answer", "line", "total", "subtotals", "skip_empty_results", "keep_trailing", "filter", "exclude", "sort_by"]
+    )]
+    query: Option<Query>,
+
+    /// Output a `label = result` row for each `Label: expression` line with a result, or a JSON
+    /// object of the results by label with `--labels=json`
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "text",
+        conflicts_with_all = ["no_zip", "answer", "line", "query", "total", "subtotals", "skip_empty_results", "keep_trailing", "filter", "exclude", "sort_by"]
+    )]
+    labels: Option<LabelsFormat>,
+
+    /// Only calculate a range of lines, e.g. `20-35`, along with the earlier variable assignments
+    /// they depend on without including those in the output
+    #[arg(long = "lines", value_name = "RANGE", conflicts_with_all = ["no_zip", "line"])]
+    range: Option<LineRange>,
+
+    /// Assign a variable before calculating the sheet without including it in the output, e.g.
+    /// `--define rate=4.5%`
+    #[arg(long = "define", value_name = "NAME=VALUE")]
+    definitions: Vec<vars::Definition>,
+
+    /// Calculate a sheet before each sheet without including it in the output, e.g. to share
+    /// variables between sheets, in addition to any in the config file's `include` key
+    #[arg(long, value_name = "SHEET")]
+    include: Vec<PathBuf>,
+
+    /// Convert currencies with the exchange rates in a snapshot from `rates fetch` instead of the
+    /// latest rates
+    #[arg(long, value_name = "FILE")]
+    rates: Option<PathBuf>,
+
+    /// Substitute `{{NAME}}` placeholders with the values of environment variables before
+    /// calculating
+    #[arg(long)]
+    template: bool,
+
+    /// Read the values of placeholders from a JSON object or TOML file before environment
+    /// variables
+    #[arg(long, value_name = "FILE", requires = "template")]
+    template_values: Option<PathBuf>,
+
+    /// Round numeric results to a number of decimal places in the output, without changing the
+    /// precision they are calculated with
+    #[arg(long, value_name = "N")]
+    round: Option<usize>,
+
+    /// How `--round` rounds results
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "half-away-from-zero",
+        requires = "round"
+    )]
+    round_mode: RoundingMode,
+
+    /// Output numeric results as plain numbers without currency symbols, units, or digit grouping
+    #[arg(long, conflicts_with = "locale")]
+    numeric: bool,
+
+    /// Always or never group the digits of numeric results, e.g. `1,234`, whichever way `soulver`
+    /// output them
+    #[arg(long, value_enum, value_name = "WHEN", conflicts_with = "numeric")]
+    group_digits: Option<Toggle>,
+
+    /// Read and write numbers the way a locale does, e.g. `de_DE` for `1.234,5`
+    #[arg(long)]
+    locale: Option<Locale>,
+
+    /// Mark the results of lines relying on live data, like currency conversions and stocks, with
+    /// `[live]`, or fail before calculating with `--offline=fail`
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "mark"
+    )]
+    offline: Option<OfflineMode>,
+
+    /// Show `⚠ error` as the result of each line that should have a result but does not, instead
+    /// of leaving it empty
+    #[arg(long, conflicts_with_all = ["answer", "labels"])]
+    mark_errors: bool,
+
+    /// If `soulver` fails to calculate a sheet, calculate the rest of it without each line it fails
+    /// on, and show `⚠ error` as those lines' results
+    #[arg(long)]
+    partial: bool,
+
+    /// Fail if any line that should have a result does not, or, with `--offline`, if any line
+    /// relies on live data, e.g. for checking sheets in CI
+    #[arg(long, conflicts_with = "partial")]
+    strict: bool,
+
+    /// Output each line's result as soon as the line is read from stdin
+    #[arg(
+        long,
+        conflicts_with_all = ["paths", "sheet_names", "stdin0", "multi_document", "print0", "answer", "line", "query", "labels", "keep_trailing", "range", "output_dir", "in_place", "total", "subtotals", "filter", "exclude", "sort_by"]
+    )]
+    stream: bool,
+
+    /// Output each line's result as soon as the line is appended to a file or named pipe, like
+    /// `tail -f`, e.g. for a log of measurements
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = ["paths", "sheet_names", "stdin0", "multi_document", "print0", "answer", "line", "query", "labels", "keep_trailing", "range", "output_dir", "in_place", "stream", "filter_mode", "dry_run", "format", "total", "subtotals", "filter", "exclude", "sort_by"]
+    )]
+    follow: Option<PathBuf>,
+
+    /// Calculate stdin as an editor's filter command, e.g. `:%!soulver-cli-zipper calculate
+    /// --filter-mode` in Vim, always outputting one line per input line and ending each line the
+    /// way stdin does. If the sheet cannot be calculated, each line that should have a result gets
+    /// `error`
+    #[arg(
+        long,
+        conflicts_with_all = ["paths", "sheet_names", "stdin0", "multi_document", "print0", "answer", "line", "query", "labels", "skip_empty_results", "filter", "exclude", "sort_by", "total", "subtotals", "range", "output_dir", "output", "in_place", "stream"]
+    )]
+    filter_mode: bool,
+
+    /// Output the exact sheet each sheet would be calculated as by `soulver`, after any includes,
+    /// definitions, templates, and dates are applied, without running it
+    #[arg(long, conflicts_with_all = ["stream", "filter_mode", "output_dir", "in_place"])]
+    dry_run: bool,
+
+    /// Output the sheets in another format instead of zipping the input with the results, e.g.
+    /// `--format xlsx --output out.xlsx` for a workbook of each sheet's inputs and results,
+    /// `--format latex` for a table to include in a document, `--format oneline` for a status line,
+    /// `--format waybar` for a status bar module, or `--format accessible` for sentences that read
+    /// better with a screen reader than aligned columns, or `--format vscode-diagnostics` for LSP
+    /// diagnostics of the lines without results. Other formats are output by the
+    /// `soulver-zipper-format-NAME` executable in `PATH`, given a JSON array of the rows on stdin
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        conflicts_with_all = ["no_zip", "multi_document", "print0", "answer", "line", "query", "labels", "stream", "filter_mode", "dry_run", "output_dir", "in_place"]
+    )]
+    format: Option<OutputFormat>,
+
+    /// The line `--format oneline` outputs, with `{{heading}}` and `{{answer}}` placeholders for
+    /// the last heading and result, instead of `heading: answer`
+    #[arg(long, value_name = "TEMPLATE", requires = "format")]
+    oneline_template: Option<String>,
+
+    /// Calculate with a `daemon --socket PATH` on this machine instead of starting `soulver` for
+    /// every sheet, e.g. from an editor plugin
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["remote", "remote_daemon"])]
+    socket: Option<PathBuf>,
+}
+
+/// An option that can be forced on or off.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Toggle {
+    On,
+    Off,
+}
+
+/// How `--sort-by` orders rows, parsed from `result`, `result:asc`, or `result:desc`.
+#[derive(Clone, Copy)]
+struct SortBy {
+    descending: bool,
+}
+
+impl FromStr for SortBy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (key, order) = s.split_once(':').unwrap_or((s, "asc"));
+        if key != "result" {
+            bail!("rows can only be sorted by `result`, not {key:?}");
+        }
+        let descending = match order {
+            "asc" => false,
+            "desc" => true,
+            _ => bail!("expected `asc` or `desc` after `result:`, not {order:?}"),
+        };
+        Ok(Self { descending })
+    }
+}
+
+/// What `--format` outputs the sheets as.
+#[derive(Clone, PartialEq, Eq)]
+enum OutputFormat {
+    /// An Excel workbook with a worksheet of each sheet's inputs and results
+    Xlsx,
+    /// A LaTeX `tabular` of inputs and results, using `booktabs` rules
+    Latex,
+    /// The last heading and the last result on one line, e.g. for a status line
+    Oneline,
+    /// A Waybar or Polybar JSON module with the last result as its text and the zipped sheet as
+    /// its tooltip
+    Waybar,
+    /// A sentence for each line, e.g. `line 3: "Foo + 2" equals 3`, for screen readers
+    Accessible,
+    /// A JSON array of LSP diagnostics for the lines that should have a result but do not
+    VscodeDiagnostics,
+    /// The output of a `soulver-zipper-format-NAME` executable
+    Plugin(String),
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "xlsx" => Self::Xlsx,
+            "latex" => Self::Latex,
+            "oneline" => Self::Oneline,
+            "waybar" => Self::Waybar,
+            "accessible" => Self::Accessible,
+            "vscode-diagnostics" => Self::VscodeDiagnostics,
+            "" => bail!("the format cannot be empty"),
+            _ => Self::Plugin(s.to_owned()),
+        })
+    }
+}
+
+/// How `--labels` outputs the results of labelled lines.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum LabelsFormat {
+    Text,
+    Json,
+}
+
+/// What `--offline` does with lines relying on live data.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OfflineMode {
+    /// Add [`LIVE_MARKER`] to their results
+    Mark,
+    /// Fail before calculating the sheet
+    Fail,
+}
+
+/// Added to the results of lines relying on live data with `--offline`.
+const LIVE_MARKER: &str = " [live]";
+
+/// The number of blank lines at the end of a document, which `soulver` does not output.
+fn trailing_blank_lines(document: &str) -> usize {
+    document
+        .lines()
+        .rev()
+        .take_while(|line| line.trim().is_empty())
+        .count()
+}
+
+/// The last non-empty result of a sheet.
+fn last_answer(lines: &[Line]) -> &str {
+    lines
+        .iter()
+        .rev()
+        .map(|line| line.output.as_str())
+        .find(|output| !output.is_empty())
+        .unwrap_or_default()
+}
+
+fn mark_live(output: &mut String) {
+    if !output.is_empty() {
+        output.push_str(LIVE_MARKER);
+    }
+}
+
+/// The sheet a document is in and the line of the sheet it starts at, for reporting its lines.
+#[derive(Clone, Copy)]
+struct Origin<'a> {
+    sheet: &'a str,
+    first_line_number: usize,
+}
+
+impl<'a> Origin<'a> {
+    fn new(sheet: &'a str) -> Self {
+        Self {
+            sheet,
+            first_line_number: 1,
+        }
+    }
+}
+
+/// The line numbers and a verb agreeing with them, e.g. `line 3 has` or `lines 3, 5 have`.
+fn lines_with_verb(line_numbers: &[usize], singular: &str, plural: &str) -> String {
+    match line_numbers {
+        [line_number] => format!("line {line_number} {singular}"),
+        _ => {
+            let line_numbers: Vec<String> = line_numbers.iter().map(usize::to_string).collect();
+            format!("lines {} {plural}", line_numbers.join(", "))
+        }
+    }
+}
+
+/// Report the lines that should have a result but do not with `--error-format`.
+fn report_failed_lines(lines: &[Line], origin: Origin) {
+    if !output::reports_line_errors() {
+        return;
+    }
+    for (index, line) in lines.iter().enumerate() {
+        if line.output.is_empty() && soulver::expects_result(&line.input) {
+            output::log_line_error(
+                origin.sheet,
+                origin.first_line_number + index,
+                &format!("no result for {:?}", line.input.trim()),
+            );
+        }
+    }
+}
+
+/// A calculated sheet, either as text or as rows to format.
+enum Output {
+    Text(String),
+    Rows(Vec<Line>),
+}
+
+/// Where `calculate` writes its output.
+enum Destination<'a> {
+    Stdout,
+    File(&'a Path),
+    /// Each sheet's output is written to its path mirrored under a directory
+    Directory(&'a Path),
+    /// Each sheet is replaced with its annotated lines, optionally backing it up with a suffix first
+    InPlace {
+        backup: Option<&'a str>,
+    },
+}
+
+impl Destination<'_> {
+    /// Write a sheet's output if it is written per sheet, returning whether it was.
+    fn write_sheet(&self, sheet: &files::Sheet, result: &str) -> Result<bool> {
+        let path = sheet.path.as_deref().unwrap_or(Path::new("stdin"));
+        match self {
+            Self::Stdout | Self::File(_) => return Ok(false),
+            Self::Directory(directory) => {
+                let path = files::mirrored_path(directory, path, ".txt");
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                files::write_atomic(&path, format!("{result}\n"))?;
+            }
+            Self::InPlace { backup } => {
+                if let Some(suffix) = backup {
+                    let mut backup_path = path.as_os_str().to_owned();
+                    backup_path.push(suffix);
+                    fs::copy(path, backup_path)?;
+                }
+                files::write_atomic(path, format!("{result}\n"))?;
+            }
+        }
+        Ok(true)
+    }
+}
+
+impl CalculateArgs {
+    pub fn socket(&self) -> Option<&Path> {
+        self.socket.as_deref()
+    }
+
+    fn destination(&self) -> Destination<'_> {
+        if self.in_place {
+            Destination::InPlace {
+                backup: self.backup.as_deref(),
+            }
+        } else if let Some(output_dir) = &self.output_dir {
+            Destination::Directory(output_dir)
+        } else if let Some(output) = &self.output {
+            Destination::File(output)
+        } else {
+            Destination::Stdout
+        }
+    }
+
+    /// Whether the output keeps the sheet's trailing blank lines.
+    fn keeps_trailing(&self) -> bool {
+        self.keep_trailing || self.filter_mode
+    }
+
+    /// Calculate a sheet named `name` after the lines of `prelude` and format its output.
+    fn calculate_sheet(&self, prelude: &[&str], sheet: &str, name: &str) -> Result<String> {
+        if self.multi_document {
+            let documents = soulver::split_documents(sheet)
+                .into_iter()
+                .map(|document| {
+                    // Each document is a slice of the sheet, so its lines are numbered from where
+                    // it starts in the sheet
+                    let start = document.as_ptr() as usize - sheet.as_ptr() as usize;
+                    let origin = Origin {
+                        sheet: name,
+                        first_line_number: sheet[..start].matches('\n').count() + 1,
+                    };
+                    self.calculate_document(prelude, document, origin)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(documents.join(&format!("\n{}\n", soulver::DOCUMENT_SEPARATOR)));
+        }
+        self.calculate_document(prelude, sheet, Origin::new(name))
+    }
+
+    /// The values to substitute into each sheet, if it is a template.
+    fn template(&self) -> Result<Option<Template>> {
+        if !self.template {
+            return Ok(None);
+        }
+        match &self.template_values {
+            Some(path) => Template::load(path).map(Some),
+            None => Ok(Some(Template::default())),
+        }
+    }
+
+    /// The lines calculated before each sheet without being included in the output: the exchange
+    /// rates, the included sheets, then the definitions.
+    fn prelude(&self, config: &Config) -> Result<Vec<String>> {
+        let mut prelude = Vec::new();
+        if let Some(path) = &self.rates {
+            let rates = fs::read_to_string(path)
+                .with_context(|| format!("failed to read rates {}", path.display()))?;
+            let rates = Rates::parse(&rates)
+                .with_context(|| format!("invalid rates {}", path.display()))?;
+            prelude.extend(rates.to_lines());
+        }
+        for path in config.include.iter().chain(&self.include) {
+            let sheet = fs::read_to_string(path)
+                .with_context(|| format!("failed to read included sheet {}", path.display()))?;
+            prelude.extend(sheet.lines().map(str::to_owned));
+        }
+        prelude.extend(self.definitions.iter().map(vars::Definition::to_line));
+        Ok(prelude)
+    }
+
+    /// Calculate a sheet after the lines of `prelude`, or as much of it as possible with
+    /// `--partial`, and transform its lines with the post hook, so every output sees them.
+    fn calculate_lines(&self, prelude: &[&str], sheet: &str) -> Result<Vec<Line>> {
+        let lines = if self.partial {
+            soulver::calculate_partially(sheet, |sheet| {
+                self.calculate_localized_lines(prelude, sheet)
+            })?
+        } else {
+            self.calculate_localized_lines(prelude, sheet)?
+        };
+        hooks::post(lines)
+    }
+
+    /// Calculate a sheet after the lines of `prelude`, reading numbers in the sheet the way
+    /// `--locale` writes them.
+    fn calculate_localized_lines(&self, prelude: &[&str], sheet: &str) -> Result<Vec<Line>> {
+        let Some(locale) = self.locale else {
+            return soulver::calculate_lines_with_prelude(prelude, sheet);
+        };
+        let (prelude, delocalized) = profile::time(Phase::Preprocess, || {
+            let prelude: Vec<String> = prelude.iter().map(|line| locale.delocalize(line)).collect();
+            (prelude, locale.delocalize(sheet))
+        });
+        let prelude: Vec<&str> = prelude.iter().map(String::as_str).collect();
+        let mut lines = soulver::calculate_lines_with_prelude(&prelude, &delocalized)?;
+        for (line, input) in lines.iter_mut().zip(sanitize::sheet(sheet).lines()) {
+            line.input = input.to_owned();
+        }
+        Ok(lines)
+    }
+
+    /// The sheet [`Self::calculate_sheet`] would run `soulver` with for `--dry-run`.
+    fn soulver_input(&self, prelude: &[&str], sheet: &str) -> Result<String> {
+        if self.multi_document {
+            let documents = soulver::split_documents(sheet)
+                .into_iter()
+                .map(|document| self.document_soulver_input(prelude, document))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(documents.join(&format!("\n{}\n", soulver::DOCUMENT_SEPARATOR)));
+        }
+        self.document_soulver_input(prelude, sheet)
+    }
+
+    fn document_soulver_input(&self, prelude: &[&str], document: &str) -> Result<String> {
+        let mut prelude = prelude.to_vec();
+        let document_lines: Vec<&str> = document.lines().collect();
+        let body = match self.range {
+            Some(range) if range.end > document_lines.len() => bail!(
+                "line {range} is out of range (the sheet has {} lines)",
+                document_lines.len(),
+            ),
+            Some(range) => {
+                prelude.extend(
+                    vars::dependencies(&document_lines, range)
+                        .into_iter()
+                        .map(|index| document_lines[index]),
+                );
+                document_lines[range.start - 1..range.end].join("\n")
+            }
+            None => document.to_owned(),
+        };
+        Ok(match self.locale {
+            Some(locale) => {
+                let prelude: Vec<String> =
+                    prelude.iter().map(|line| locale.delocalize(line)).collect();
+                let prelude: Vec<&str> = prelude.iter().map(String::as_str).collect();
+                soulver::soulver_input(&prelude, &locale.delocalize(&body))
+            }
+            None => soulver::soulver_input(&prelude, &body),
+        })
+    }
+
+    /// Report the lines that should have a result but do not, then fail with `--strict` or show
+    /// [`soulver::ERROR_MARKER`] as their results with `--mark-errors`.
+    fn check_failed_lines(&self, lines: &mut [Line], origin: Origin) -> Result<()> {
+        report_failed_lines(lines, origin);
+        let failed: Vec<usize> = lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.output.is_empty() && soulver::expects_result(&line.input))
+            .map(|(index, _)| index)
+            .collect();
+        if self.strict && !failed.is_empty() {
+            let line_numbers: Vec<usize> = failed
+                .iter()
+                .map(|index| origin.first_line_number + index)
+                .collect();
+            bail!(
+                "{} no result",
+                lines_with_verb(&line_numbers, "has", "have")
+            );
+        }
+        if self.mark_errors {
+            for index in failed {
+                lines[index].output = soulver::ERROR_MARKER.to_owned();
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether numeric results are reformatted by [`Self::format_result`].
+    fn reformats_numbers(&self) -> bool {
+        self.round.is_some() || self.numeric || self.group_digits.is_some()
+    }
+
+    /// Format a result for the output, rounding it with `--round`, making it plain with
+    /// `--numeric`, grouping its digits with `--group-digits`, and writing its numbers the way
+    /// `--locale` does.
+    fn format_result(&self, output: String) -> String {
+        let output = match Quantity::parse(&output) {
+            Some(mut quantity) if self.reformats_numbers() => {
+                if let Some(decimals) = self.round {
+                    quantity = quantity.round(decimals, self.round_mode);
+                }
+                if self.numeric {
+                    quantity = quantity.plain();
+                }
+                if let Some(group_digits) = self.group_digits {
+                    quantity.grouped = group_digits == Toggle::On;
+                }
+                quantity.to_string()
+            }
+            _ => output,
+        };
+        match self.locale {
+            Some(locale) => locale.localize(&output),
+            None => output,
+        }
+    }
+
+    /// Whether a row is output with `--filter` and `--exclude`.
+    fn shows(&self, input: &str) -> bool {
+        (self.filter.is_empty() || self.filter.iter().any(|regex| regex.is_match(input)))
+            && !self.exclude.iter().any(|regex| regex.is_match(input))
+    }
+
+    /// Filter rows with `--filter` and `--exclude` and sort them with `--sort-by`, returning the
+    /// rows along with the new indices of the live rows.
+    fn arrange_rows(&self, lines: Vec<Line>, live: &[usize]) -> (Vec<Line>, Vec<usize>) {
+        let mut rows: Vec<(Line, bool)> = lines
+            .into_iter()
+            .enumerate()
+            .filter(|(_, line)| self.shows(&line.input))
+            .map(|(index, line)| (line, live.contains(&index)))
+            .collect();
+        if let Some(sort_by) = self.sort_by {
+            // Rows without a numeric result stay in order after the sorted rows
+            let value = |line: &Line| Quantity::parse(&line.output).map(|quantity| quantity.value);
+            rows.sort_by(|(a, _), (b, _)| match (value(a), value(b)) {
+                (Some(a), Some(b)) if sort_by.descending => b.total_cmp(&a),
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            });
+        }
+        let live = rows
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, is_live))| *is_live)
+            .map(|(index, _)| index)
+            .collect();
+        (rows.into_iter().map(|(line, _)| line).collect(), live)
+    }
+
+    /// The indices of the lines of `lines` relying on live data with `--offline`, failing if
+    /// there are any with `--offline=fail` or `--strict`.
+    fn live_lines(&self, lines: &[&str]) -> Result<Vec<usize>> {
+        let Some(mode) = self.offline else {
+            return Ok(Vec::new());
+        };
+        let live = live::live_lines(lines, self.rates.is_some());
+        if (mode == OfflineMode::Fail || self.strict) && !live.is_empty() {
+            let line_numbers: Vec<usize> = live.iter().map(|index| index + 1).collect();
+            bail!(
+                "{} on live data",
+                lines_with_verb(&line_numbers, "relies", "rely"),
+            );
+        }
+        Ok(live)
+    }
+
+    fn calculate_document(
+        &self,
+        prelude: &[&str],
+        document: &str,
+        origin: Origin,
+    ) -> Result<String> {
+        Ok(match self.calculate_output(prelude, document, origin)? {
+            Output::Text(text) => text,
+            Output::Rows(lines) => match &self.format {
+                Some(OutputFormat::Latex) => latex::table(&lines),
+                Some(OutputFormat::Oneline) => self.oneline(&lines)?,
+                Some(OutputFormat::Waybar) => json::Value::object([
+                    ("text", json::Value::from(last_answer(&lines))),
+                    ("tooltip", json::Value::from(soulver::zip(&lines))),
+                ])
+                .to_string(),
+                Some(OutputFormat::Accessible) => accessible::sentences(&lines),
+                Some(OutputFormat::VscodeDiagnostics) => {
+                    diagnostics::diagnostics(&lines, self.range.map_or(1, |range| range.start))
+                        .to_string()
+                }
+                Some(OutputFormat::Plugin(name)) => plugin::format(name, &lines)?,
+                Some(OutputFormat::Xlsx) | None if self.in_place => check::annotate(&lines),
+                Some(OutputFormat::Xlsx) | None => soulver::zip(&lines),
+            },
+        })
+    }
+
+    /// Collapse a sheet to its last heading and result with `--format oneline`.
+    fn oneline(&self, lines: &[Line]) -> Result<String> {
+        let heading = lines.iter().rev().find_map(totals::heading);
+        let answer = last_answer(lines);
+        let Some(template) = &self.oneline_template else {
+            return Ok(match heading {
+                Some(heading) => format!("{heading}: {answer}"),
+                None => answer.to_owned(),
+            });
+        };
+        Template::new(vec![
+            ("heading".to_owned(), heading.unwrap_or_default().to_owned()),
+            ("answer".to_owned(), answer.to_owned()),
+        ])
+        .render(template)
+    }
+
+    /// Calculate a sheet as its rows of inputs and results, for outputs that are not text.
+    fn calculate_rows(&self, prelude: &[&str], sheet: &str, name: &str) -> Result<Vec<Line>> {
+        match self.calculate_output(prelude, sheet, Origin::new(name))? {
+            Output::Rows(lines) => Ok(lines),
+            Output::Text(_) => bail!("the output is not rows of inputs and results"),
+        }
+    }
+
+    fn calculate_output(&self, prelude: &[&str], document: &str, origin: Origin) -> Result<Output> {
+        let mut prelude = prelude.to_vec();
+        let document_lines: Vec<&str> = document.lines().collect();
+        let mut live = self.live_lines(&document_lines)?;
+        if self.no_zip {
+            if prelude.is_empty()
+                && self.locale.is_none()
+                && !self.reformats_numbers()
+                && live.is_empty()
+                && !self.keeps_trailing()
+                && !output::reports_line_errors()
+                && !self.mark_errors
+                && !self.partial
+                && !self.strict
+                && !hooks::has_post()
+            {
+                return soulver::run_soulver(document).map(Output::Text);
+            }
+            let mut lines = self.calculate_lines(&prelude, document)?;
+            self.check_failed_lines(&mut lines, origin)?;
+            let mut outputs: Vec<String> = lines
+                .into_iter()
+                .enumerate()
+                .map(|(index, line)| {
+                    let mut output = self.format_result(line.output);
+                    if live.contains(&index) {
+                        mark_live(&mut output);
+                    }
+                    output
+                })
+                .collect();
+            if self.keeps_trailing() {
+                outputs.resize(
+                    outputs.len() + trailing_blank_lines(document),
+                    String::new(),
+                );
+            }
+            return Ok(Output::Text(outputs.join("\n")));
+        }
+
+        let first_line_number = self.range.map_or(1, |range| range.start);
+        let mut lines = match self.range {
+            Some(range) => {
+                if range.end > document_lines.len() {
+                    bail!(
+                        "line {range} is out of range (the sheet has {} lines)",
+                        document_lines.len(),
+                    );
+                }
+                prelude.extend(
+                    vars::dependencies(&document_lines, range)
+                        .into_iter()
+                        .map(|index| document_lines[index]),
+                );
+                let body = document_lines[range.start - 1..range.end].join("\n");
+                live = live
+                    .into_iter()
+                    .filter(|index| range.contains(index + 1))
+                    .map(|index| index + 1 - range.start)
+                    .collect();
+                self.calculate_lines(&prelude, &body)?
+            }
+            None => self.calculate_lines(&prelude, document)?,
+        };
+        self.check_failed_lines(
+            &mut lines,
+            Origin {
+                first_line_number: origin.first_line_number + first_line_number - 1,
+                ..origin
+            },
+        )?;
+        if let Some(query) = &self.query {
+            let rows: Vec<json::Value> = lines
+                .into_iter()
+                .enumerate()
+                .map(|(index, line)| {
+                    let mut output = self.format_result(line.output);
+                    if live.contains(&index) {
+                        mark_live(&mut output);
+                    }
+                    json::Value::object([
+                        ("line", json::Value::from(first_line_number + index)),
+                        ("input", json::Value::from(line.input)),
+                        ("output", json::Value::from(output)),
+                    ])
+                })
+                .collect();
+            let outputs: Vec<String> = query
+                .run(&json::Value::from(rows))?
+                .iter()
+                .map(query::format_output)
+                .collect();
+            return Ok(Output::Text(outputs.join("\n")));
+        }
+        if let Some(format) = self.labels {
+            let results: Vec<(String, String)> = lines
+                .into_iter()
+                .enumerate()
+                .filter_map(|(index, line)| {
+                    let (label, _) = vars::parse_label(&line.input)?;
+                    let mut output = self.format_result(line.output);
+                    if output.is_empty() {
+                        return None;
+                    }
+                    if live.contains(&index) {
+                        mark_live(&mut output);
+                    }
+                    Some((label.to_owned(), output))
+                })
+                .collect();
+            return Ok(Output::Text(match format {
+                LabelsFormat::Text => results
+                    .iter()
+                    .map(|(label, output)| format!("{label} = {output}"))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                LabelsFormat::Json => json::Value::object(results).to_string(),
+            }));
+        }
+        if !self.filter.is_empty() || !self.exclude.is_empty() || self.sort_by.is_some() {
+            (lines, live) = self.arrange_rows(lines, &live);
+        }
+        // The results are marked after any totals are calculated, so keep the lines to find them
+        // among the inserted rows
+        let calculated = if live.is_empty() {
+            Vec::new()
+        } else {
+            lines.clone()
+        };
+        if self.answer {
+            return Ok(Output::Text(
+                lines
+                    .into_iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, line)| {
+                        let mut output = self.format_result(line.output);
+                        if live.contains(&index) {
+                            mark_live(&mut output);
+                        }
+                        output
+                    })
+                    .find(|output| !output.is_empty())
+                    .unwrap_or_default(),
+            ));
+        }
+        if !self.line.is_empty() {
+            let number_of_lines = document.lines().count();
+            if let Some(range) = self.line.iter().find(|range| range.end > number_of_lines) {
+                bail!("line {range} is out of range (the sheet has {number_of_lines} lines)");
+            }
+            let outputs: Vec<String> = (1..=number_of_lines)
+                .filter(|line_number| self.line.iter().any(|range| range.contains(*line_number)))
+                .map(|line_number| {
+                    let mut output = lines.get(line_number - 1).map_or(String::new(), |line| {
+                        self.format_result(line.output.clone())
+                    });
+                    if live.contains(&(line_number - 1)) {
+                        mark_live(&mut output);
+                    }
+                    output
+                })
+                .collect();
+            return Ok(Output::Text(outputs.join("\n")));
+        }
+        if self.subtotals {
+            totals::insert_subtotals(&mut lines);
+        } else if self.total {
+            totals::append_total(&mut lines);
+        }
+        // Totals only insert rows, so the calculated lines are found in order
+        let mut next_calculated = 0;
+        for line in &mut lines {
+            let is_live = if calculated.get(next_calculated) == Some(line) {
+                next_calculated += 1;
+                live.contains(&(next_calculated - 1))
+            } else {
+                false
+            };
+            line.output = self.format_result(std::mem::take(&mut line.output));
+            if is_live {
+                mark_live(&mut line.output);
+            }
+        }
+        if self.skip_empty_results {
+            lines.retain(|line| !line.output.is_empty());
+        }
+        if self.keeps_trailing() {
+            let blank_line = Line {
+                input: String::new(),
+                output: String::new(),
+            };
+            lines.resize(lines.len() + trailing_blank_lines(document), blank_line);
+        }
+        Ok(Output::Rows(lines))
+    }
+}
+
+/// Calculate stdin for `--filter-mode`, falling back to an `error` result for each line that
+/// should have a result so the editor's buffer keeps its lines, and keeping each line's ending so
+/// the buffer's only changes are the results.
+fn filter(args: &CalculateArgs, config: &Config) -> Result<()> {
+    let sheet = io::read_to_string(io::stdin()).context("failed to read stdin")?;
+    let number_of_lines = sheet.lines().count();
+    let result = (|| {
+        let prelude = args.prelude(config)?;
+        let prelude: Vec<&str> = prelude.iter().map(String::as_str).collect();
+        let contents = match args.template()? {
+            Some(template) => template.render(&sheet)?,
+            None => sheet.clone(),
+        };
+        let result = args.calculate_sheet(&prelude, &hooks::pre(contents)?, "<stdin>")?;
+        if result.lines().count() != number_of_lines {
+            bail!("the results do not match the lines of the sheet");
+        }
+        Ok(result)
+    })();
+    let out = result.unwrap_or_else(|error| {
+        output::log_error(Some("<stdin>"), &error);
+        let lines: Vec<Line> = sheet
+            .lines()
+            .map(|input| Line {
+                input: input.to_owned(),
+                output: if soulver::expects_result(input) {
+                    "error".to_owned()
+                } else {
+                    String::new()
+                },
+            })
+            .collect();
+        if args.no_zip {
+            let outputs: Vec<String> = lines.into_iter().map(|line| line.output).collect();
+            outputs.join("\n")
+        } else {
+            soulver::zip(&lines)
+        }
+    });
+    output!("{}", with_line_endings(&out, &sheet));
+    Ok(())
+}
+
+/// End each line of `out` the way the same line of `sheet` ends, with `\r\n`, `\n`, or nothing.
+fn with_line_endings(out: &str, sheet: &str) -> String {
+    let endings = sheet.split_inclusive('\n').map(|line| {
+        if line.ends_with("\r\n") {
+            "\r\n"
+        } else if line.ends_with('\n') {
+            "\n"
+        } else {
+            ""
+        }
+    });
+    out.split('\n')
+        .zip(endings.chain(iter::repeat("\n")))
+        .map(|(line, ending)| format!("{}{ending}", line.trim_end_matches('\r')))
+        .collect()
+}
+
+/// Calculate batches of lines as they are read, recalculating the sheet so far after each batch and
+/// outputting the new lines' results, failing at the first line without a result with `--strict`.
+fn stream<I>(args: &CalculateArgs, config: &Config, name: &str, batches: I) -> Result<()>
+where
+    I: IntoIterator<Item = io::Result<Vec<String>>>,
+{
+    let prelude = args.prelude(config)?;
+    let prelude: Vec<&str> = prelude.iter().map(String::as_str).collect();
+    let template = args.template()?;
+    let mut sheet = String::new();
+    let mut line_count = 0;
+    let mut out = io::stdout().lock();
+    for batch in batches {
+        let mut batch = batch?;
+        for line in &mut batch {
+            if let Some(template) = &template {
+                *line = template.render(line)?;
+            }
+            sheet.push_str(line);
+            sheet.push('\n');
+        }
+        let mut lines = args.calculate_lines(&prelude, &sheet)?;
+        if let Some(new_lines) = lines.get_mut(line_count..) {
+            let origin = Origin {
+                sheet: name,
+                first_line_number: line_count + 1,
+            };
+            args.check_failed_lines(new_lines, origin)?;
+        }
+        let sheet_lines: Vec<&str> = sheet.lines().collect();
+        let live_lines = args.live_lines(&sheet_lines)?;
+
+        for (index, line) in (line_count..).zip(&batch) {
+            let mut output = lines
+                .get(index)
+                .map(|line| args.format_result(line.output.clone()))
+                .unwrap_or_default();
+            if live_lines.contains(&index) {
+                mark_live(&mut output);
+            }
+
+            if output::is_output_silenced() || args.skip_empty_results && output.is_empty() {
+                continue;
+            }
+            if args.no_zip {
+                writeln!(out, "{output}")?;
+            } else if output.is_empty() {
+                writeln!(out, "{line} |")?;
+            } else {
+                writeln!(out, "{line} | {output}")?;
+            }
+        }
+        out.flush()?;
+        line_count += batch.len();
+    }
+    Ok(())
+}
+
+/// Calculate sheets into an `.xlsx` workbook with a worksheet for each sheet.
+fn write_workbook(
+    args: &CalculateArgs,
+    sheets: &SheetArgs,
+    prelude: &[&str],
+    template: Option<&Template>,
+    path: &Path,
+    quiet: bool,
+) -> Result<ExitCode> {
+    let results = sheets.process(
+        quiet,
+        |sheet| {
+            let contents = profile::time(Phase::Preprocess, || {
+                match template {
+                    Some(template) => template.render(&sheet.contents),
+                    None => Ok(sheet.contents.clone()),
+                }
+                .and_then(hooks::pre)
+            })?;
+            args.calculate_rows(prelude, &contents, &sheet.name())
+        },
+        |_| true,
+    )?;
+
+    let mut success = true;
+    let mut worksheets = Vec::new();
+    let single_sheet = results.len() == 1;
+    for (sheet, result) in results {
+        match result {
+            Ok(lines) => {
+                let name = sheet
+                    .path
+                    .as_deref()
+                    .and_then(Path::file_stem)
+                    .map_or_else(|| sheet.name(), |stem| stem.to_string_lossy().into_owned());
+                worksheets.push((name, lines));
+            }
+            Err(error) if single_sheet => return Err(error),
+            Err(error) => {
+                success = false;
+                report_sheet_error(&sheet, &error);
+            }
+        }
+    }
+    let worksheets: Vec<(&str, &[Line])> = worksheets
+        .iter()
+        .map(|(name, lines)| (name.as_str(), lines.as_slice()))
+        .collect();
+    files::write_atomic(path, xlsx::workbook(&worksheets))?;
+    Ok(if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+pub fn calculate(args: &CalculateArgs, config: &Config, quiet: bool) -> Result<ExitCode> {
+    if args.stream {
+        let lines = io::stdin().lock().lines();
+        stream(
+            args,
+            config,
+            "<stdin>",
+            lines.map(|line| line.map(|line| vec![line])),
+        )?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if let Some(path) = &args.follow {
+        let name = path.display().to_string();
+        stream(args, config, &name, files::Follower::open(path)?)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if args.filter_mode {
+        filter(args, config)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let prelude = profile::time(Phase::Preprocess, || args.prelude(config))?;
+    let prelude: Vec<&str> = prelude.iter().map(String::as_str).collect();
+    let template = args.template()?;
+    let destination = args.destination();
+    let mut sheets = args.sheets.clone();
+    if !args.sheet_names.is_empty() {
+        let directories = library::directories(config);
+        for name in &args.sheet_names {
+            sheets.paths.push(library::find(&directories, name)?);
+        }
+    }
+    if let Some(OutputFormat::Xlsx) = args.format {
+        let Destination::File(path) = destination else {
+            bail!("`--format xlsx` requires `--output`");
+        };
+        return write_workbook(args, &sheets, &prelude, template.as_ref(), path, quiet);
+    }
+    let results = sheets.process(
+        quiet,
+        |sheet| {
+            // Annotations from a previous `--in-place` are calculated again
+            let source = if args.in_place {
+                check::strip_annotations(&sheet.contents)
+            } else {
+                sheet.contents.clone()
+            };
+            let contents = profile::time(Phase::Preprocess, || {
+                match &template {
+                    Some(template) => template.render(&source),
+                    None => Ok(source.clone()),
+                }
+                .and_then(hooks::pre)
+            })?;
+            let result = if args.dry_run {
+                args.soulver_input(&prelude, &contents)?
+            } else {
+                args.calculate_sheet(&prelude, &contents, &sheet.name())?
+            };
+            // The sheet is annotated as written, not as the template or pre hook rewrote it
+            let result = if args.in_place && contents != source {
+                check::move_annotations(&source, &contents, &result)
+                    .with_context(|| format!("failed to annotate {}", sheet.name()))?
+            } else {
+                result
+            };
+            let written = destination.write_sheet(sheet, &result)?;
+            Ok((result, written))
+        },
+        |_| true,
+    )?;
+
+    if config.history && !args.dry_run {
+        for (sheet, result) in &results {
+            if let Ok((result, _)) = result {
+                history::append(&history::Entry::now(&sheet.contents, result))
+                    .context("failed to record the history")?;
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let mut success = true;
+    if results.len() == 1 && !args.print0 {
+        let (_, result) = results.into_iter().next().unwrap();
+        let (result, written) = result?;
+        if !written {
+            out.push_str(&result);
+            out.push('\n');
+        }
+    } else {
+        for (index, (sheet, result)) in results.iter().enumerate() {
+            match result {
+                Ok((_, true)) => {}
+                Ok((result, false)) if args.print0 => {
+                    out.push_str(result);
+                    out.push('\0');
+                }
+                Ok((result, false)) => {
+                    if index > 0 {
+                        out.push('\n');
+                    }
+                    out.push_str(&format!("==> {} <==\n{result}\n", sheet.name()));
+                }
+                Err(error) => {
+                    success = false;
+                    report_sheet_error(sheet, error);
+                }
+            }
+        }
+    }
+
+    match destination {
+        Destination::Stdout => output!("{out}"),
+        Destination::File(path) => files::write_atomic(path, &out)?,
+        Destination::Directory(_) | Destination::InPlace { .. } => {}
+    }
+    Ok(if success {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::process;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{Cli, Commands};
+
+    fn args(arguments: &[&str]) -> CalculateArgs {
+        let cli = Cli::parse_from(["soulver-cli-zipper", "calculate"].iter().chain(arguments));
+        let Some(Commands::Calculate(args)) = cli.command else {
+            unreachable!("the arguments are for `calculate`");
+        };
+        *args
+    }
+
+    /// A sheet in a directory of its own, which is removed once the test is done.
+    fn sheet(name: &str, contents: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("soulver-calculate-{name}-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("sheet.soulver");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_in_place_template() {
+        let path = sheet("in-place-template", "Rent = {{rent}}\nRent * 12\n");
+        let values = path.with_file_name("values.json");
+        fs::write(&values, r#"{"rent": 1000}"#).unwrap();
+        let args = args(&[
+            "--template",
+            "--template-values",
+            values.to_str().unwrap(),
+            "-i",
+            path.to_str().unwrap(),
+        ]);
+        for _ in 0..2 {
+            calculate(&args, &Config::default(), true).unwrap();
+            let annotated = fs::read_to_string(&path).unwrap();
+            assert!(annotated.starts_with("Rent = {{rent}} => "), "{annotated}");
+            assert!(annotated.contains("\nRent * 12       => "), "{annotated}");
+        }
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_in_place_symlink() {
+        let target = sheet("in-place-symlink", "1 + 2\n");
+        let link = target.with_file_name("link.soulver");
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        calculate(
+            &args(&["-i", link.to_str().unwrap()]),
+            &Config::default(),
+            true,
+        )
+        .unwrap();
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "1 + 2 => 3\n");
+        fs::remove_dir_all(target.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_stream_strict() {
+        let batches = [Ok(vec!["1 + 1".to_owned()]), Ok(vec!["foo bar".to_owned()])];
+        let error = stream(
+            &args(&["--stream", "--strict"]),
+            &Config::default(),
+            "<stdin>",
+            batches,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "line 2 has no result");
+    }
+
+    #[test]
+    fn test_lines_with_verb() {
+        assert_eq!(lines_with_verb(&[3], "has", "have"), "line 3 has");
+        assert_eq!(lines_with_verb(&[3, 5], "has", "have"), "lines 3, 5 have");
+    }
+
+    #[test]
+    fn test_with_line_endings() {
+        assert_eq!(
+            with_line_endings("1 | 1\n2 | 2", "1\r\n2\r\n"),
+            "1 | 1\r\n2 | 2\r\n"
+        );
+        assert_eq!(with_line_endings("1 | 1\n2 | 2", "1\n2"), "1 | 1\n2 | 2");
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Result, bail};
+
+use crate::soulver::{self, Line};
+
+/// Separates an expression from the answer it is expected to produce.
+const ANNOTATION_SEPARATOR: &str = "=>";
+
+/// An annotated line whose result does not match its annotation.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    /// The 1-based line number in the sheet
+    pub line_number: usize,
+    /// The line up to the annotation separator, including any padding
+    pub expression: String,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl Mismatch {
+    /// Format the mismatch as a diff of the annotated line.
+    pub fn diff(&self) -> String {
+        format!(
+            "-{expression}{ANNOTATION_SEPARATOR} {expected}\n+{expression}{ANNOTATION_SEPARATOR} {actual}",
+            expression = self.expression,
+            expected = self.expected,
+            actual = self.actual,
+        )
+    }
+}
+
+fn split_annotation(line: &str) -> (&str, Option<&str>) {
+    match line.rsplit_once(ANNOTATION_SEPARATOR) {
+        Some((expression, expected)) => (expression, Some(expected.trim())),
+        None => (line, None),
+    }
+}
+
+/// Remove the annotations from a sheet, so it can be calculated and annotated again.
+pub fn strip_annotations(sheet: &str) -> String {
+    sheet
+        .split('\n')
+        .map(|line| match split_annotation(line) {
+            (expression, Some(_)) if !soulver::is_comment(line.trim_start()) => {
+                expression.trim_end()
+            }
+            _ => line,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Annotate each line that has a result with it, aligning the annotations, so the sheet can still
+/// be calculated and [`check`]ed.
+pub fn annotate(lines: &[Line]) -> String {
+    let width = lines
+        .iter()
+        .filter(|line| !line.output.is_empty())
+        .map(|line| line.input.trim_end().chars().count())
+        .max()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|line| {
+            if line.output.is_empty() {
+                return line.input.clone();
+            }
+            let input = line.input.trim_end();
+            let padding = " ".repeat(width - input.chars().count());
+            format!("{input}{padding} {ANNOTATION_SEPARATOR} {}", line.output)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Move the annotations [`annotate`] added to the lines of `rewritten` onto the lines of `source`,
+/// which `rewritten` was rewritten from line by line, e.g. by filling in a template, so annotating
+/// the sheet in place keeps it as written.
+pub fn move_annotations(source: &str, rewritten: &str, annotated: &str) -> Result<String> {
+    let source: Vec<&str> = source.split('\n').collect();
+    let rewritten: Vec<&str> = rewritten.split('\n').collect();
+    let annotated: Vec<&str> = annotated.split('\n').collect();
+    if source.len() != rewritten.len() || annotated.len() > rewritten.len() {
+        bail!("the sheet was rewritten to a different number of lines, so it cannot be annotated");
+    }
+    let lines: Vec<Line> = source
+        .iter()
+        .zip(&rewritten)
+        .enumerate()
+        .map(|(index, (source, rewritten))| {
+            let output = annotated
+                .get(index)
+                .and_then(|annotated| annotated.strip_prefix(rewritten.trim_end()))
+                .and_then(|rest| rest.trim_start().strip_prefix(ANNOTATION_SEPARATOR))
+                .map_or("", str::trim);
+            Line {
+                input: (*source).to_owned(),
+                output: output.to_owned(),
+            }
+        })
+        .collect();
+    Ok(annotate(&lines))
+}
+
+/// Recalculate an annotated sheet and return the lines whose results differ from their annotations.
+pub fn check(file: &str) -> Result<Vec<Mismatch>> {
+    let lines: Vec<(&str, Option<&str>)> = file.lines().map(split_annotation).collect();
+    let sheet: Vec<&str> = lines.iter().map(|(expression, _)| *expression).collect();
+    let results = soulver::calculate_lines(&sheet.join("\n"))?;
+
+    Ok(lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (expression, expected))| {
+            let expected = (*expected)?;
+            let actual = results
+                .get(index)
+                .map(|line| line.output.trim())
+                .unwrap_or("");
+            (actual != expected).then(|| Mismatch {
+                line_number: index + 1,
+                expression: (*expression).to_owned(),
+                expected: expected.to_owned(),
+                actual: actual.to_owned(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_annotation() {
+        assert_eq!(split_annotation("1 + 2  => 3"), ("1 + 2  ", Some("3")))
+    }
+
+    #[test]
+    fn test_split_annotation_none() {
+        assert_eq!(split_annotation("1 + 2"), ("1 + 2", None))
+    }
+
+    #[test]
+    fn test_strip_annotations() {
+        assert_eq!(
+            strip_annotations("Foo = 1  => 1\n# a => b\nFoo + 2"),
+            "Foo = 1\n# a => b\nFoo + 2"
+        )
+    }
+
+    #[test]
+    fn test_annotate_twice() {
+        let annotate_sheet = |sheet: &str| {
+            let lines = soulver::calculate_lines(&strip_annotations(sheet)).unwrap();
+            annotate(&lines)
+        };
+        let once = annotate_sheet("# Foo\nFoo = 1\nFoo + 20");
+        assert_eq!(once, "# Foo\nFoo = 1  => 1\nFoo + 20 => 21");
+        assert_eq!(annotate_sheet(&once), once);
+        assert_eq!(check(&once).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn test_move_annotations() {
+        assert_eq!(
+            move_annotations(
+                "# {{name}}\nRent = {{rent}}\nRent * 12",
+                "# Flat\nRent = 1000\nRent * 12",
+                "# Flat\nRent = 1000 => 1,000\nRent * 12   => 12,000",
+            )
+            .unwrap(),
+            "# {{name}}\nRent = {{rent}} => 1,000\nRent * 12       => 12,000",
+        );
+        assert!(move_annotations("a\nb", "a", "a => 1").is_err());
+    }
+
+    #[test]
+    fn test_mismatch_diff() {
+        let mismatch = Mismatch {
+            line_number: 1,
+            expression: "1 + 2  ".to_owned(),
+            expected: "4".to_owned(),
+            actual: "3".to_owned(),
+        };
+        assert_eq!(mismatch.diff(), "-1 + 2  => 4\n+1 + 2  => 3")
+    }
+
+    #[test]
+    fn test_check_match() {
+        assert_eq!(check("Foo = 1  => 1\nFoo + 2  => 3").unwrap(), vec![])
+    }
+
+    #[test]
+    fn test_check_mismatch() {
+        assert_eq!(
+            check("# Foo\n1 + 2 => 4\n3").unwrap(),
+            vec![Mismatch {
+                line_number: 2,
+                expression: "1 + 2 ".to_owned(),
+                expected: "4".to_owned(),
+                actual: "3".to_owned(),
+            }],
+        )
+    }
+}
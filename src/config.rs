@@ -0,0 +1,440 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+
+use crate::hooks::Hooks;
+use crate::sanitize::Sanitize;
+
+/// The environment variable to read the config file from instead of the default path.
+pub const CONFIG_PATH_VARIABLE: &str = "SOULVER_CLI_ZIPPER_CONFIG";
+
+/// Options read from the config file, which is written in a subset of TOML.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    /// Sheets calculated before every sheet without being included in the output
+    pub include: Vec<PathBuf>,
+    /// Prefixes of lines `soulver` outputs nothing for, instead of `#` and `//`
+    pub comment_prefixes: Option<Vec<String>>,
+    /// Whether to record each `calculate` run in the history journal
+    pub history: bool,
+    /// The directory to find sheets by name in instead of the Soulver library
+    pub library: Option<PathBuf>,
+    /// The host to run `soulver` on over SSH, e.g. `user@mac`
+    pub remote: Option<String>,
+    /// The token clients must send to the daemon
+    pub daemon_token: Option<String>,
+    /// Commands run on each sheet and its results by `calculate`
+    pub hooks: Hooks,
+    /// What is done with control characters and zero-width codepoints in sheets
+    pub sanitize: Option<Sanitize>,
+}
+
+/// A value in the config file.
+#[derive(Debug, PartialEq, Eq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Integer(_) => "integer",
+            Self::Bool(_) => "boolean",
+            Self::Array(_) => "array",
+        }
+    }
+
+    fn into_strings(self, key: &str) -> Result<Vec<String>> {
+        let Self::Array(values) = self else {
+            bail!("{key} must be an array, not a {}", self.type_name());
+        };
+        values
+            .into_iter()
+            .map(|value| match value {
+                Self::String(string) => Ok(string),
+                value => bail!(
+                    "{key} must only contain strings, not a {}",
+                    value.type_name()
+                ),
+            })
+            .collect()
+    }
+}
+
+/// A line of a TOML document.
+enum Statement {
+    /// A `[table]` header
+    Table(String),
+    Entry(String, Value),
+}
+
+/// A parser for the subset of TOML used by the config file.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    /// Consume the next character if it matches `predicate`.
+    fn next_if(&mut self, predicate: impl Fn(char) -> bool) -> Option<char> {
+        self.peek().filter(|c| predicate(*c))?;
+        self.next()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.next_if(|c| c == expected).is_some()
+    }
+
+    fn line_number(&self) -> usize {
+        self.text[..self.position].matches('\n').count() + 1
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.next_if(|c| c == ' ' || c == '\t').is_some() {}
+    }
+
+    fn skip_comment(&mut self) {
+        if self.eat('#') {
+            while self.next_if(|c| c != '\n').is_some() {}
+        }
+    }
+
+    /// Skip whitespace, new lines, and comments.
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_spaces();
+            self.skip_comment();
+            if self.next_if(|c| c == '\n' || c == '\r').is_none() {
+                return;
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        let quote = self.next();
+        let mut string = String::new();
+        loop {
+            match (quote, self.next()) {
+                (_, None | Some('\n')) => bail!("unterminated string"),
+                (Some(quote), Some(c)) if c == quote => return Ok(string),
+                (Some('"'), Some('\\')) => string.push(match self.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    c => bail!("invalid escape {c:?}"),
+                }),
+                (_, Some(c)) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String> {
+        if matches!(self.peek(), Some('"' | '\'')) {
+            return self.parse_string();
+        }
+        let mut key = String::new();
+        while let Some(c) = self.next_if(is_bare_key_char) {
+            key.push(c);
+        }
+        if key.is_empty() {
+            bail!("expected a key");
+        }
+        Ok(key)
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        match self.peek() {
+            Some('"' | '\'') => Ok(Value::String(self.parse_string()?)),
+            Some('[') => {
+                self.next();
+                let mut values = Vec::new();
+                loop {
+                    self.skip_blank();
+                    if self.eat(']') {
+                        return Ok(Value::Array(values));
+                    }
+                    values.push(self.parse_value()?);
+                    self.skip_blank();
+                    if !self.eat(',') {
+                        if !self.eat(']') {
+                            bail!("expected `,` or `]` in array");
+                        }
+                        return Ok(Value::Array(values));
+                    }
+                }
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(c) = self.next_if(|c| is_bare_key_char(c) || c == '+') {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    _ => match word.replace('_', "").parse() {
+                        Ok(integer) => Ok(Value::Integer(integer)),
+                        Err(_) => bail!("invalid value {word:?}"),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Parse a table header or `key = value` line.
+    fn parse_statement(&mut self) -> Result<Statement> {
+        let statement = if self.eat('[') {
+            self.skip_spaces();
+            let table = self.parse_key()?;
+            self.skip_spaces();
+            if !self.eat(']') {
+                bail!("expected `]`");
+            }
+            Statement::Table(table)
+        } else {
+            let key = self.parse_key()?;
+            self.skip_spaces();
+            if !self.eat('=') {
+                bail!("expected `=` after {key:?}");
+            }
+            self.skip_spaces();
+            Statement::Entry(key, self.parse_value()?)
+        };
+        self.skip_spaces();
+        self.skip_comment();
+        if !matches!(self.peek(), None | Some('\n' | '\r')) {
+            bail!("expected a new line");
+        }
+        Ok(statement)
+    }
+}
+
+fn is_bare_key_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Parse a TOML document into its keys and values, prefixing keys in tables with the table name.
+fn parse_toml(text: &str) -> Result<Vec<(String, Value)>> {
+    let mut parser = Parser { text, position: 0 };
+    let mut entries: Vec<(String, Value)> = Vec::new();
+    let mut table = String::new();
+    loop {
+        parser.skip_blank();
+        if parser.peek().is_none() {
+            return Ok(entries);
+        }
+        let line_number = parser.line_number();
+        let statement = parser
+            .parse_statement()
+            .with_context(|| format!("line {line_number}"))?;
+        match statement {
+            Statement::Table(name) => table = name,
+            Statement::Entry(key, value) => {
+                let key = if table.is_empty() {
+                    key
+                } else {
+                    format!("{table}.{key}")
+                };
+                if entries.iter().any(|(other, _)| *other == key) {
+                    bail!("line {line_number}: {key} is set more than once");
+                }
+                entries.push((key, value));
+            }
+        }
+    }
+}
+
+/// Parse a TOML document of keys with string, integer, or boolean values, converting the values
+/// to text.
+pub fn parse_toml_strings(text: &str) -> Result<Vec<(String, String)>> {
+    parse_toml(text)?
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                Value::String(string) => string,
+                Value::Integer(integer) => integer.to_string(),
+                Value::Bool(value) => value.to_string(),
+                Value::Array(_) => bail!("{key} must not be an array"),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+/// Expand a leading `~` and resolve a path relative to the config file's directory.
+fn resolve_path(path: &str, directory: &Path) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = env::var_os("HOME")
+    {
+        return Path::new(&home).join(rest);
+    }
+    directory.join(path)
+}
+
+impl Config {
+    /// The path of the config file, `$XDG_CONFIG_HOME/soulver-cli-zipper/config.toml` by default.
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os(CONFIG_PATH_VARIABLE) {
+            return Some(path.into());
+        }
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(Path::new(&env::var_os("HOME")?).join(".config")))?;
+        Some(config_home.join(env!("CARGO_PKG_NAME")).join("config.toml"))
+    }
+
+    /// The directory containing the config file, which also stores snippets and templates.
+    pub fn directory() -> Option<PathBuf> {
+        Some(Self::path()?.parent()?.to_owned())
+    }
+
+    /// Read the config file, or the default config if there isn't one.
+    pub fn load() -> Result<Self> {
+        let Some(path) = Self::path() else {
+            return Ok(Self::default());
+        };
+        let text = match fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => return Err(error.into()),
+        };
+        let directory = path.parent().unwrap_or(Path::new(""));
+        Self::parse(&text, directory).with_context(|| format!("invalid config {}", path.display()))
+    }
+
+    /// Parse a config file, resolving relative paths against `directory`.
+    pub fn parse(text: &str, directory: &Path) -> Result<Self> {
+        let mut config = Self::default();
+        for (key, value) in parse_toml(text)? {
+            match key.as_str() {
+                "include" => {
+                    config.include = value
+                        .into_strings(&key)?
+                        .iter()
+                        .map(|path| resolve_path(path, directory))
+                        .collect();
+                }
+                "library" => match value {
+                    Value::String(path) => config.library = Some(resolve_path(&path, directory)),
+                    value => bail!("library must be a string, not a {}", value.type_name()),
+                },
+                "remote" => match value {
+                    Value::String(host) => config.remote = Some(host),
+                    value => bail!("remote must be a string, not a {}", value.type_name()),
+                },
+                "daemon_token" => match value {
+                    Value::String(token) => config.daemon_token = Some(token),
+                    value => bail!("daemon_token must be a string, not a {}", value.type_name()),
+                },
+                "hooks.pre" => match value {
+                    Value::String(command) => config.hooks.pre = Some(command),
+                    value => bail!("hooks.pre must be a string, not a {}", value.type_name()),
+                },
+                "hooks.post" => match value {
+                    Value::String(command) => config.hooks.post = Some(command),
+                    value => bail!("hooks.post must be a string, not a {}", value.type_name()),
+                },
+                "sanitize" => match value {
+                    Value::String(sanitize) => match Sanitize::from_str(&sanitize, false) {
+                        Ok(sanitize) => config.sanitize = Some(sanitize),
+                        Err(_) => bail!(
+                            "sanitize must be \"off\", \"strip\", or \"escape\", not {sanitize:?}"
+                        ),
+                    },
+                    value => bail!("sanitize must be a string, not a {}", value.type_name()),
+                },
+                "history" => match value {
+                    Value::Bool(history) => config.history = history,
+                    value => bail!("history must be a boolean, not a {}", value.type_name()),
+                },
+                "comment_prefixes" => config.comment_prefixes = Some(value.into_strings(&key)?),
+                _ => bail!("unknown key {key}"),
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_toml() {
+        let text = "# Comment\na = \"x\\\"y\" # Trailing\nb = [\n  'c:\\d', \"e\",\n]\n\n[f]\ng = true\nh = -1_000\n";
+        assert_eq!(
+            parse_toml(text).unwrap(),
+            vec![
+                ("a".to_owned(), Value::String("x\"y".to_owned())),
+                (
+                    "b".to_owned(),
+                    Value::Array(vec![
+                        Value::String("c:\\d".to_owned()),
+                        Value::String("e".to_owned()),
+                    ]),
+                ),
+                ("f.g".to_owned(), Value::Bool(true)),
+                ("f.h".to_owned(), Value::Integer(-1000)),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_parse_toml_errors() {
+        assert!(parse_toml("a = \"b").is_err());
+        assert!(parse_toml("a = [1 2]").is_err());
+        assert!(parse_toml("a = 1\na = 2").is_err());
+        assert!(parse_toml("a = 1 b").is_err());
+
+        let error = parse_toml("a = 1\n\nb =").unwrap_err();
+        assert_eq!(error.to_string(), "line 3");
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            "include = [\"common.soulver\"]\ncomment_prefixes = [\"#\", \";\"]",
+            Path::new("/config"),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                include: vec![PathBuf::from("/config/common.soulver")],
+                comment_prefixes: Some(vec!["#".to_owned(), ";".to_owned()]),
+                history: false,
+                library: None,
+                remote: None,
+                daemon_token: None,
+                hooks: Hooks::default(),
+                sanitize: None,
+            },
+        )
+    }
+
+    #[test]
+    fn test_parse_config_errors() {
+        assert!(Config::parse("include = \"common.soulver\"", Path::new("")).is_err());
+        assert!(Config::parse("unknown = 1", Path::new("")).is_err());
+        assert!(Config::parse("history = \"yes\"", Path::new("")).is_err());
+    }
+}
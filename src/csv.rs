@@ -0,0 +1,149 @@
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+
+/// Parse CSV into rows of fields, allowing quoted fields containing commas, quotes written as
+/// `""`, and newlines.
+pub fn parse(text: &str) -> Result<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut chars = text.chars().peekable();
+    let mut in_quotes = false;
+    let mut line_number = 1;
+    while let Some(c) = chars.next() {
+        if c == '\n' {
+            line_number += 1;
+        }
+        match c {
+            '"' if in_quotes => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            _ if in_quotes => field.push(c),
+            ',' => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        bail!("unterminated quoted field on line {line_number}");
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// Format a field for CSV, quoting it if it contains a comma, quote, or newline.
+fn format_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Format a row of fields as a line of CSV.
+pub fn format_row<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|field| format_field(field.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// How `import csv` outputs the calculated sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ImportFormat {
+    /// The zipped sheet, like `calculate`
+    Zipped,
+    /// The CSV rows with a `result` column appended
+    Csv,
+}
+
+/// How `import csv` turns rows into lines of a sheet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Import {
+    /// The 0-based index of the column with each line's expression
+    pub expression_column: usize,
+    /// The 0-based index of the column to label each line with
+    pub label_column: Option<usize>,
+    /// Whether to assign the expression to a variable named by the label instead of labelling it
+    pub variables: bool,
+}
+
+impl Import {
+    /// The line of a sheet for a row, which is blank if the row has no expression.
+    pub fn line(&self, row: &[String]) -> String {
+        let cell = |index: usize| {
+            row.get(index).map_or(String::new(), |cell| {
+                cell.split_whitespace().collect::<Vec<_>>().join(" ")
+            })
+        };
+        let expression = cell(self.expression_column);
+        let label = self.label_column.map(cell).unwrap_or_default();
+        match (label.is_empty() || expression.is_empty(), self.variables) {
+            (true, _) => expression,
+            (false, true) => format!("{label} = {expression}"),
+            (false, false) => format!("{label}: {expression}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|field| (*field).to_owned()).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let text = "Item,Cost\r\nRent,\"£1,000\"\n\"Say \"\"hi\"\"\",\"a\nb\"\n\nLast,1";
+        assert_eq!(
+            parse(text).unwrap(),
+            [
+                row(&["Item", "Cost"]),
+                row(&["Rent", "£1,000"]),
+                row(&["Say \"hi\"", "a\nb"]),
+                row(&[""]),
+                row(&["Last", "1"]),
+            ],
+        );
+        assert!(parse("a,\"b\n").is_err());
+    }
+
+    #[test]
+    fn test_format_row() {
+        assert_eq!(
+            format_row(&["Rent", "£1,000.00", "Say \"hi\""]),
+            "Rent,\"£1,000.00\",\"Say \"\"hi\"\"\"",
+        );
+    }
+
+    #[test]
+    fn test_import_line() {
+        let mut import = Import {
+            expression_column: 1,
+            label_column: None,
+            variables: false,
+        };
+        let rent = row(&["Rent", "£1,000 * 12"]);
+        assert_eq!(import.line(&rent), "£1,000 * 12");
+        import.label_column = Some(0);
+        assert_eq!(import.line(&rent), "Rent: £1,000 * 12");
+        import.variables = true;
+        assert_eq!(import.line(&rent), "Rent = £1,000 * 12");
+        assert_eq!(import.line(&row(&["Empty"])), "");
+    }
+}
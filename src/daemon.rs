@@ -0,0 +1,807 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, Ipv4Addr, TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+#[cfg(unix)]
+use std::os::fd::{FromRawFd, IntoRawFd, RawFd};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::config::Config;
+use crate::json;
+use crate::metrics::Metrics;
+use crate::output;
+use crate::preview;
+use crate::soulver;
+use crate::task;
+
+/// The environment variable with the token clients must send to the daemon, which overrides
+/// `daemon_token` in the config file.
+pub const TOKEN_VARIABLE: &str = "SOULVER_CLI_ZIPPER_DAEMON_TOKEN";
+
+/// The token shared by the daemon and its clients, from [`TOKEN_VARIABLE`] or the config file.
+pub fn token(config: &Config) -> Result<String> {
+    env::var(TOKEN_VARIABLE)
+        .ok()
+        .or_else(|| config.daemon_token.clone())
+        .filter(|token| !token.is_empty())
+        .ok_or_else(|| {
+            anyhow!("no daemon token, set {TOKEN_VARIABLE} or `daemon_token` in the config file")
+        })
+}
+
+/// The version of the protocol clients and the daemon speak.
+///
+/// Every message is a frame of its kind, the length of its text in bytes, and a new line, followed
+/// by the text, e.g. `sheet 5\n1 + 1`. Clients send `version` with this version, then `token` with
+/// the token unless they connected to a Unix socket, then any number of `sheet`s, which are each
+/// answered with `ok` and the output or `error` and why.
+pub const PROTOCOL_VERSION: &str = "1";
+
+/// The longest header of a message, its kind and length, so a client cannot make the daemon read
+/// without end by never sending a new line.
+const MAX_HEADER_LENGTH: usize = 64;
+
+/// The longest token clients can send, so a client cannot make the daemon read without end before
+/// checking its token.
+const MAX_TOKEN_LENGTH: usize = 1024;
+
+/// The longest output clients accept from the daemon, so a broken daemon cannot make them run out
+/// of memory.
+const MAX_OUTPUT_LENGTH: usize = 256 * 1024 * 1024;
+
+/// How long the daemon waits to read from or write to a client, so idle clients cannot keep their
+/// connections open forever.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long the daemon lets `soulver` calculate a sheet before killing it, so a sheet that never
+/// finishes cannot keep a process running forever.
+const CALCULATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The longest HTTP request line and headers the metrics endpoint reads.
+const MAX_HTTP_REQUEST_LENGTH: u64 = 8 * 1024;
+
+/// How long the metrics endpoint waits to read a request from or write a response to a client.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How many metrics requests are answered at once.
+const MAX_HTTP_CONNECTIONS: NonZeroUsize = NonZeroUsize::new(4).unwrap();
+
+/// How long `--max-requests-per-minute` counts a client's requests for.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// What the daemon accepts from each client, so a runaway client cannot exhaust its machine.
+pub struct Limits {
+    /// The largest sheet in bytes
+    pub max_sheet_size: usize,
+    /// How many sheets each client's address can send in a minute
+    pub max_requests_per_minute: NonZeroUsize,
+    /// How many clients can be connected at once, each calculating at most one sheet at a time
+    pub max_connections: NonZeroUsize,
+}
+
+/// Counts each client address's requests in the last [`RATE_LIMIT_WINDOW`].
+#[derive(Default)]
+struct RateLimiter {
+    requests: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl RateLimiter {
+    /// Count a request from a client at `now`, returning whether it is within the limit.
+    fn allow(&self, client: IpAddr, limit: NonZeroUsize, now: Instant) -> bool {
+        let mut requests = self.requests.lock().unwrap();
+        let times = requests.entry(client).or_default();
+        while times
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= RATE_LIMIT_WINDOW)
+        {
+            times.pop_front();
+        }
+        if times.len() >= limit.get() {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+/// Whether two tokens are the same, taking as long for any tokens of the same length so the
+/// token cannot be guessed from how long it takes to be rejected.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+/// Write a message of the protocol, a line with its kind and length followed by its text.
+fn write_message(writer: &mut impl Write, kind: &str, text: &str) -> io::Result<()> {
+    write!(writer, "{kind} {}\n{text}", text.len())?;
+    writer.flush()
+}
+
+/// Read a message of the protocol as its kind and text, or `None` at the end of the stream, failing
+/// if its text is longer than `max_length` bytes.
+fn read_message(reader: &mut impl BufRead, max_length: usize) -> Result<Option<(String, String)>> {
+    let mut header = Vec::new();
+    reader
+        .by_ref()
+        .take(MAX_HEADER_LENGTH as u64)
+        .read_until(b'\n', &mut header)?;
+    if header.is_empty() {
+        return Ok(None);
+    }
+    if !header.ends_with(b"\n") && header.len() == MAX_HEADER_LENGTH {
+        bail!("the message header is longer than the limit of {MAX_HEADER_LENGTH} bytes");
+    }
+    let header = String::from_utf8_lossy(&header);
+    let Some((kind, length)) = header.trim_end().split_once(' ') else {
+        bail!("invalid message header {header:?}");
+    };
+    let length: usize = length
+        .parse()
+        .with_context(|| format!("invalid message length {length:?}"))?;
+    if length > max_length {
+        bail!("the {kind} is {length} bytes, more than the limit of {max_length}");
+    }
+    // The text is read as it arrives rather than into a buffer of its length, so a length that is
+    // never sent is not allocated
+    let mut text = Vec::new();
+    reader.by_ref().take(length as u64).read_to_end(&mut text)?;
+    if text.len() < length {
+        bail!("the connection closed partway through the {kind}");
+    }
+    Ok(Some((kind.to_owned(), String::from_utf8(text)?)))
+}
+
+/// Where a daemon listens for clients.
+#[derive(Debug)]
+pub enum Address {
+    /// A TCP address like `mac.local:7700`, which clients must send the token to
+    Tcp(String),
+    /// A Unix socket, which anyone allowed to open it can connect to
+    Unix(PathBuf),
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(address) => f.write_str(address),
+            Self::Unix(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Calculates sheets with a daemon instead of a local `soulver`.
+#[derive(Debug)]
+pub struct Client {
+    pub address: Address,
+    token: Option<String>,
+}
+
+impl Client {
+    /// A client of a daemon listening on TCP, which sends it the token.
+    pub fn tcp(address: String, token: String) -> Self {
+        Self {
+            address: Address::Tcp(address),
+            token: Some(token),
+        }
+    }
+
+    /// A client of a daemon listening on a Unix socket.
+    pub fn unix(path: PathBuf) -> Self {
+        Self {
+            address: Address::Unix(path),
+            token: None,
+        }
+    }
+
+    /// Calculate a sheet with the daemon's `soulver`, returning its output.
+    pub fn calculate(&self, sheet: &str) -> Result<String> {
+        let context = || format!("failed to connect to the daemon at {}", self.address);
+        match &self.address {
+            Address::Tcp(address) => {
+                self.request(&TcpStream::connect(address).with_context(context)?, sheet)
+            }
+            #[cfg(unix)]
+            Address::Unix(path) => {
+                self.request(&UnixStream::connect(path).with_context(context)?, sheet)
+            }
+            #[cfg(not(unix))]
+            Address::Unix(_) => bail!("Unix sockets are not supported on this platform"),
+        }
+    }
+
+    fn request<S>(&self, stream: &S, sheet: &str) -> Result<String>
+    where
+        for<'a> &'a S: Read + Write,
+    {
+        let mut writer = stream;
+        write_message(&mut writer, "version", PROTOCOL_VERSION)?;
+        if let Some(token) = &self.token {
+            write_message(&mut writer, "token", token)?;
+        }
+        write_message(&mut writer, "sheet", sheet)?;
+        match read_message(&mut BufReader::new(stream), MAX_OUTPUT_LENGTH)? {
+            Some((kind, output)) if kind == "ok" => Ok(output),
+            Some((kind, error)) if kind == "error" => bail!("the daemon failed: {error}"),
+            Some((kind, _)) => bail!("unexpected {kind:?} message from the daemon"),
+            None => bail!("the daemon closed the connection"),
+        }
+    }
+}
+
+/// The state shared by the daemon's connections.
+struct Server<'a> {
+    /// The token clients must send, unless they connect to a Unix socket
+    token: Option<&'a str>,
+    limits: &'a Limits,
+    metrics: Metrics,
+    rate_limiter: RateLimiter,
+}
+
+/// Answer the sheets of a client at the address `client`, described as `peer`, after checking its
+/// protocol version and token, until it disconnects.
+fn handle<S>(stream: &S, peer: &str, client: IpAddr, server: &Server) -> Result<()>
+where
+    for<'a> &'a S: Read + Write,
+{
+    let Server {
+        token,
+        limits,
+        metrics,
+        rate_limiter,
+    } = server;
+    let mut reader = BufReader::new(stream);
+    let mut writer = stream;
+    match read_message(&mut reader, MAX_TOKEN_LENGTH)? {
+        Some((kind, version)) if kind == "version" && version == PROTOCOL_VERSION => {}
+        // Clients like `daemon --socket` checking whether the daemon is running send nothing
+        None => return Ok(()),
+        Some((kind, version)) if kind == "version" => {
+            write_message(
+                &mut writer,
+                "error",
+                &format!("unsupported protocol version {version:?}, expected {PROTOCOL_VERSION}"),
+            )?;
+            bail!("{peer} uses the unsupported protocol version {version:?}");
+        }
+        _ => {
+            write_message(&mut writer, "error", "expected the protocol version")?;
+            bail!("{peer} did not send the protocol version");
+        }
+    }
+    if let Some(token) = token {
+        match read_message(&mut reader, MAX_TOKEN_LENGTH)? {
+            Some((kind, client_token)) if kind == "token" && tokens_match(&client_token, token) => {
+            }
+            _ => {
+                metrics.record_rejected_connection();
+                write_message(&mut writer, "error", "invalid token")?;
+                bail!("{peer} sent an invalid token");
+            }
+        }
+    }
+    loop {
+        let (kind, sheet) = match read_message(&mut reader, limits.max_sheet_size) {
+            Ok(Some(message)) => message,
+            Ok(None) => return Ok(()),
+            Err(error) => {
+                // The rest of the message is not read, so the connection cannot be used again
+                let _ = write_message(&mut writer, "error", &format!("{error:#}"));
+                return Err(error.context(format!("invalid request from {peer}")));
+            }
+        };
+        if kind != "sheet" {
+            bail!("unexpected {kind:?} message from {peer}");
+        }
+        let limit = limits.max_requests_per_minute;
+        if !rate_limiter.allow(client, limit, Instant::now()) {
+            metrics.record_rate_limited_request();
+            write_message(
+                &mut writer,
+                "error",
+                &format!("the limit of {limit} sheets a minute was reached, try again later"),
+            )?;
+            continue;
+        }
+        output::trace(
+            "daemon_request",
+            &format!("daemon: sheet of {} bytes from {peer}", sheet.len()),
+            vec![
+                ("peer", json::Value::from(peer)),
+                ("bytes", json::Value::from(sheet.len())),
+            ],
+        );
+        let start = Instant::now();
+        // Dropping the task once it times out kills its `soulver`
+        let result = task::spawn(move || soulver::run_local_soulver(&sheet))
+            .wait(CALCULATION_TIMEOUT)
+            .unwrap_or_else(|| {
+                Err(anyhow!(
+                    "the sheet took longer than {}s to calculate",
+                    CALCULATION_TIMEOUT.as_secs(),
+                ))
+            });
+        metrics.record_request(start.elapsed(), result.is_err());
+        match result {
+            Ok(output) => write_message(&mut writer, "ok", &output)?,
+            Err(error) => write_message(&mut writer, "error", &format!("{error:#}"))?,
+        }
+    }
+}
+
+/// Read an HTTP request's line and the token of its `Authorization: Bearer` header, if any.
+fn read_http_request(reader: &mut impl BufRead) -> io::Result<(String, Option<String>)> {
+    let mut reader = reader.take(MAX_HTTP_REQUEST_LENGTH);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    let mut token = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            return Ok((request_line, token));
+        }
+        if let Some((name, value)) = header.split_once(':')
+            && name.eq_ignore_ascii_case("authorization")
+            && let Some(bearer) = value.trim().strip_prefix("Bearer ")
+        {
+            token = Some(bearer.trim().to_owned());
+        }
+    }
+}
+
+/// The response to an HTTP request for the metrics, which must send the daemon's token.
+fn metrics_response(
+    request_line: &str,
+    client_token: Option<&str>,
+    token: &str,
+    metrics: &Metrics,
+) -> String {
+    if !client_token.is_some_and(|client_token| tokens_match(client_token, token)) {
+        metrics.record_rejected_connection();
+        return "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Bearer\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned();
+    }
+    match preview::request_path(request_line) {
+        Some("/metrics") => {
+            let text = metrics.render();
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{text}",
+                text.len(),
+            )
+        }
+        _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+    }
+}
+
+/// Answer HTTP requests for the metrics at `/metrics` that send the token as a bearer token.
+fn serve_metrics(listener: &TcpListener, metrics: &Metrics, token: &str) {
+    task::serve_connections(
+        listener.incoming(),
+        HTTP_TIMEOUT,
+        MAX_HTTP_CONNECTIONS,
+        |mut stream| {
+            let Ok((request_line, client_token)) = read_http_request(&mut BufReader::new(&stream))
+            else {
+                return;
+            };
+            let response = metrics_response(&request_line, client_token.as_deref(), token, metrics);
+            // A client closing the connection early is not an error
+            let _ = stream.write_all(response.as_bytes());
+        },
+    );
+}
+
+/// The first file descriptor systemd passes a service for its sockets.
+#[cfg(all(unix, not(target_os = "macos")))]
+const SYSTEMD_FIRST_FD: RawFd = 3;
+
+/// The listening socket systemd passed the daemon, from the `LISTEN_PID` and `LISTEN_FDS` it set.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn inherited_fd() -> Result<RawFd> {
+    let pid = env::var("LISTEN_PID").ok().and_then(|pid| pid.parse().ok());
+    let count: usize = env::var("LISTEN_FDS")
+        .ok()
+        .and_then(|count| count.parse().ok())
+        .unwrap_or(0);
+    if pid != Some(process::id()) || count == 0 {
+        bail!("systemd did not pass the daemon a socket, start it from a socket unit");
+    }
+    if count > 1 {
+        bail!("systemd passed the daemon {count} sockets, expected one");
+    }
+    Ok(SYSTEMD_FIRST_FD)
+}
+
+/// The listening socket launchd opened for the daemon's job, from its `Listeners` socket.
+#[cfg(target_os = "macos")]
+fn inherited_fd() -> Result<RawFd> {
+    use std::ffi::{c_char, c_int, c_void};
+
+    unsafe extern "C" {
+        fn launch_activate_socket(
+            name: *const c_char,
+            fds: *mut *mut c_int,
+            count: *mut usize,
+        ) -> c_int;
+        fn free(pointer: *mut c_void);
+    }
+
+    let mut fds = std::ptr::null_mut();
+    let mut count = 0;
+    // SAFETY: launchd allocates the array of `count` descriptors, which is freed once read
+    let error = unsafe { launch_activate_socket(c"Listeners".as_ptr(), &mut fds, &mut count) };
+    if error != 0 {
+        return Err(io::Error::from_raw_os_error(error)).context(
+            "launchd did not pass the daemon a socket, start it from a job with `Sockets`",
+        );
+    }
+    let fd = unsafe {
+        let fd = (count == 1).then(|| *fds);
+        free(fds.cast());
+        fd
+    };
+    fd.ok_or_else(|| anyhow!("launchd passed the daemon {count} sockets, expected one"))
+}
+
+/// What the daemon listens for clients on.
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Listener {
+    pub fn bind_tcp(address: &str) -> Result<Self> {
+        TcpListener::bind(address)
+            .map(Self::Tcp)
+            .with_context(|| format!("failed to listen on {address}"))
+    }
+
+    /// Listen on a Unix socket only the current user can connect to, replacing one left behind
+    /// by a daemon that is no longer running.
+    #[cfg(unix)]
+    pub fn bind_unix(path: &Path) -> Result<Self> {
+        use std::fs;
+        use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+        if let Ok(metadata) = fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                bail!("{} already exists and is not a socket", path.display());
+            }
+            if UnixStream::connect(path).is_ok() {
+                bail!("a daemon is already listening on {}", path.display());
+            }
+            fs::remove_file(path)
+                .with_context(|| format!("failed to remove the old socket {}", path.display()))?;
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("failed to listen on {}", path.display()))?;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+        Ok(Self::Unix(listener))
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind_unix(_path: &Path) -> Result<Self> {
+        bail!("Unix sockets are not supported on this platform")
+    }
+
+    /// The socket a service manager like systemd or launchd listens on for the daemon, so it is
+    /// only started once a client connects.
+    #[cfg(unix)]
+    pub fn inherited() -> Result<Self> {
+        let fd = inherited_fd()?;
+        // SAFETY: the service manager passed the socket for the daemon alone to own
+        let listener = unsafe { UnixListener::from_raw_fd(fd) };
+        if listener.local_addr().is_ok() {
+            return Ok(Self::Unix(listener));
+        }
+        // The address of a TCP socket is not a Unix socket address
+        // SAFETY: the socket is no longer owned by the Unix listener
+        Ok(Self::Tcp(unsafe {
+            TcpListener::from_raw_fd(listener.into_raw_fd())
+        }))
+    }
+
+    #[cfg(not(unix))]
+    pub fn inherited() -> Result<Self> {
+        bail!("socket activation is not supported on this platform")
+    }
+
+    /// Whether clients must send the token, which they need not over a Unix socket only they
+    /// can connect to.
+    pub fn requires_token(&self) -> bool {
+        matches!(self, Self::Tcp(_))
+    }
+
+    /// Where the listener is listening, for telling the user.
+    fn describe(&self) -> Result<String> {
+        Ok(match self {
+            Self::Tcp(listener) => listener.local_addr()?.to_string(),
+            #[cfg(unix)]
+            Self::Unix(listener) => match listener.local_addr()?.as_pathname() {
+                Some(path) => path.display().to_string(),
+                None => "an unnamed socket".to_owned(),
+            },
+        })
+    }
+}
+
+/// Calculate sheets with the local `soulver` for clients connecting to the listener, with the
+/// token if the listener [requires it](Listener::requires_token), optionally serving Prometheus
+/// metrics over HTTP on another address to clients sending the token.
+pub fn serve(
+    listener: &Listener,
+    token: Option<&str>,
+    metrics_address: Option<&str>,
+    limits: &Limits,
+) -> Result<()> {
+    let metrics_token = match (metrics_address, token) {
+        (Some(_), None) => bail!("serving metrics requires a daemon token"),
+        (_, token) => token,
+    };
+    let metrics_listener = metrics_address
+        .map(|address| {
+            TcpListener::bind(address).with_context(|| format!("failed to listen on {address}"))
+        })
+        .transpose()?;
+    outputln!("Listening on {}", listener.describe()?);
+    if let Some(metrics_listener) = &metrics_listener {
+        outputln!(
+            "Serving metrics at http://{}/metrics",
+            metrics_listener.local_addr()?
+        );
+    }
+    let server = Server {
+        token: token.filter(|_| listener.requires_token()),
+        limits,
+        metrics: Metrics::default(),
+        rate_limiter: RateLimiter::default(),
+    };
+    thread::scope(|scope| {
+        if let (Some(metrics_listener), Some(metrics_token)) = (&metrics_listener, metrics_token) {
+            scope.spawn(|| serve_metrics(metrics_listener, &server.metrics, metrics_token));
+        }
+        let server = &server;
+        let max_connections = limits.max_connections;
+        match listener {
+            Listener::Tcp(listener) => {
+                let incoming = listener.incoming();
+                task::serve_connections(incoming, CONNECTION_TIMEOUT, max_connections, |stream| {
+                    let result = stream
+                        .peer_addr()
+                        .map_err(Into::into)
+                        .and_then(|peer| handle(&stream, &peer.to_string(), peer.ip(), server));
+                    if let Err(error) = result {
+                        output::log_error(None, &error);
+                    }
+                });
+            }
+            #[cfg(unix)]
+            Listener::Unix(listener) => {
+                let incoming = listener.incoming();
+                task::serve_connections(incoming, CONNECTION_TIMEOUT, max_connections, |stream| {
+                    // Every client of a Unix socket is on this machine
+                    let client = IpAddr::V4(Ipv4Addr::LOCALHOST);
+                    if let Err(error) = handle(&stream, "a local client", client, server) {
+                        output::log_error(None, &error);
+                    }
+                });
+            }
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens_match() {
+        assert!(tokens_match("secret", "secret"));
+        assert!(!tokens_match("secret", "secreT"));
+        assert!(!tokens_match("secret", "secrets"));
+    }
+
+    #[test]
+    fn test_messages() {
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, "sheet", "1 + 1\n£5").unwrap();
+        write_message(&mut buffer, "sheet", "").unwrap();
+        assert_eq!(buffer, "sheet 9\n1 + 1\n£5sheet 0\n".as_bytes());
+
+        let mut reader = buffer.as_slice();
+        assert_eq!(
+            read_message(&mut reader, 9).unwrap(),
+            Some(("sheet".to_owned(), "1 + 1\n£5".to_owned())),
+        );
+        assert_eq!(
+            read_message(&mut reader, 9).unwrap(),
+            Some(("sheet".to_owned(), String::new())),
+        );
+        assert_eq!(read_message(&mut reader, 9).unwrap(), None);
+        assert!(read_message(&mut "sheet x\n".as_bytes(), 9).is_err());
+    }
+
+    #[test]
+    fn test_read_message_limit() {
+        let error = read_message(&mut "sheet 10\n1234567890".as_bytes(), 9).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the sheet is 10 bytes, more than the limit of 9",
+        );
+    }
+
+    #[test]
+    fn test_read_message_long_header() {
+        let header = "sheet ".repeat(100_000);
+        let error = read_message(&mut header.as_bytes(), usize::MAX).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the message header is longer than the limit of 64 bytes",
+        );
+    }
+
+    #[test]
+    fn test_read_message_truncated() {
+        // A length that is never sent is not read into memory
+        let error = read_message(
+            &mut "ok 18446744073709551615\n1".as_bytes(),
+            MAX_OUTPUT_LENGTH,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the ok is 18446744073709551615 bytes, more than the limit of 268435456",
+        );
+        let error = read_message(&mut "sheet 5\n1 +".as_bytes(), 9).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the connection closed partway through the sheet",
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_handle_unsupported_version() {
+        let (client, daemon) = UnixStream::pair().unwrap();
+        let server = Server {
+            token: None,
+            limits: &Limits {
+                max_sheet_size: 9,
+                max_requests_per_minute: NonZeroUsize::new(60).unwrap(),
+                max_connections: NonZeroUsize::new(1).unwrap(),
+            },
+            metrics: Metrics::default(),
+            rate_limiter: RateLimiter::default(),
+        };
+        write_message(&mut &client, "version", "0").unwrap();
+        let error = handle(
+            &daemon,
+            "a local client",
+            Ipv4Addr::LOCALHOST.into(),
+            &server,
+        );
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "a local client uses the unsupported protocol version \"0\"",
+        );
+        assert_eq!(
+            read_message(&mut BufReader::new(&client), usize::MAX).unwrap(),
+            Some((
+                "error".to_owned(),
+                "unsupported protocol version \"0\", expected 1".to_owned()
+            )),
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_handle_sheet() {
+        let (client, daemon) = UnixStream::pair().unwrap();
+        let server = Server {
+            token: None,
+            limits: &Limits {
+                max_sheet_size: 9,
+                max_requests_per_minute: NonZeroUsize::new(60).unwrap(),
+                max_connections: NonZeroUsize::new(1).unwrap(),
+            },
+            metrics: Metrics::default(),
+            rate_limiter: RateLimiter::default(),
+        };
+        write_message(&mut &client, "version", PROTOCOL_VERSION).unwrap();
+        write_message(&mut &client, "sheet", "1 + 2").unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        handle(
+            &daemon,
+            "a local client",
+            Ipv4Addr::LOCALHOST.into(),
+            &server,
+        )
+        .unwrap();
+        assert_eq!(
+            read_message(&mut BufReader::new(&client), MAX_OUTPUT_LENGTH).unwrap(),
+            Some(("ok".to_owned(), "3\n".to_owned())),
+        );
+    }
+
+    #[test]
+    fn test_read_http_request() {
+        let request = "GET /metrics HTTP/1.1\r\nHost: mac\r\nauthorization: Bearer secret \r\n\r\n";
+        assert_eq!(
+            read_http_request(&mut request.as_bytes()).unwrap(),
+            (
+                "GET /metrics HTTP/1.1\r\n".to_owned(),
+                Some("secret".to_owned())
+            ),
+        );
+        let (_, token) =
+            read_http_request(&mut "GET /metrics HTTP/1.1\r\n\r\n".as_bytes()).unwrap();
+        assert_eq!(token, None);
+    }
+
+    #[test]
+    fn test_metrics_response() {
+        let metrics = Metrics::default();
+        let request_line = "GET /metrics HTTP/1.1\r\n";
+        let status = |response: String| response.lines().next().unwrap().to_owned();
+        assert_eq!(
+            status(metrics_response(
+                request_line,
+                Some("secret"),
+                "secret",
+                &metrics
+            )),
+            "HTTP/1.1 200 OK",
+        );
+        assert_eq!(
+            status(metrics_response(
+                request_line,
+                Some("guess"),
+                "secret",
+                &metrics
+            )),
+            "HTTP/1.1 401 Unauthorized",
+        );
+        assert_eq!(
+            status(metrics_response(request_line, None, "secret", &metrics)),
+            "HTTP/1.1 401 Unauthorized",
+        );
+        assert_eq!(
+            status(metrics_response(
+                "GET / HTTP/1.1",
+                Some("secret"),
+                "secret",
+                &metrics
+            )),
+            "HTTP/1.1 404 Not Found",
+        );
+    }
+
+    #[test]
+    fn test_rate_limiter() {
+        let rate_limiter = RateLimiter::default();
+        let limit = NonZeroUsize::new(2).unwrap();
+        let client = IpAddr::from([192, 168, 1, 2]);
+        let other_client = IpAddr::from([192, 168, 1, 3]);
+        let start = Instant::now();
+        assert!(rate_limiter.allow(client, limit, start));
+        assert!(rate_limiter.allow(client, limit, start + Duration::from_secs(1)));
+        assert!(!rate_limiter.allow(client, limit, start + Duration::from_secs(2)));
+        assert!(rate_limiter.allow(other_client, limit, start + Duration::from_secs(2)));
+        // The first request no longer counts a minute later
+        assert!(rate_limiter.allow(client, limit, start + Duration::from_secs(60)));
+        assert!(!rate_limiter.allow(client, limit, start + Duration::from_secs(60)));
+    }
+}
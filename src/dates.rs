@@ -0,0 +1,219 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Error, Result, bail};
+
+use crate::vars;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// A calendar date, parsed from `YYYY-MM-DD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+impl Date {
+    /// The date a number of days after the Unix epoch, January 1, 1970.
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        // Howard Hinnant's `civil_from_days`, counting from March so leap days end each era's year
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_from_march + 2) / 5 + 1;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        Self {
+            year: year as i32,
+            month: month as u32,
+            day: day as u32,
+        }
+    }
+
+    /// The current date in UTC.
+    pub fn today() -> Self {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        Self::from_days_since_epoch((seconds / 86_400) as i64)
+    }
+
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    /// The name of the month, e.g. `June`.
+    pub fn month_name(self) -> &'static str {
+        MONTH_NAMES[self.month as usize - 1]
+    }
+
+    pub fn next_day(self) -> Self {
+        if self.day < days_in_month(self.year, self.month) {
+            Self {
+                day: self.day + 1,
+                ..self
+            }
+        } else if self.month < 12 {
+            Self {
+                month: self.month + 1,
+                day: 1,
+                ..self
+            }
+        } else {
+            Self {
+                year: self.year + 1,
+                month: 1,
+                day: 1,
+            }
+        }
+    }
+
+    pub fn previous_day(self) -> Self {
+        if self.day > 1 {
+            Self {
+                day: self.day - 1,
+                ..self
+            }
+        } else if self.month > 1 {
+            Self {
+                month: self.month - 1,
+                day: days_in_month(self.year, self.month - 1),
+                ..self
+            }
+        } else {
+            Self {
+                year: self.year - 1,
+                month: 12,
+                day: 31,
+            }
+        }
+    }
+}
+
+impl FromStr for Date {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parts: Vec<&str> = s.split('-').collect();
+        let [year, month, day] = parts[..] else {
+            bail!("expected a date in the form YYYY-MM-DD, got {s:?}");
+        };
+        let (Ok(year), Ok(month), Ok(day)) = (year.parse(), month.parse(), day.parse()) else {
+            bail!("expected a date in the form YYYY-MM-DD, got {s:?}");
+        };
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            bail!("{s:?} is not a valid date");
+        }
+        Ok(Self { year, month, day })
+    }
+}
+
+/// Formats the date the way it is written in sheets, e.g. `June 1, 2024`.
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}, {}", self.month_name(), self.day, self.year)
+    }
+}
+
+/// Replace the relative dates `today`, `tomorrow`, and `yesterday` in a sheet with dates relative
+/// to `today`, so it calculates the same results on any day.
+pub fn pin_today(sheet: &str, today: Date) -> String {
+    let mut pinned = sheet.to_owned();
+    for (word, date) in [
+        ("today", today),
+        ("tomorrow", today.next_day()),
+        ("yesterday", today.previous_day()),
+    ] {
+        // ASCII lowercasing keeps byte offsets the same
+        let lowercase = pinned.to_ascii_lowercase();
+        let date = date.to_string();
+        for index in vars::find_name(&lowercase, word).into_iter().rev() {
+            pinned.replace_range(index..index + word.len(), &date);
+        }
+    }
+    pinned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> Date {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            date("2024-06-01"),
+            Date {
+                year: 2024,
+                month: 6,
+                day: 1,
+            },
+        );
+        assert!("2023-02-29".parse::<Date>().is_err());
+        assert!("2024-13-01".parse::<Date>().is_err());
+        assert!("June 1".parse::<Date>().is_err());
+    }
+
+    #[test]
+    fn test_from_days_since_epoch() {
+        assert_eq!(Date::from_days_since_epoch(0), date("1970-01-01"));
+        assert_eq!(Date::from_days_since_epoch(19_875), date("2024-06-01"));
+        assert_eq!(Date::from_days_since_epoch(19_782), date("2024-02-29"));
+        assert_eq!(Date::from_days_since_epoch(-1), date("1969-12-31"));
+    }
+
+    #[test]
+    fn test_next_and_previous_day() {
+        assert_eq!(date("2024-02-28").next_day(), date("2024-02-29"));
+        assert_eq!(date("2024-12-31").next_day(), date("2025-01-01"));
+        assert_eq!(date("2024-03-01").previous_day(), date("2024-02-29"));
+        assert_eq!(date("2024-01-01").previous_day(), date("2023-12-31"));
+    }
+
+    #[test]
+    fn test_pin_today() {
+        assert_eq!(
+            pin_today(
+                "Today + 30 days\ntomorrow - yesterday\ntodays",
+                date("2024-06-01"),
+            ),
+            "June 1, 2024 + 30 days\nJune 2, 2024 - May 31, 2024\ntodays",
+        )
+    }
+}
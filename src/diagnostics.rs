@@ -0,0 +1,69 @@
+use crate::json;
+use crate::soulver::{self, Line};
+
+/// The LSP `DiagnosticSeverity` of lines without results.
+const SEVERITY_ERROR: usize = 1;
+
+/// A position in a document, as LSP counts them: a zero-based line and a UTF-16 offset.
+fn position(line: usize, character: usize) -> json::Value {
+    json::Value::object([
+        ("line", json::Value::from(line)),
+        ("character", json::Value::from(character)),
+    ])
+}
+
+/// LSP diagnostics spanning each line that should have a result but does not, with lines
+/// numbered from `first_line_number`.
+pub fn diagnostics(lines: &[Line], first_line_number: usize) -> json::Value {
+    json::Value::from(
+        lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.output.is_empty() && soulver::expects_result(&line.input))
+            .map(|(index, line)| {
+                let line_index = first_line_number - 1 + index;
+                json::Value::object([
+                    (
+                        "range",
+                        json::Value::object([
+                            ("start", position(line_index, 0)),
+                            (
+                                "end",
+                                position(line_index, line.input.encode_utf16().count()),
+                            ),
+                        ]),
+                    ),
+                    ("severity", json::Value::from(SEVERITY_ERROR)),
+                    ("source", json::Value::from("soulver-cli-zipper")),
+                    (
+                        "message",
+                        json::Value::from(format!("no result for {:?}", line.input.trim())),
+                    ),
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        let lines = [
+            Line::new("# Costs", ""),
+            Line::new("1 + 2", "3"),
+            Line::new("£5 foo", ""),
+        ];
+        assert_eq!(
+            diagnostics(&lines, 1).to_string(),
+            r#"[{"range":{"start":{"line":2,"character":0},"end":{"line":2,"character":6}},"severity":1,"source":"soulver-cli-zipper","message":"no result for \"£5 foo\""}]"#
+        );
+        assert!(
+            diagnostics(&lines, 10)
+                .to_string()
+                .contains(r#""start":{"line":11,"character":0}"#)
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use anyhow::Result;
+
+use crate::json;
+use crate::soulver::{self, Line};
+
+/// A difference between the calculated lines of two sheets.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change {
+    /// A line only in the new sheet
+    Added { new_line_number: usize, line: Line },
+    /// A line only in the old sheet
+    Removed { old_line_number: usize, line: Line },
+    /// A line in both sheets whose result has changed, e.g. due to exchange rates
+    ResultChanged {
+        old_line_number: usize,
+        new_line_number: usize,
+        input: String,
+        old_output: String,
+        new_output: String,
+    },
+}
+
+impl Change {
+    pub fn to_json(&self) -> json::Value {
+        match self {
+            Self::Added {
+                new_line_number,
+                line,
+            } => json::Value::object([
+                ("kind", json::Value::from("added")),
+                ("new_line", json::Value::from(*new_line_number)),
+                ("input", json::Value::from(line.input.as_str())),
+                ("output", json::Value::from(line.output.as_str())),
+            ]),
+            Self::Removed {
+                old_line_number,
+                line,
+            } => json::Value::object([
+                ("kind", json::Value::from("removed")),
+                ("old_line", json::Value::from(*old_line_number)),
+                ("input", json::Value::from(line.input.as_str())),
+                ("output", json::Value::from(line.output.as_str())),
+            ]),
+            Self::ResultChanged {
+                old_line_number,
+                new_line_number,
+                input,
+                old_output,
+                new_output,
+            } => json::Value::object([
+                ("kind", json::Value::from("result_changed")),
+                ("old_line", json::Value::from(*old_line_number)),
+                ("new_line", json::Value::from(*new_line_number)),
+                ("input", json::Value::from(input.as_str())),
+                ("old_output", json::Value::from(old_output.as_str())),
+                ("new_output", json::Value::from(new_output.as_str())),
+            ]),
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Added {
+                new_line_number,
+                line,
+            } => write!(f, "+{new_line_number}: {} | {}", line.input, line.output),
+            Self::Removed {
+                old_line_number,
+                line,
+            } => write!(f, "-{old_line_number}: {} | {}", line.input, line.output),
+            Self::ResultChanged {
+                new_line_number,
+                input,
+                old_output,
+                new_output,
+                ..
+            } => write!(
+                f,
+                "~{new_line_number}: {input} | {old_output} -> {new_output}"
+            ),
+        }
+    }
+}
+
+/// Align two sheets' lines by their inputs using the longest common subsequence and return the
+/// changes between them.
+pub fn diff_lines(old: &[Line], new: &[Line]) -> Vec<Change> {
+    // lengths[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lengths = vec![vec![0_usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i][j] = if old[i].input == new[j].input {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i].input == new[j].input {
+            if old[i].output != new[j].output {
+                changes.push(Change::ResultChanged {
+                    old_line_number: i + 1,
+                    new_line_number: j + 1,
+                    input: new[j].input.clone(),
+                    old_output: old[i].output.clone(),
+                    new_output: new[j].output.clone(),
+                });
+            }
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            changes.push(Change::Removed {
+                old_line_number: i + 1,
+                line: old[i].clone(),
+            });
+            i += 1;
+        } else {
+            changes.push(Change::Added {
+                new_line_number: j + 1,
+                line: new[j].clone(),
+            });
+            j += 1;
+        }
+    }
+    changes
+}
+
+/// Calculate two sheets and return the changes between them.
+pub fn diff(old: &str, new: &str) -> Result<Vec<Change>> {
+    Ok(diff_lines(
+        &soulver::calculate_lines(old)?,
+        &soulver::calculate_lines(new)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines_same() {
+        let lines = [Line::new("1", "1"), Line::new("2", "2")];
+        assert_eq!(diff_lines(&lines, &lines), vec![])
+    }
+
+    #[test]
+    fn test_diff_lines_result_changed() {
+        assert_eq!(
+            diff_lines(
+                &[Line::new("$1 in £", "£0.80")],
+                &[Line::new("$1 in £", "£0.75")]
+            ),
+            vec![Change::ResultChanged {
+                old_line_number: 1,
+                new_line_number: 1,
+                input: "$1 in £".to_owned(),
+                old_output: "£0.80".to_owned(),
+                new_output: "£0.75".to_owned(),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_diff_lines_input_changed() {
+        let old = [Line::new("Foo = 1", "1"), Line::new("Foo + 1", "2")];
+        let new = [Line::new("Foo = 2", "2"), Line::new("Foo + 1", "3")];
+        assert_eq!(
+            diff_lines(&old, &new)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["-1: Foo = 1 | 1", "+1: Foo = 2 | 2", "~2: Foo + 1 | 2 -> 3"],
+        )
+    }
+
+    #[test]
+    fn test_diff_lines_inserted() {
+        let old = [Line::new("1", "1"), Line::new("3", "3")];
+        let new = [
+            Line::new("1", "1"),
+            Line::new("2", "2"),
+            Line::new("3", "3"),
+        ];
+        assert_eq!(
+            diff_lines(&old, &new),
+            vec![Change::Added {
+                new_line_number: 2,
+                line: Line::new("2", "2"),
+            }],
+        )
+    }
+
+    #[test]
+    fn test_change_to_json() {
+        assert_eq!(
+            Change::Removed {
+                old_line_number: 2,
+                line: Line::new("2", "2"),
+            }
+            .to_json()
+            .to_string(),
+            r#"{"kind":"removed","old_line":2,"input":"2","output":"2"}"#,
+        )
+    }
+}
@@ -0,0 +1,697 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::bundle;
+
+/// The name of the file listing paths to skip when walking directories, using gitignore syntax.
+pub const IGNORE_FILE_NAME: &str = ".soulverignore";
+
+/// A sheet read from a file or stdin.
+pub struct Sheet {
+    /// The path of the sheet, or `None` for stdin
+    pub path: Option<PathBuf>,
+    /// The 1-based number of the sheet when reading NUL-separated sheets from stdin
+    pub record: Option<usize>,
+    pub contents: String,
+}
+
+impl Sheet {
+    /// A name for the sheet to use in output.
+    pub fn name(&self) -> String {
+        match (&self.path, self.record) {
+            (Some(path), _) => path.display().to_string(),
+            (None, Some(record)) => format!("<stdin #{record}>"),
+            (None, None) => "<stdin>".to_owned(),
+        }
+    }
+}
+
+/// Split NUL-separated sheets, ignoring the terminator after the last sheet.
+fn split_records(input: &str) -> Vec<&str> {
+    let input = input.strip_suffix('\0').unwrap_or(input);
+    if input.is_empty() {
+        return Vec::new();
+    }
+    input.split('\0').collect()
+}
+
+/// Match a glob pattern against a `/`-separated path.
+///
+/// `*` matches any characters except `/`, `**` matches any characters including `/`, `?` matches a
+/// single character, and `[...]` matches a single character in a set such as `[abc]`, `[a-z]`, or
+/// `[!abc]`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    glob_match_chars(&pattern, &text)
+}
+
+fn glob_match_chars(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', rest @ ..] => {
+            let rest = rest.strip_prefix(&['/']).unwrap_or(rest);
+            (0..=text.len()).any(|skip| glob_match_chars(rest, &text[skip..]))
+                || (0..text.len())
+                    .any(|skip| text[skip] == '/' && glob_match_chars(rest, &text[skip + 1..]))
+        }
+        ['*', rest @ ..] => (0..=text.len())
+            .take_while(|&skip| skip == 0 || text[skip - 1] != '/')
+            .any(|skip| glob_match_chars(rest, &text[skip..])),
+        ['?', rest @ ..] => {
+            matches!(text, [c, ..] if *c != '/') && glob_match_chars(rest, &text[1..])
+        }
+        ['[', rest @ ..] => {
+            let Some(end) = rest
+                .iter()
+                .skip(1)
+                .position(|&c| c == ']')
+                .map(|end| end + 1)
+            else {
+                return text.first() == Some(&'[') && glob_match_chars(rest, &text[1..]);
+            };
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            let (negated, set) = match &rest[..end] {
+                ['!' | '^', set @ ..] => (true, set),
+                set => (false, set),
+            };
+            let mut matched = false;
+            let mut index = 0;
+            while index < set.len() {
+                if index + 2 < set.len() && set[index + 1] == '-' {
+                    matched |= (set[index]..=set[index + 2]).contains(&c);
+                    index += 3;
+                } else {
+                    matched |= set[index] == c;
+                    index += 1;
+                }
+            }
+            matched != negated && glob_match_chars(&rest[end + 1..], &text[1..])
+        }
+        [p, rest @ ..] => text.first() == Some(p) && glob_match_chars(rest, &text[1..]),
+    }
+}
+
+/// The `/`-separated path of a file relative to `root`.
+fn relative_path(root: &Path, file: &Path) -> String {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    let components: Vec<_> = relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect();
+    components.join("/")
+}
+
+/// Whether a file found while walking `root` matches `glob`.
+///
+/// Patterns without a `/` are matched against the file name, otherwise they are matched against
+/// the path relative to `root`.
+fn matches_glob(glob: &str, root: &Path, file: &Path) -> bool {
+    if glob.contains('/') {
+        glob_match(glob, &relative_path(root, file))
+    } else {
+        file.file_name()
+            .is_some_and(|name| glob_match(glob, &name.to_string_lossy()))
+    }
+}
+
+/// A pattern from a `.soulverignore` file.
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    /// The directory containing the `.soulverignore` file
+    base: PathBuf,
+    pattern: String,
+    /// Whether the pattern is matched against the path relative to `base` rather than the name
+    anchored: bool,
+    negated: bool,
+    directory_only: bool,
+}
+
+impl IgnoreRule {
+    fn parse(base: &Path, line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(line) => (true, line),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (directory_only, line) = match line.strip_suffix('/') {
+            Some(line) => (true, line),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        Some(Self {
+            base: base.to_owned(),
+            pattern: line.strip_prefix('/').unwrap_or(line).to_owned(),
+            anchored,
+            negated,
+            directory_only,
+        })
+    }
+
+    fn matches(&self, path: &Path, is_dir: bool) -> bool {
+        if self.directory_only && !is_dir {
+            return false;
+        }
+        if self.anchored {
+            glob_match(&self.pattern, &relative_path(&self.base, path))
+        } else {
+            path.file_name()
+                .is_some_and(|name| glob_match(&self.pattern, &name.to_string_lossy()))
+        }
+    }
+}
+
+/// Whether the last rule matching a path ignores it.
+fn is_ignored(rules: &[IgnoreRule], path: &Path, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .rev()
+        .find(|rule| rule.matches(path, is_dir))
+        .is_some_and(|rule| !rule.negated)
+}
+
+fn read_ignore_rules(directory: &Path) -> Result<Vec<IgnoreRule>> {
+    let path = directory.join(IGNORE_FILE_NAME);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .filter_map(|line| IgnoreRule::parse(directory, line))
+        .collect())
+}
+
+fn walk(
+    root: &Path,
+    directory: &Path,
+    glob: Option<&str>,
+    mut rules: Vec<IgnoreRule>,
+    out: &mut Vec<PathBuf>,
+) -> Result<()> {
+    rules.extend(read_ignore_rules(directory)?);
+
+    let mut entries = fs::read_dir(directory)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        // Bundles are directories, but are read as sheets
+        let is_dir = path.is_dir() && !bundle::is_bundle(&path);
+        if is_ignored(&rules, &path, is_dir) {
+            continue;
+        }
+        if is_dir {
+            walk(root, &path, glob, rules.clone(), out)?;
+        } else if path
+            .file_name()
+            .is_some_and(|name| name != IGNORE_FILE_NAME)
+            && glob.is_none_or(|glob| matches_glob(glob, root, &path))
+        {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Expand paths into the sheets to calculate, sorted by path within each directory.
+///
+/// Directories are only allowed when `recursive` is set, in which case the files under them that
+/// match `glob` and aren't excluded by a `.soulverignore` file are included. Files given directly
+/// are always included.
+pub fn collect_sheets(
+    paths: &[PathBuf],
+    recursive: bool,
+    glob: Option<&str>,
+) -> Result<Vec<PathBuf>> {
+    let mut out = Vec::new();
+    for path in paths {
+        if path.is_dir() && !bundle::is_bundle(path) {
+            if !recursive {
+                bail!("{} is a directory (use --recursive)", path.display());
+            }
+            walk(path, path, glob, Vec::new(), &mut out)?;
+        } else {
+            out.push(path.clone());
+        }
+    }
+    Ok(out)
+}
+
+/// Read the sheets at paths as expanded by [`collect_sheets`], or stdin if there are no paths.
+///
+/// If `stdin0` is set, stdin is read as multiple NUL-separated sheets. Each sheet comes with
+/// whether it could be read, so one unreadable file can be reported without stopping the rest of
+/// a batch, in which case its contents are empty.
+pub fn read_sheets(
+    paths: &[PathBuf],
+    recursive: bool,
+    glob: Option<&str>,
+    stdin0: bool,
+) -> Result<Vec<(Sheet, Result<()>)>> {
+    if paths.is_empty() {
+        let mut contents = String::new();
+        io::stdin().read_to_string(&mut contents)?;
+        if stdin0 {
+            return Ok(split_records(&contents)
+                .into_iter()
+                .enumerate()
+                .map(|(index, contents)| {
+                    let sheet = Sheet {
+                        path: None,
+                        record: Some(index + 1),
+                        contents: contents.to_owned(),
+                    };
+                    (sheet, Ok(()))
+                })
+                .collect());
+        }
+        let sheet = Sheet {
+            path: None,
+            record: None,
+            contents,
+        };
+        return Ok(vec![(sheet, Ok(()))]);
+    }
+
+    let sheets = collect_sheets(paths, recursive, glob)?
+        .into_iter()
+        .map(|path| {
+            let contents = if bundle::is_bundle(&path) {
+                bundle::read_text(&path)
+            } else {
+                fs::read_to_string(&path).map_err(Into::into)
+            }
+            .with_context(|| format!("failed to read {}", path.display()));
+            let (contents, read) = match contents {
+                Ok(contents) => (contents, Ok(())),
+                Err(error) => (String::new(), Err(error)),
+            };
+            let sheet = Sheet {
+                contents,
+                path: Some(path),
+                record: None,
+            };
+            (sheet, read)
+        })
+        .collect();
+    Ok(sheets)
+}
+
+/// Write a file atomically by writing to a temporary file next to it and renaming it into place,
+/// so readers never see a partially written file.
+///
+/// An existing file keeps its permissions, and a symlink is kept by replacing the file it links
+/// to instead.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path = &match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => path.to_owned(),
+        Err(error) => {
+            return Err(error).with_context(|| format!("failed to resolve {}", path.display()));
+        }
+    };
+    let permissions = fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions());
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file path", path.display()))?;
+    let mut temporary_name = OsString::from(".");
+    temporary_name.push(file_name);
+    temporary_name.push(format!(".{}.tmp", process::id()));
+    let temporary_path = path.with_file_name(temporary_name);
+
+    let written = (|| {
+        let mut file = File::create(&temporary_path)?;
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.write_all(contents.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&temporary_path, path)
+    })();
+    if written.is_err() {
+        let _ = fs::remove_file(&temporary_path);
+    }
+    Ok(written?)
+}
+
+/// Create a new file in the temporary directory that only the current user can read, named with
+/// a prefix and a suffix, e.g. an extension.
+///
+/// The file is created with a name no file had, retrying until one is found, so another user of a
+/// shared temporary directory cannot plant a file or symlink at the name beforehand.
+pub fn create_temporary(prefix: &str, suffix: &str) -> Result<(PathBuf, File)> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let nanos = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .subsec_nanos();
+    for attempt in 0..TEMPORARY_ATTEMPTS {
+        let name = format!("{prefix}-{}-{nanos:x}-{attempt}{suffix}", process::id());
+        let path = env::temp_dir().join(name);
+        match options.open(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => {}
+            Err(error) => {
+                return Err(error).with_context(|| format!("failed to create {}", path.display()));
+            }
+        }
+    }
+    bail!("failed to create a temporary file after {TEMPORARY_ATTEMPTS} attempts")
+}
+
+/// How many names [`create_temporary`] tries.
+const TEMPORARY_ATTEMPTS: usize = 100;
+
+/// How often a followed file is checked for new lines once everything in it has been read.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Reads a file or named pipe as it is appended to, like `tail -f`, in batches of the complete
+/// lines that are available, so what is already in a file is read at once.
+pub struct Follower<R> {
+    reader: R,
+    /// The start of a line that has not been finished yet
+    partial: String,
+}
+
+impl Follower<BufReader<File>> {
+    /// Follow a file, which for a named pipe waits until it is opened for writing.
+    pub fn open(path: &Path) -> Result<Self> {
+        let file =
+            File::open(path).with_context(|| format!("failed to open {}", path.display()))?;
+        Ok(Self::new(BufReader::new(file)))
+    }
+}
+
+impl<R: BufRead> Follower<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            partial: String::new(),
+        }
+    }
+
+    /// The complete lines that can be read before the end of the file.
+    fn read_lines(&mut self) -> io::Result<Vec<String>> {
+        let mut lines = Vec::new();
+        while self.reader.read_line(&mut self.partial)? > 0 {
+            if let Some(line) = self.partial.strip_suffix('\n') {
+                lines.push(line.strip_suffix('\r').unwrap_or(line).to_owned());
+                self.partial.clear();
+            }
+        }
+        Ok(lines)
+    }
+}
+
+impl<R: BufRead> Iterator for Follower<R> {
+    type Item = io::Result<Vec<String>>;
+
+    /// The next batch of lines, waiting for one to be appended if there are none, so this never
+    /// ends.
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.read_lines() {
+                Ok(lines) if lines.is_empty() => thread::sleep(FOLLOW_INTERVAL),
+                result => return Some(result),
+            }
+        }
+    }
+}
+
+/// The path mirroring `path` under `directory`, with `suffix` appended to the file name.
+///
+/// `path` is normalized lexically, with each `..` removing the component before it, and absolute
+/// paths and `..` can't escape `directory`, e.g. `notes/a.soulver` is mirrored to
+/// `out/notes/a.soulver.txt` and `/home/a/../b.soulver` to `out/home/b.soulver.txt`.
+pub fn mirrored_path(directory: &Path, path: &Path, suffix: &str) -> PathBuf {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(component) => components.push(component),
+            Component::ParentDir => {
+                components.pop();
+            }
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+        }
+    }
+    let mut mirrored = directory
+        .join(components.iter().collect::<PathBuf>())
+        .into_os_string();
+    mirrored.push(suffix);
+    PathBuf::from(mirrored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match_star() {
+        assert!(glob_match("*.soulver", "budget.soulver"));
+        assert!(!glob_match("*.soulver", "budget.soulver.snap"));
+        assert!(!glob_match("*.soulver", "notes/budget.soulver"));
+    }
+
+    #[test]
+    fn test_glob_match_double_star() {
+        assert!(glob_match("**/*.soulver", "budget.soulver"));
+        assert!(glob_match("**/*.soulver", "notes/2024/budget.soulver"));
+        assert!(glob_match("notes/**", "notes/2024/budget.soulver"));
+        assert!(!glob_match("notes/**", "other/budget.soulver"));
+    }
+
+    #[test]
+    fn test_glob_match_question_mark() {
+        assert!(glob_match("202?.soulver", "2024.soulver"));
+        assert!(!glob_match("202?.soulver", "20245.soulver"));
+    }
+
+    #[test]
+    fn test_glob_match_set() {
+        assert!(glob_match("[a-c]*", "budget"));
+        assert!(!glob_match("[a-c]*", "expenses"));
+        assert!(glob_match("[!a-c]*", "expenses"));
+    }
+
+    #[test]
+    fn test_matches_glob_relative_path() {
+        let root = Path::new("vault");
+        assert!(matches_glob(
+            "finance/*.soulver",
+            root,
+            Path::new("vault/finance/budget.soulver"),
+        ));
+        assert!(matches_glob(
+            "*.soulver",
+            root,
+            Path::new("vault/finance/budget.soulver"),
+        ));
+    }
+
+    fn rules(base: &str, lines: &str) -> Vec<IgnoreRule> {
+        lines
+            .lines()
+            .filter_map(|line| IgnoreRule::parse(Path::new(base), line))
+            .collect()
+    }
+
+    #[test]
+    fn test_is_ignored_name() {
+        let rules = rules("vault", "# Scratch sheets\nscratch*.soulver");
+        assert!(is_ignored(
+            &rules,
+            Path::new("vault/scratch.soulver"),
+            false
+        ));
+        assert!(is_ignored(
+            &rules,
+            Path::new("vault/a/scratch-2.soulver"),
+            false
+        ));
+        assert!(!is_ignored(
+            &rules,
+            Path::new("vault/budget.soulver"),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_is_ignored_anchored() {
+        let rules = rules("vault", "/budget.soulver\ntemplates/*.soulver");
+        assert!(is_ignored(&rules, Path::new("vault/budget.soulver"), false));
+        assert!(!is_ignored(
+            &rules,
+            Path::new("vault/a/budget.soulver"),
+            false
+        ));
+        assert!(is_ignored(
+            &rules,
+            Path::new("vault/templates/a.soulver"),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_is_ignored_directory_only() {
+        let rules = rules("vault", "templates/");
+        assert!(is_ignored(&rules, Path::new("vault/a/templates"), true));
+        assert!(!is_ignored(&rules, Path::new("vault/a/templates"), false));
+    }
+
+    #[test]
+    fn test_is_ignored_negated() {
+        let rules = rules("vault", "*.soulver\n!budget.soulver");
+        assert!(is_ignored(
+            &rules,
+            Path::new("vault/scratch.soulver"),
+            false
+        ));
+        assert!(!is_ignored(
+            &rules,
+            Path::new("vault/budget.soulver"),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_split_records() {
+        assert_eq!(split_records("1 + 2\0Foo = 1\0"), vec!["1 + 2", "Foo = 1"]);
+        assert_eq!(split_records("1 + 2\0\0"), vec!["1 + 2", ""]);
+        assert_eq!(split_records(""), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_mirrored_path() {
+        assert_eq!(
+            mirrored_path(Path::new("out"), Path::new("notes/a.soulver"), ".txt"),
+            PathBuf::from("out/notes/a.soulver.txt"),
+        )
+    }
+
+    #[test]
+    fn test_mirrored_path_absolute() {
+        assert_eq!(
+            mirrored_path(Path::new("out"), Path::new("/home/a/../b.soulver"), ".txt"),
+            PathBuf::from("out/home/b.soulver.txt"),
+        );
+        assert_eq!(
+            mirrored_path(Path::new("out"), Path::new("../../b.soulver"), ".txt"),
+            PathBuf::from("out/b.soulver.txt"),
+        )
+    }
+
+    #[test]
+    fn test_create_temporary() {
+        let (first, mut file) = create_temporary("soulver-temporary", ".soulver").unwrap();
+        file.write_all(b"1 + 1").unwrap();
+        let (second, _) = create_temporary("soulver-temporary", ".soulver").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(&first).unwrap(), "1 + 1");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn test_read_sheets_unreadable() {
+        let directory = std::env::temp_dir().join(format!("soulver-read-sheets-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let present = directory.join("present.soulver");
+        let missing = directory.join("missing.soulver");
+        fs::write(&present, "1 + 1").unwrap();
+        let sheets = read_sheets(&[missing.clone(), present], false, None, false).unwrap();
+        assert_eq!(sheets.len(), 2);
+        let error = sheets[0].1.as_ref().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("failed to read {}", missing.display())
+        );
+        assert_eq!(sheets[1].0.contents, "1 + 1");
+        assert!(sheets[1].1.is_ok());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let directory =
+            std::env::temp_dir().join(format!("soulver-write-atomic-mode-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("sheet.soulver");
+        fs::write(&path, "1").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        write_atomic(&path, "2").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_symlink() {
+        let directory =
+            std::env::temp_dir().join(format!("soulver-write-atomic-link-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let target = directory.join("sheet.soulver");
+        let link = directory.join("link.soulver");
+        fs::write(&target, "1").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        write_atomic(&link, "2").unwrap();
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink()
+        );
+        assert_eq!(fs::read_to_string(&target).unwrap(), "2");
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let directory =
+            std::env::temp_dir().join(format!("soulver-write-atomic-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("out.txt");
+        write_atomic(&path, "1 | 1\n").unwrap();
+        write_atomic(&path, "2 | 2\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2 | 2\n");
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 1);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn test_follower() {
+        let mut follower = Follower::new("1 + 1\r\n2\n3".as_bytes());
+        assert_eq!(
+            follower.next().unwrap().unwrap(),
+            ["1 + 1".to_owned(), "2".to_owned()],
+        );
+        assert_eq!(follower.partial, "3");
+    }
+}
@@ -0,0 +1,853 @@
+//! A bitmap monospace font for rendering sheets to images, rasterized from DejaVu Sans Mono, which
+//! is free to use and redistribute under the Bitstream Vera license.
+
+pub const GLYPH_WIDTH: usize = 8;
+pub const GLYPH_HEIGHT: usize = 16;
+
+/// Each glyph's rows from the top, with the coverage of each pixel from 0 to 15 in a nibble, the
+/// leftmost pixel in the highest.
+const GLYPHS: [(char, [u32; GLYPH_HEIGHT]); 102] = [
+    (
+        ' ',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '!',
+        [
+            0x00000000, 0x00000000, 0x00066000, 0x00099000, 0x00099000, 0x00099000, 0x00099000,
+            0x00099000, 0x00077000, 0x00000000, 0x00066000, 0x00099000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '"',
+        [
+            0x00000000, 0x00000000, 0x00744700, 0x00B66B00, 0x00B66B00, 0x00A55A00, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '#',
+        [
+            0x00000000, 0x00000000, 0x00053080, 0x000D33C0, 0x002E0780, 0x3DEFDEED, 0x02A82F32,
+            0x00D33C00, 0x99F9BD92, 0x6AC6D861, 0x0970E100, 0x0D24C000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '$',
+        [
+            0x00000000, 0x00001000, 0x00028000, 0x0049B730, 0x06E7A880, 0x0B628000, 0x09B38000,
+            0x01AFE920, 0x0002A9D0, 0x000280F4, 0x063285E1, 0x06CEFC30, 0x00028000, 0x00028000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '%',
+        [
+            0x00000000, 0x00000000, 0x02400000, 0x5CAC0000, 0xB3095000, 0xA61B3012, 0x1AD739A3,
+            0x016B8300, 0x4A40BCD3, 0x0004904B, 0x0004A04A, 0x00009DC2, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '&',
+        [
+            0x00000000, 0x00000000, 0x007CC600, 0x05D33400, 0x07B00000, 0x03F20000, 0x05EC0000,
+            0x3E2B903A, 0x8802E54A, 0x99005EB6, 0x4E400BF1, 0x07FDEAC8, 0x00121000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '\'',
+        [
+            0x00000000, 0x00000000, 0x00065000, 0x00098000, 0x00098000, 0x00087000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '(',
+        [
+            0x00000000, 0x00000100, 0x0000A700, 0x0003E000, 0x00098000, 0x000E4000, 0x002F2000,
+            0x004F0000, 0x003F1000, 0x001F3000, 0x000C6000, 0x0007A000, 0x0001E200, 0x00006600,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        ')',
+        [
+            0x00000000, 0x00100000, 0x007A0000, 0x000E3000, 0x00099000, 0x0004E000, 0x0002F200,
+            0x0000F400, 0x0001F300, 0x0003F100, 0x0006D000, 0x000A7000, 0x002E1000, 0x00660000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '*',
+        [
+            0x00000000, 0x00000000, 0x00055000, 0x07266270, 0x02ABBA20, 0x005DD500, 0x09766790,
+            0x00066000, 0x00011000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '+',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00055000, 0x00088000, 0x00088000,
+            0x4BBDDBB4, 0x266AA662, 0x00088000, 0x00088000, 0x00022000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        ',',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x000BD000, 0x000CC000, 0x001F5000, 0x004B0000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '-',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00122100, 0x00ADDA00, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '.',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x000DB000, 0x000DB000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '/',
+        [
+            0x00000000, 0x00000000, 0x00000480, 0x00000C70, 0x00004E10, 0x0000B800, 0x0003F100,
+            0x000A8000, 0x002F2000, 0x009A0000, 0x01F30000, 0x08B00000, 0x1E400000, 0x14000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '0',
+        [
+            0x00000000, 0x00000000, 0x007CC700, 0x06E44E50, 0x0C8008C0, 0x0F4004F0, 0x2F3773F2,
+            0x2F3BB3F2, 0x1F4004F1, 0x0E6006E0, 0x08C11C80, 0x01BEEB10, 0x00022000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '1',
+        [
+            0x00000000, 0x00000000, 0x01699100, 0x05CAF200, 0x0004F200, 0x0004F200, 0x0004F200,
+            0x0004F200, 0x0004F200, 0x0004F200, 0x0025F420, 0x04FFFFF2, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '2',
+        [
+            0x00000000, 0x00000000, 0x06BDC600, 0x0B746F60, 0x000009B0, 0x000009B0, 0x00002E40,
+            0x0001C800, 0x000BA000, 0x00AB0000, 0x0AD32220, 0x0FFFFFD0, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '3',
+        [
+            0x00000000, 0x00000000, 0x07BDC600, 0x08546E70, 0x000008B0, 0x00000B90, 0x004BDA10,
+            0x00268E50, 0x000007D0, 0x000005F0, 0x15001CB0, 0x1DFDFC20, 0x00121000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '4',
+        [
+            0x00000000, 0x00000000, 0x00006910, 0x0003EF20, 0x000C7F20, 0x00794F20, 0x02D14F20,
+            0x0B604F20, 0x4E668F72, 0x4BBBCFC4, 0x00004F20, 0x00004F20, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '5',
+        [
+            0x00000000, 0x00000000, 0x06999920, 0x09A66610, 0x09800000, 0x09A65000, 0x09CBED20,
+            0x00001CA0, 0x000006D0, 0x000007D0, 0x14002D90, 0x1EFEFA10, 0x00221000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '6',
+        [
+            0x00000000, 0x00000000, 0x004BDB30, 0x04F84530, 0x0B800000, 0x0F346200, 0x2FBBAF60,
+            0x2F9007E0, 0x1F5003F2, 0x0E5003F1, 0x09B009D0, 0x01CEED30, 0x00022000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '7',
+        [
+            0x00000000, 0x00000000, 0x19999990, 0x16666BB0, 0x00000D60, 0x00004E10, 0x0000A900,
+            0x0001F400, 0x0007D000, 0x000D7000, 0x004F2000, 0x00AB0000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '8',
+        [
+            0x00000000, 0x00000000, 0x018CC810, 0x0AC33CA0, 0x0D6006D0, 0x0B8008B0, 0x02BBBB20,
+            0x06D77D60, 0x1F5005F1, 0x2F3003F2, 0x0E8008E0, 0x04EEEE40, 0x00022000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '9',
+        [
+            0x00000000, 0x00000000, 0x019DC600, 0x0BB34E60, 0x1F3007C0, 0x2F2006F0, 0x1F4008F2,
+            0x0AD56DF2, 0x007B94F0, 0x000007C0, 0x02103E60, 0x07FEF800, 0x00121000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        ':',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x000BA000, 0x000DB000,
+            0x00021000, 0x00000000, 0x00000000, 0x000DB000, 0x000DB000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        ';',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x000BA000, 0x000DB000,
+            0x00021000, 0x00000000, 0x00000000, 0x000BD000, 0x000CC000, 0x001F5000, 0x004B0000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '<',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000012, 0x00004AF5, 0x017DD720,
+            0x4FA40000, 0x3BE83000, 0x0028EC71, 0x000005B6, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '=',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x6FFFFFF6,
+            0x12222221, 0x26666662, 0x4BBBBBB4, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '>',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x21000000, 0x5FA40000, 0x027DD710,
+            0x00004AF4, 0x00038EB3, 0x17CE8200, 0x6B500000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '?',
+        [
+            0x00000000, 0x00000000, 0x028CD810, 0x05845E80, 0x000009B0, 0x00002E60, 0x0002E800,
+            0x000AA000, 0x000B8000, 0x00043000, 0x00085000, 0x000D8000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '@',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x007DDE70, 0x0AA101C4, 0x4C004469, 0x960ACAD9,
+            0xB33D0079, 0xD25A0049, 0xB32D1089, 0x8708DBD9, 0x3D103301, 0x08C30000, 0x005CED90,
+            0x00000100, 0x00000000,
+        ],
+    ),
+    (
+        'A',
+        [
+            0x00000000, 0x00000000, 0x00099000, 0x002EF200, 0x007BB700, 0x00B67B00, 0x01F22F10,
+            0x06D00D60, 0x0AD99DA0, 0x0E9889E0, 0x4F1001F4, 0x9B0000B9, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'B',
+        [
+            0x00000000, 0x00000000, 0x08999700, 0x0D966DA0, 0x0D6004F0, 0x0D6007E0, 0x0DDBBD40,
+            0x0D966CA0, 0x0D6001F4, 0x0D6000F6, 0x0D7228F2, 0x0DFFFC40, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'C',
+        [
+            0x00000000, 0x00000000, 0x002ADC80, 0x02E944B0, 0x0AB00000, 0x0E600000, 0x1F400000,
+            0x2F400000, 0x0F600000, 0x0B900000, 0x04F40060, 0x006EEED0, 0x00002200, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'D',
+        [
+            0x00000000, 0x00000000, 0x19997100, 0x2F86BE30, 0x2F400BB0, 0x2F4005F0, 0x2F4004F2,
+            0x2F4004F2, 0x2F4004F1, 0x2F4008D0, 0x2F526F50, 0x2FFFC500, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'E',
+        [
+            0x00000000, 0x00000000, 0x07999990, 0x0BB66660, 0x0B900000, 0x0B900000, 0x0BEBBBA0,
+            0x0BB66650, 0x0B900000, 0x0B900000, 0x0BA22220, 0x0BFFFFF2, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'F',
+        [
+            0x00000000, 0x00000000, 0x05999992, 0x08E66661, 0x08D00000, 0x08D00000, 0x08FBBB80,
+            0x08E66640, 0x08D00000, 0x08D00000, 0x08D00000, 0x08D00000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'G',
+        [
+            0x00000000, 0x00000000, 0x004BDB50, 0x04F745B0, 0x0D800000, 0x3F300000, 0x5F100000,
+            0x5F008FF2, 0x4F2014F2, 0x1F5002F2, 0x08D203F2, 0x009FDFA0, 0x00012100, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'H',
+        [
+            0x00000000, 0x00000000, 0x19200291, 0x2F4004F2, 0x2F4004F2, 0x2F4004F2, 0x2FCBBCF2,
+            0x2F8668F2, 0x2F4004F2, 0x2F4004F2, 0x2F4004F2, 0x2F4004F2, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'I',
+        [
+            0x00000000, 0x00000000, 0x07999960, 0x046BB640, 0x00099000, 0x00099000, 0x00099000,
+            0x00099000, 0x00099000, 0x00099000, 0x012AA210, 0x0BFFFF90, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'J',
+        [
+            0x00000000, 0x00000000, 0x00699920, 0x00467F40, 0x00002F40, 0x00002F40, 0x00002F40,
+            0x00002F40, 0x00002F40, 0x00002F30, 0x36007F00, 0x3DEDF600, 0x00221000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'K',
+        [
+            0x00000000, 0x00000000, 0x19200195, 0x2F401BA0, 0x2F41BB00, 0x2F4AB100, 0x2FDF4000,
+            0x2FC9D100, 0x2F41D900, 0x2F404F40, 0x2F4009D1, 0x2F4001D9, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'L',
+        [
+            0x00000000, 0x00000000, 0x06700000, 0x09B00000, 0x09B00000, 0x09B00000, 0x09B00000,
+            0x09B00000, 0x09B00000, 0x09B00000, 0x09C22221, 0x09FFFFF6, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'M',
+        [
+            0x00000000, 0x00000000, 0x59400494, 0x8EB00BE6, 0x8BD11DB6, 0x8B8678B6, 0x8B3BB3B6,
+            0x8B0DD0B6, 0x8B0440B6, 0x8B0000B6, 0x8B0000B6, 0x8B0000B6, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'N',
+        [
+            0x00000000, 0x00000000, 0x19700191, 0x2FF102F2, 0x2FB702F2, 0x2F5D02F2, 0x2F2C42F2,
+            0x2F26A2F2, 0x2F21E4F2, 0x2F209AF2, 0x2F203FF2, 0x2F200CF2, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'O',
+        [
+            0x00000000, 0x00000000, 0x007CC700, 0x07E55E70, 0x0E7007E0, 0x2F3003F2, 0x4F2002F4,
+            0x4F2002F4, 0x3F3003F3, 0x0F5005F0, 0x0AB00BA0, 0x01CEEC10, 0x00022000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'P',
+        [
+            0x00000000, 0x00000000, 0x07999810, 0x0BB66CD1, 0x0B9001F5, 0x0B9000F6, 0x0B9008F2,
+            0x0BFFFE60, 0x0BA22000, 0x0B900000, 0x0B900000, 0x0B900000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'Q',
+        [
+            0x00000000, 0x00000000, 0x007CC700, 0x07E55E70, 0x0E7007E0, 0x2F3003F2, 0x4F2002F4,
+            0x4F2002F4, 0x3F3003F3, 0x0F5005F0, 0x0AB00BA0, 0x01CEEC10, 0x00027E30, 0x00000640,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'R',
+        [
+            0x00000000, 0x00000000, 0x19999500, 0x2F868F70, 0x2F4008D0, 0x2F4008D0, 0x2F525E80,
+            0x2FEDF800, 0x2F404F30, 0x2F4009B0, 0x2F4002F4, 0x2F40009B, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'S',
+        [
+            0x00000000, 0x00000000, 0x018CDA40, 0x0AC44780, 0x1F300000, 0x1F500000, 0x09FB8300,
+            0x0049CF70, 0x000006F1, 0x000002F2, 0x081008E0, 0x0BFDED40, 0x00122000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'T',
+        [
+            0x00000000, 0x00000000, 0x79999997, 0x466BB664, 0x00099000, 0x00099000, 0x00099000,
+            0x00099000, 0x00099000, 0x00099000, 0x00099000, 0x00099000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'U',
+        [
+            0x00000000, 0x00000000, 0x09200290, 0x0F4004F0, 0x0F4004F0, 0x0F4004F0, 0x0F4004F0,
+            0x0F4004F0, 0x0F4004F0, 0x0F4004F0, 0x0C9009C0, 0x03DEED30, 0x00022000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'V',
+        [
+            0x00000000, 0x00000000, 0x58000085, 0x4F1001F4, 0x0E5005E0, 0x0B8008B0, 0x06D00D60,
+            0x02F11F20, 0x00C66C00, 0x00899800, 0x004DE400, 0x000EE000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'W',
+        [
+            0x00000000, 0x00000000, 0x93000039, 0xC600006C, 0xA800008A, 0x8A0CC0A8, 0x6B0EE0B6,
+            0x3D3BB3D3, 0x1F7777F1, 0x0EC44BE0, 0x0CF11FC0, 0x09C00C90, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'X',
+        [
+            0x00000000, 0x00000000, 0x29200094, 0x0BA007D1, 0x02F41E40, 0x007CAA00, 0x000DE100,
+            0x002EF300, 0x00AA8B00, 0x04E21E50, 0x1D7006E1, 0x8C0000C8, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'Y',
+        [
+            0x00000000, 0x00000000, 0x58000085, 0x2F4004F2, 0x07C00D70, 0x00D66D00, 0x005EE500,
+            0x000BB000, 0x00099000, 0x00099000, 0x00099000, 0x00099000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'Z',
+        [
+            0x00000000, 0x00000000, 0x08999995, 0x056668F5, 0x00000BA0, 0x00006E10, 0x0002E500,
+            0x000BA000, 0x006E1000, 0x01E50000, 0x0AB22221, 0x0FFFFFF9, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '[',
+        [
+            0x00000000, 0x00022100, 0x000FEA00, 0x000F4000, 0x000F4000, 0x000F4000, 0x000F4000,
+            0x000F4000, 0x000F4000, 0x000F4000, 0x000F4000, 0x000F4000, 0x000F5100, 0x000BB800,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '\\',
+        [
+            0x00000000, 0x00000000, 0x19100000, 0x0C700000, 0x05E00000, 0x00D60000, 0x006D0000,
+            0x000E5000, 0x0007C000, 0x0001E400, 0x00008B00, 0x00001F30, 0x000008A0, 0x00000140,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        ']',
+        [
+            0x00000000, 0x00122000, 0x00AEF000, 0x0004F000, 0x0004F000, 0x0004F000, 0x0004F000,
+            0x0004F000, 0x0004F000, 0x0004F000, 0x0004F000, 0x0004F000, 0x0015F000, 0x008BB000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '^',
+        [
+            0x00000000, 0x00000000, 0x00088000, 0x008CC800, 0x06D11D60, 0x2C2002C2, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '_',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x66666666, 0x22222222,
+        ],
+    ),
+    (
+        '`',
+        [
+            0x00000000, 0x00830000, 0x004D1000, 0x00057000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'a',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00466200, 0x09C9AE40, 0x010007B0,
+            0x00589BD0, 0x0BC669D0, 0x1F1007D0, 0x1F401DD0, 0x08FDE9D0, 0x00120000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'b',
+        [
+            0x00000000, 0x01100000, 0x0B800000, 0x0B800000, 0x0B846300, 0x0BDBAE60, 0x0BC006E0,
+            0x0B8002F2, 0x0B8000F4, 0x0B8002F1, 0x0BD108C0, 0x0BBEDE30, 0x00012000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'c',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00036510, 0x00AE9AC0, 0x05E10010,
+            0x0A900000, 0x0B800000, 0x09A00000, 0x04F40030, 0x006EDEB0, 0x00002200, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'd',
+        [
+            0x00000000, 0x00000110, 0x000006B0, 0x000006B0, 0x003646B0, 0x06FABCB0, 0x0E600CB0,
+            0x2F2008B0, 0x4F1007B0, 0x1F3008B0, 0x0C801DB0, 0x03DDEAB0, 0x00021000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'e',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00156300, 0x03EBAE60, 0x0D8004E0,
+            0x2F5444F3, 0x3F999992, 0x1F200000, 0x0BA00040, 0x01BEDEB0, 0x00012100, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'f',
+        [
+            0x00000000, 0x00000220, 0x0004EDB0, 0x000B8000, 0x034C8430, 0x079EB980, 0x000B6000,
+            0x000B6000, 0x000B6000, 0x000B6000, 0x000B6000, 0x000B6000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'g',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00364130, 0x05FABCB0, 0x0E600BB0,
+            0x2F2008B0, 0x3F1007B0, 0x1F3008B0, 0x0BA02DB0, 0x02CFD8B0, 0x000008A0, 0x03524D40,
+            0x03BDC600, 0x00000000,
+        ],
+    ),
+    (
+        'h',
+        [
+            0x00000000, 0x01100000, 0x0B800000, 0x0B800000, 0x0B836400, 0x0BCBAF60, 0x0BB008B0,
+            0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'i',
+        [
+            0x00000000, 0x00011000, 0x0008B000, 0x00034000, 0x01443000, 0x049CB000, 0x0008B000,
+            0x0008B000, 0x0008B000, 0x0008B000, 0x0008B000, 0x0BDEFDD2, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'j',
+        [
+            0x00000000, 0x00002000, 0x0002F200, 0x00016100, 0x00444000, 0x019AF200, 0x0002F200,
+            0x0002F200, 0x0002F200, 0x0002F200, 0x0002F200, 0x0002F200, 0x0002F100, 0x0349D000,
+            0x08BA2000, 0x00000000,
+        ],
+    ),
+    (
+        'k',
+        [
+            0x00000000, 0x01100000, 0x08B00000, 0x08B00000, 0x08B00141, 0x08B01D80, 0x08B1D800,
+            0x08CDA000, 0x08F8F400, 0x08B07D10, 0x08B00BB0, 0x08B002E7, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'l',
+        [
+            0x00000000, 0x02220000, 0x0BBF2000, 0x000F2000, 0x000F2000, 0x000F2000, 0x000F2000,
+            0x000F2000, 0x000F2000, 0x000F2000, 0x000D7000, 0x0004DFB0, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'm',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x13451630, 0x4EAED9F2, 0x4D09A0C5,
+            0x4B0890B6, 0x4B0890B6, 0x4B0890B6, 0x4B0890B6, 0x4B0890B6, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'n',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x03236400, 0x0BCBAF60, 0x0BB008B0,
+            0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'o',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00266200, 0x04EAAE40, 0x0C8008C0,
+            0x1F3003F1, 0x2F2002F2, 0x0F4004F0, 0x0BA00AB0, 0x02DEED20, 0x00022000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'p',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x03146300, 0x0BCBAF50, 0x0BC006D0,
+            0x0B8002F2, 0x0B7002F2, 0x0B8003F1, 0x0BD108C0, 0x0BAEDD30, 0x0B612000, 0x0B600000,
+            0x08400000, 0x00000000,
+        ],
+    ),
+    (
+        'q',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00264130, 0x04FABDD0, 0x0C800BD0,
+            0x1F3006D0, 0x2F2006D0, 0x0F4007D0, 0x0BA00CD0, 0x02DDDAD0, 0x000316D0, 0x000006D0,
+            0x000004A0, 0x00000000,
+        ],
+    ),
+    (
+        'r',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00221661, 0x0099DAB8, 0x009E2001,
+            0x009A0000, 0x00980000, 0x00980000, 0x00980000, 0x00980000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        's',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00266400, 0x04EA9C60, 0x09A00000,
+            0x06E63000, 0x007BED30, 0x00000B90, 0x03100A90, 0x08FDEC20, 0x00122000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        't',
+        [
+            0x00000000, 0x00000000, 0x00160000, 0x004F0000, 0x047F4430, 0x19BF9970, 0x004F0000,
+            0x004F0000, 0x004F0000, 0x004F0000, 0x002F3000, 0x0008EFB0, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'u',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x03200130, 0x0B8006D0, 0x0B8006D0,
+            0x0B8006D0, 0x0B8006D0, 0x0B8006D0, 0x09B01CD0, 0x03EED9D0, 0x00120000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'v',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x14000041, 0x1F2002F1, 0x0B8008B0,
+            0x05D00D50, 0x01F33F10, 0x00A88A00, 0x005DD500, 0x000EE000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'w',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x41000014, 0xC500005C, 0x98011089,
+            0x5C0BA0C5, 0x2F0CC0F2, 0x0D7997D0, 0x0AE44DA0, 0x06E00E60, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'x',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x14100141, 0x0A900AA0, 0x01D56D10,
+            0x003EE300, 0x001DD100, 0x009AB900, 0x06D11D60, 0x3E4004E3, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        'y',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x14000032, 0x1F4001F3, 0x099007C0,
+            0x04E00C60, 0x00D63F10, 0x007B8900, 0x001FE400, 0x000AD000, 0x000B7000, 0x037F1000,
+            0x08B40000, 0x00000000,
+        ],
+    ),
+    (
+        'z',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x02444430, 0x05999DB0, 0x00003E30,
+            0x0001D600, 0x000B9000, 0x009C0000, 0x05E10000, 0x09FFFFB0, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '{',
+        [
+            0x00000000, 0x00000110, 0x0002DE80, 0x0007B000, 0x00089000, 0x00089000, 0x000A9000,
+            0x058D4000, 0x059D3000, 0x000A9000, 0x00089000, 0x00089000, 0x0007B000, 0x0003EC70,
+            0x00000210, 0x00000000,
+        ],
+    ),
+    (
+        '|',
+        [
+            0x00000000, 0x00011000, 0x00088000, 0x00088000, 0x00088000, 0x00088000, 0x00088000,
+            0x00088000, 0x00088000, 0x00088000, 0x00088000, 0x00088000, 0x00088000, 0x00088000,
+            0x00088000, 0x00011000,
+        ],
+    ),
+    (
+        '}',
+        [
+            0x00000000, 0x01100000, 0x08ED2000, 0x000B7000, 0x00098000, 0x00098000, 0x00099000,
+            0x0004D850, 0x0003D950, 0x00099000, 0x00098000, 0x00098000, 0x000B7000, 0x07CE3000,
+            0x01200000, 0x00000000,
+        ],
+    ),
+    (
+        '~',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x3DFD8475, 0x44039B91, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '£',
+        [
+            0x00000000, 0x00000000, 0x0005CDA1, 0x003F7261, 0x007D0000, 0x009B0000, 0x009B0000,
+            0x0BEFDD20, 0x009B0000, 0x009B0000, 0x02AC2220, 0x2FFFFFF4, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '€',
+        [
+            0x00000000, 0x00000000, 0x0018DC70, 0x00BB3490, 0x04F10000, 0x19C22100, 0x5DC88500,
+            0x2CB43000, 0x5CD86000, 0x06D00000, 0x01E70040, 0x004DEEB0, 0x00002200, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '×',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x0B7007B0, 0x02E77E20,
+            0x002EE200, 0x007EE700, 0x07E22E70, 0x09200280, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '÷',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00044000, 0x000BB000, 0x00033000,
+            0x4BBBBBB4, 0x26666662, 0x00077000, 0x000BB000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '°',
+        [
+            0x00000000, 0x00000000, 0x004CC400, 0x00D22D00, 0x00C00C00, 0x009BB800, 0x00033000,
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '–',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x22222222, 0xBBBBBBBB, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+    (
+        '−',
+        [
+            0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x4BBBBBB4, 0x26666662, 0x00000000, 0x00000000, 0x00000000, 0x00000000, 0x00000000,
+            0x00000000, 0x00000000,
+        ],
+    ),
+];
+
+/// The coverage of each pixel of a character's glyph, or of `?` if the font does not have one.
+pub fn glyph(c: char) -> [[u8; GLYPH_WIDTH]; GLYPH_HEIGHT] {
+    let rows = GLYPHS
+        .iter()
+        .find(|(glyph, _)| *glyph == c)
+        .or_else(|| GLYPHS.iter().find(|(glyph, _)| *glyph == '?'))
+        .map(|(_, rows)| rows)
+        .unwrap();
+    rows.map(|row| {
+        std::array::from_fn(|column| ((row >> (4 * (GLYPH_WIDTH - 1 - column))) & 0xF) as u8)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph() {
+        assert!(glyph(' ').iter().flatten().all(|coverage| *coverage == 0));
+        // The bar is drawn down the middle of its glyph
+        assert_eq!(glyph('|')[8][0], 0);
+        assert!(glyph('|')[8][3] > 0);
+        assert_eq!(glyph('✓'), glyph('?'));
+    }
+}
@@ -0,0 +1,213 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result, bail};
+
+use crate::dates::Date;
+use crate::json;
+
+/// The environment variable to read the history journal from instead of the default path.
+pub const HISTORY_PATH_VARIABLE: &str = "SOULVER_CLI_ZIPPER_HISTORY";
+
+/// A `calculate` run recorded in the history journal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// When the sheet was calculated, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// The working directory the sheet was calculated in
+    pub directory: String,
+    pub input: String,
+    pub output: String,
+}
+
+impl Entry {
+    /// An entry for a sheet calculated now in the working directory.
+    pub fn now(input: &str, output: &str) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs());
+        let directory = env::current_dir()
+            .map(|directory| directory.display().to_string())
+            .unwrap_or_default();
+        Self {
+            timestamp,
+            directory,
+            input: input.to_owned(),
+            output: output.to_owned(),
+        }
+    }
+
+    pub fn to_json(&self) -> json::Value {
+        json::Value::object([
+            ("timestamp", json::Value::Number(self.timestamp as f64)),
+            ("directory", json::Value::from(self.directory.as_str())),
+            ("input", json::Value::from(self.input.as_str())),
+            ("output", json::Value::from(self.output.as_str())),
+        ])
+    }
+
+    pub fn from_json(value: &json::Value) -> Result<Self> {
+        let string = |key| match value.get(key) {
+            Some(json::Value::String(string)) => Ok(string.clone()),
+            _ => bail!("expected a string {key}"),
+        };
+        let Some(json::Value::Number(timestamp)) = value.get("timestamp") else {
+            bail!("expected a numeric timestamp");
+        };
+        Ok(Self {
+            timestamp: *timestamp as u64,
+            directory: string("directory")?,
+            input: string("input")?,
+            output: string("output")?,
+        })
+    }
+
+    /// The first line of the input with any text, to summarise the entry with.
+    pub fn title(&self) -> &str {
+        self.input
+            .lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty())
+            .unwrap_or("")
+    }
+}
+
+/// Summarise an entry for `history list`, with its number.
+pub fn summary(number: usize, entry: &Entry) -> String {
+    format!(
+        "{number:>4}  {}  {}  {}",
+        format_timestamp(entry.timestamp),
+        entry.directory,
+        entry.title(),
+    )
+}
+
+/// The numbers of the entries whose input or output contains a term, ignoring case.
+pub fn search(entries: &[Entry], term: &str) -> Vec<usize> {
+    let term = term.to_lowercase();
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            entry.input.to_lowercase().contains(&term)
+                || entry.output.to_lowercase().contains(&term)
+        })
+        .map(|(index, _)| index + 1)
+        .collect()
+}
+
+/// Format a timestamp in UTC, e.g. `June 1, 2024 14:05 UTC`.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let date = Date::from_days_since_epoch((timestamp / 86_400) as i64);
+    let seconds_of_day = timestamp % 86_400;
+    let (hours, minutes) = (seconds_of_day / 3600, seconds_of_day % 3600 / 60);
+    format!("{date} {hours:02}:{minutes:02} UTC")
+}
+
+/// The path of the history journal, a JSON object for each entry on each line.
+pub fn path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(HISTORY_PATH_VARIABLE) {
+        return Some(path.into());
+    }
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| Some(Path::new(&env::var_os("HOME")?).join(".local/share")))?;
+    Some(data_home.join(env!("CARGO_PKG_NAME")).join("history.jsonl"))
+}
+
+/// Append an entry to the history journal.
+pub fn append(entry: &Entry) -> Result<()> {
+    let path = path().context("could not find the history journal's path")?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("failed to open {}", path.display()))?;
+    writeln!(file, "{}", entry.to_json())?;
+    Ok(())
+}
+
+/// Parse a history journal.
+pub fn parse(text: &str) -> Result<Vec<Entry>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            Entry::from_json(&json::parse(line)?).with_context(|| format!("line {}", index + 1))
+        })
+        .collect()
+}
+
+/// Read the history journal, which is empty if nothing has been recorded.
+pub fn read() -> Result<Vec<Entry>> {
+    let Some(path) = path() else {
+        return Ok(Vec::new());
+    };
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    parse(&text).with_context(|| format!("invalid history {}", path.display()))
+}
+
+/// Get an entry by its number in `history list`, counting from 1.
+pub fn get(entries: Vec<Entry>, number: usize) -> Result<Entry> {
+    let count = entries.len();
+    match number
+        .checked_sub(1)
+        .and_then(|index| entries.into_iter().nth(index))
+    {
+        Some(entry) => Ok(entry),
+        None => bail!("there is no history entry {number} (there are {count})"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let entry = Entry {
+            timestamp: 1_717_250_700,
+            directory: "/home/user".to_owned(),
+            input: "\n# Rent\n£1000 * 12".to_owned(),
+            output: "# Rent     |\n£1000 * 12 | £12,000.00".to_owned(),
+        };
+        let text = format!("{}\n\n{}\n", entry.to_json(), entry.to_json());
+        assert_eq!(parse(&text).unwrap(), [entry.clone(), entry.clone()]);
+        assert_eq!(entry.title(), "# Rent");
+        assert!(parse("{\"timestamp\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let entry = |input: &str, output: &str| Entry {
+            timestamp: 0,
+            directory: String::new(),
+            input: input.to_owned(),
+            output: output.to_owned(),
+        };
+        let entries = [
+            entry("Rent = £1000", "Rent = £1000 | £1,000.00"),
+            entry("5 km in miles", "5 km in miles | 3.11 mi"),
+            entry("1 + 2", "1 + 2 | 3"),
+        ];
+        assert_eq!(search(&entries, "RENT"), [1]);
+        assert_eq!(search(&entries, "3"), [2, 3]);
+        assert!(search(&entries, "BTC").is_empty());
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(1_717_250_700), "June 1, 2024 14:05 UTC");
+    }
+}
@@ -0,0 +1,120 @@
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result, bail};
+
+use crate::json;
+use crate::plugin;
+use crate::soulver::Line;
+
+/// Shell commands `calculate` runs on each sheet before calculating it and on its results before
+/// formatting them, set in the config file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Hooks {
+    /// Given the sheet on stdin, outputs the sheet to calculate instead
+    pub pre: Option<String>,
+    /// Given the rows as a JSON array of objects with `input` and `output`, outputs the rows to
+    /// format instead
+    pub post: Option<String>,
+}
+
+static HOOKS: OnceLock<Hooks> = OnceLock::new();
+
+/// Run hooks for the rest of the process.
+pub fn set(hooks: Hooks) {
+    HOOKS.set(hooks).expect("hooks are only set once");
+}
+
+/// Run a hook with `sh`, giving it text on stdin and returning its stdout.
+fn run(name: &str, command: &str, input: &str) -> Result<String> {
+    let child = Command::new("sh")
+        .args(["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("failed to run the {name} hook"))?;
+    let output = plugin::communicate(child, input.as_bytes())?;
+    if !output.status.success() {
+        bail!("the {name} hook failed with {}", output.status);
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("the {name} hook output invalid UTF-8"))
+}
+
+/// Rewrite a sheet with the pre hook, if there is one.
+pub fn pre(sheet: String) -> Result<String> {
+    match HOOKS.get().and_then(|hooks| hooks.pre.as_deref()) {
+        Some(command) => run("pre", command, &sheet),
+        None => Ok(sheet),
+    }
+}
+
+/// Parse rows from a JSON array of objects with `input` and `output`.
+fn parse_rows(value: &json::Value) -> Result<Vec<Line>> {
+    let json::Value::Array(rows) = value else {
+        bail!("expected an array of rows");
+    };
+    rows.iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let field = |key| match row.get(key) {
+                Some(json::Value::String(text)) => Ok(text.clone()),
+                None => Ok(String::new()),
+                Some(_) => bail!("{key} of row {} is not a string", index + 1),
+            };
+            Ok(Line {
+                input: field("input")?,
+                output: field("output")?,
+            })
+        })
+        .collect()
+}
+
+/// Whether there is a post hook, which needs the rows of each sheet.
+pub fn has_post() -> bool {
+    HOOKS.get().is_some_and(|hooks| hooks.post.is_some())
+}
+
+/// Transform a sheet's rows with the post hook, if there is one.
+pub fn post(lines: Vec<Line>) -> Result<Vec<Line>> {
+    let Some(command) = HOOKS.get().and_then(|hooks| hooks.post.as_deref()) else {
+        return Ok(lines);
+    };
+    let output = run("post", command, &plugin::rows(&lines).to_string())?;
+    let value = json::parse(&output).context("the post hook output invalid JSON")?;
+    parse_rows(&value).context("the post hook output invalid rows")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rows() {
+        let value = json::parse(r##"[{"input": "1 + 1", "output": "2"}, {"input": "# A"}]"##);
+        assert_eq!(
+            parse_rows(&value.unwrap()).unwrap(),
+            vec![
+                Line {
+                    input: "1 + 1".to_owned(),
+                    output: "2".to_owned(),
+                },
+                Line {
+                    input: "# A".to_owned(),
+                    output: String::new(),
+                },
+            ],
+        );
+        assert!(parse_rows(&json::parse(r#"{"input": "1"}"#).unwrap()).is_err());
+        assert!(parse_rows(&json::parse(r#"[{"output": 2}]"#).unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(run("pre", "tr a-z A-Z", "abc\n").unwrap(), "ABC\n");
+        assert!(run("pre", "exit 1", "").is_err());
+        // A hook that outputs as it reads is given all of a long sheet
+        let sheet = "1 + 1\n".repeat(100_000);
+        assert_eq!(run("pre", "cat", &sheet).unwrap(), sheet);
+    }
+}
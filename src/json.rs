@@ -0,0 +1,363 @@
+use std::fmt::{self, Write};
+
+use anyhow::{Context, Result, bail};
+
+/// A JSON value, serialized compactly with [`Display`](fmt::Display).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    /// An object, keeping its keys in insertion order
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The value of a key, if this is an object with it.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Self::Object(entries) => entries
+                .iter()
+                .find(|(other, _)| other == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    /// Build an object from key-value pairs.
+    pub fn object<K, V, I>(entries: I) -> Self
+    where
+        K: Into<String>,
+        V: Into<Value>,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        Self::Object(
+            entries
+                .into_iter()
+                .map(|(key, value)| (key.into(), value.into()))
+                .collect(),
+        )
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Self::Number(value)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Self::Number(value as f64)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_owned())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Self::Null, Into::into)
+    }
+}
+
+impl<T: Into<Value>> From<Vec<T>> for Value {
+    fn from(value: Vec<T>) -> Self {
+        Self::Array(value.into_iter().map(Into::into).collect())
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in string.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("null"),
+            Self::Bool(value) => write!(f, "{value}"),
+            Self::Number(value) if value.is_finite() => write!(f, "{value}"),
+            Self::Number(_) => f.write_str("null"),
+            Self::String(value) => write_string(f, value),
+            Self::Array(values) => {
+                f.write_char('[')?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                f.write_char(']')
+            }
+            Self::Object(entries) => {
+                f.write_char('{')?;
+                for (index, (key, value)) in entries.iter().enumerate() {
+                    if index > 0 {
+                        f.write_char(',')?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{value}")?;
+                }
+                f.write_char('}')
+            }
+        }
+    }
+}
+
+/// A parser for JSON text.
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self
+            .peek()
+            .is_some_and(|c| matches!(c, ' ' | '\t' | '\n' | '\r'))
+        {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<()> {
+        if !self.text[self.position..].starts_with(expected) {
+            bail!("expected `{expected}` at byte {}", self.position);
+        }
+        self.position += expected.len();
+        Ok(())
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        let value = match self.peek() {
+            Some('n') => self.expect("null").map(|()| Value::Null)?,
+            Some('t') => self.expect("true").map(|()| Value::Bool(true))?,
+            Some('f') => self.expect("false").map(|()| Value::Bool(false))?,
+            Some('"') => Value::String(self.parse_string()?),
+            Some('[') => {
+                self.next();
+                let mut values = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.next();
+                } else {
+                    loop {
+                        values.push(self.parse_value()?);
+                        match self.next() {
+                            Some(',') => {}
+                            Some(']') => break,
+                            _ => bail!("expected `,` or `]` at byte {}", self.position),
+                        }
+                    }
+                }
+                Value::Array(values)
+            }
+            Some('{') => {
+                self.next();
+                let mut entries = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.next();
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.parse_string()?;
+                        self.skip_whitespace();
+                        self.expect(":")?;
+                        entries.push((key, self.parse_value()?));
+                        match self.next() {
+                            Some(',') => {}
+                            Some('}') => break,
+                            _ => bail!("expected `,` or `}}` at byte {}", self.position),
+                        }
+                    }
+                }
+                Value::Object(entries)
+            }
+            Some(c) if c == '-' || c.is_ascii_digit() => {
+                let start = self.position;
+                while self
+                    .peek()
+                    .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.next();
+                }
+                let number = &self.text[start..self.position];
+                Value::Number(
+                    number
+                        .parse()
+                        .with_context(|| format!("invalid number {number:?}"))?,
+                )
+            }
+            _ => bail!("expected a value at byte {}", self.position),
+        };
+        self.skip_whitespace();
+        Ok(value)
+    }
+
+    fn parse_string(&mut self) -> Result<String> {
+        self.expect("\"")?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                None => bail!("unterminated string"),
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(match self.next() {
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some('/') => '/',
+                    Some('b') => '\u{8}',
+                    Some('f') => '\u{c}',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('t') => '\t',
+                    Some('u') => self.parse_unicode_escape()?,
+                    c => bail!("invalid escape {c:?}"),
+                }),
+                Some(c) => string.push(c),
+            }
+        }
+    }
+
+    fn parse_hex(&mut self) -> Result<u32> {
+        let digits = self
+            .text
+            .get(self.position..self.position + 4)
+            .context("truncated unicode escape")?;
+        self.position += 4;
+        u32::from_str_radix(digits, 16).with_context(|| format!("invalid escape {digits:?}"))
+    }
+
+    /// Parse the hex digits of a `\\u` escape, including a following low surrogate if needed.
+    fn parse_unicode_escape(&mut self) -> Result<char> {
+        let high = self.parse_hex()?;
+        let code_point = if (0xd800..0xdc00).contains(&high) {
+            self.expect("\\u")?;
+            let low = self.parse_hex()?;
+            0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00) & 0x3ff)
+        } else {
+            high
+        };
+        char::from_u32(code_point).context("invalid unicode escape")
+    }
+}
+
+/// Parse JSON text.
+pub fn parse(text: &str) -> Result<Value> {
+    let mut parser = Parser { text, position: 0 };
+    let value = parser.parse_value()?;
+    if parser.position < text.len() {
+        bail!("unexpected trailing characters at byte {}", parser.position);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_string_escapes() {
+        assert_eq!(
+            Value::from("\"a\"\\\n\u{1}").to_string(),
+            r#""\"a\"\\\n\u0001""#,
+        )
+    }
+
+    #[test]
+    fn test_display_numbers() {
+        assert_eq!(
+            Value::from(vec![
+                Value::from(3_usize),
+                Value::from(1.5),
+                Value::from(f64::NAN)
+            ])
+            .to_string(),
+            "[3,1.5,null]",
+        )
+    }
+
+    #[test]
+    fn test_display_object() {
+        assert_eq!(
+            Value::object([("input", Value::from("1 + 2")), ("output", Value::Null)]).to_string(),
+            r#"{"input":"1 + 2","output":null}"#,
+        )
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(r#" {"a": [1, -2.5e1, true, null], "b": "x\"\u00e9\ud83d\ude00", "c": {}} "#)
+                .unwrap(),
+            Value::object([
+                (
+                    "a",
+                    Value::from(vec![
+                        Value::from(1.0),
+                        Value::from(-25.0),
+                        Value::from(true),
+                        Value::Null,
+                    ]),
+                ),
+                ("b", Value::from("x\"é😀")),
+                ("c", Value::Object(Vec::new())),
+            ]),
+        )
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        let value = Value::object([("input", Value::from("1\n2")), ("output", Value::Null)]);
+        assert_eq!(parse(&value.to_string()).unwrap(), value)
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("").is_err());
+        assert!(parse("[1,]").is_err());
+        assert!(parse("{\"a\" 1}").is_err());
+        assert!(parse("\"a").is_err());
+        assert!(parse("1 2").is_err());
+    }
+}
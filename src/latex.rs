@@ -0,0 +1,87 @@
+use crate::soulver::{self, Line};
+use crate::totals;
+
+/// Escape the characters LaTeX treats specially, so text is typeset as it is.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str(r"\textbackslash{}"),
+            '~' => escaped.push_str(r"\textasciitilde{}"),
+            '^' => escaped.push_str(r"\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Format lines as a `booktabs` `tabular` of inputs and results, with `#` headings spanning both
+/// columns in bold and `---` dividers as rules.
+pub fn table(lines: &[Line]) -> String {
+    let mut table = vec![
+        r"\begin{tabular}{lr}".to_owned(),
+        r"\toprule".to_owned(),
+        r"Input & Result \\".to_owned(),
+        r"\midrule".to_owned(),
+    ];
+    for line in lines {
+        if let Some(heading) = totals::heading(line) {
+            table.push(format!(
+                r"\multicolumn{{2}}{{l}}{{\textbf{{{}}}}} \\",
+                escape(heading)
+            ));
+        } else if soulver::is_divider(&line.input) {
+            table.push(r"\midrule".to_owned());
+        } else {
+            table.push(format!(
+                r"{} & {} \\",
+                escape(&line.input),
+                escape(&line.output)
+            ));
+        }
+    }
+    table.push(r"\bottomrule".to_owned());
+    table.push(r"\end{tabular}".to_owned());
+    table.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("50% of $10_000"), r"50\% of \$10\_000");
+        assert_eq!(
+            escape(r"a\b ~ 2^3 {x}"),
+            r"a\textbackslash{}b \textasciitilde{} 2\textasciicircum{}3 \{x\}"
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let lines = [
+            Line::new("# Food & drink", ""),
+            Line::new("lunch: $12 + $3", "$15.00"),
+            Line::new("---", ""),
+            Line::new("2 + 2", "4"),
+        ];
+        assert_eq!(
+            table(&lines),
+            r"\begin{tabular}{lr}
+\toprule
+Input & Result \\
+\midrule
+\multicolumn{2}{l}{\textbf{Food \& drink}} \\
+lunch: \$12 + \$3 & \$15.00 \\
+\midrule
+2 + 2 & 4 \\
+\bottomrule
+\end{tabular}"
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Zipping the lines of a sheet with the results calculated for them, without running `soulver`.
+
+/// How [`zip_lines`] formats a table of inputs and results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZipOptions {
+    /// Extend divider lines like `---` without results across the whole table
+    pub extend_dividers: bool,
+}
+
+impl Default for ZipOptions {
+    fn default() -> Self {
+        Self {
+            extend_dividers: true,
+        }
+    }
+}
+
+/// Whether a line is a horizontal rule like `----`, which `soulver` outputs nothing for.
+pub fn is_divider(line: &str) -> bool {
+    let trimmed = line.trim();
+    trimmed.len() >= 3 && trimmed.chars().all(|c| c == '-')
+}
+
+/// Format inputs and their results as a table, with the results aligned after a `|` column.
+///
+/// An input without a result, either `None`, empty, or missing from the end of `outputs`, is
+/// followed by the column on its own.
+pub fn zip_lines(inputs: &[&str], outputs: &[Option<String>], options: &ZipOptions) -> String {
+    let output = |index: usize| {
+        outputs
+            .get(index)
+            .and_then(Option::as_deref)
+            .filter(|output| !output.is_empty())
+    };
+    let longest_input_line_length = inputs
+        .iter()
+        .map(|input| input.chars().count())
+        .max()
+        .unwrap_or(0);
+    let longest_output_line_length = (0..inputs.len())
+        .filter_map(output)
+        .map(|output| output.chars().count())
+        .max()
+        .unwrap_or(0);
+    let table_width = if longest_output_line_length == 0 {
+        longest_input_line_length + " |".len()
+    } else {
+        longest_input_line_length + " | ".len() + longest_output_line_length
+    };
+
+    let mut out = String::new();
+    for (index, input) in inputs.iter().enumerate() {
+        match output(index) {
+            None if options.extend_dividers && is_divider(input) => {
+                out.push_str(&"-".repeat(table_width));
+            }
+            None => out.push_str(&format!(
+                "{input:<width$} |",
+                width = longest_input_line_length,
+            )),
+            Some(output) => out.push_str(&format!(
+                "{input:<width$} | {output}",
+                width = longest_input_line_length,
+            )),
+        }
+        out.push('\n');
+    }
+    if out.ends_with('\n') {
+        out.pop();
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_lines() {
+        let outputs = [Some("10".to_owned()), None, Some("1,000".to_owned())];
+        assert_eq!(
+            zip_lines(
+                &["Foo = 10", "----", "Foo * 100"],
+                &outputs,
+                &ZipOptions::default()
+            ),
+            "Foo = 10  | 10\n-----------------\nFoo * 100 | 1,000",
+        );
+        assert_eq!(
+            zip_lines(
+                &["Foo = 10", "----", "Foo * 100"],
+                &outputs,
+                &ZipOptions {
+                    extend_dividers: false
+                }
+            ),
+            "Foo = 10  | 10\n----      |\nFoo * 100 | 1,000",
+        );
+    }
+
+    #[test]
+    fn test_zip_lines_missing_outputs() {
+        assert_eq!(
+            zip_lines(
+                &["# £", "1", "---"],
+                &[Some(String::new()), Some("1".to_owned())],
+                &ZipOptions::default()
+            ),
+            "# £ |\n1   | 1\n-------",
+        );
+        assert_eq!(zip_lines(&[], &[], &ZipOptions::default()), "");
+    }
+
+    #[test]
+    fn test_zip_lines_aligns_results() {
+        // Every result starts in the same column, however long the inputs are
+        let inputs = ["a", "£££", "bb", "long input"];
+        let outputs: Vec<Option<String>> = inputs
+            .iter()
+            .map(|input| Some(input.to_uppercase()))
+            .collect();
+        let zipped = zip_lines(&inputs, &outputs, &ZipOptions::default());
+        for (line, input) in zipped.lines().zip(inputs) {
+            let (before, after) = line.split_once(" | ").unwrap();
+            assert_eq!(before.chars().count(), "long input".len());
+            assert_eq!(before.trim_end(), input);
+            assert_eq!(after, input.to_uppercase());
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
+
+use crate::bundle;
+use crate::config::Config;
+use crate::json;
+
+/// Where Soulver 3 keeps its document library, relative to the home directory: in iCloud Drive,
+/// then locally in the app's container.
+const LIBRARY_DIRECTORIES: [&str; 2] = [
+    "Library/Mobile Documents/iCloud~app~soulver~mac/Documents",
+    "Library/Containers/app.soulver.mac/Data/Documents",
+];
+
+/// The extension of plain text sheets found in library directories, alongside bundles.
+const SHEET_EXTENSION: &str = "soulver";
+
+/// A sheet found in a library directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LibrarySheet {
+    /// The name the sheet is shown with in the app, which is its file name without the extension
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl LibrarySheet {
+    pub fn to_json(&self) -> json::Value {
+        json::Value::object([
+            ("name", json::Value::from(self.name.as_str())),
+            ("path", json::Value::from(self.path.display().to_string())),
+        ])
+    }
+}
+
+/// How `list` outputs sheets.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListFormat {
+    /// The name and path of each sheet, separated by a tab
+    Plain,
+    /// A JSON array of objects with each sheet's `name` and `path`
+    Json,
+}
+
+/// Format sheets for `list`.
+pub fn format_sheets(sheets: &[LibrarySheet], format: ListFormat) -> String {
+    match format {
+        ListFormat::Plain => sheets
+            .iter()
+            .map(|sheet| format!("{}\t{}", sheet.name, sheet.path.display()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        ListFormat::Json => {
+            json::Value::from(sheets.iter().map(LibrarySheet::to_json).collect::<Vec<_>>())
+                .to_string()
+        }
+    }
+}
+
+/// The library directories to look for sheets in: the `library` directory set in the config file,
+/// or the Soulver 3 library's locations that exist.
+pub fn directories(config: &Config) -> Vec<PathBuf> {
+    if let Some(library) = &config.library {
+        return vec![library.clone()];
+    }
+    let Some(home) = std::env::var_os("HOME") else {
+        return Vec::new();
+    };
+    LIBRARY_DIRECTORIES
+        .iter()
+        .map(|directory| Path::new(&home).join(directory))
+        .filter(|directory| directory.is_dir())
+        .collect()
+}
+
+fn walk(directory: &Path, out: &mut Vec<LibrarySheet>) -> Result<()> {
+    let mut entries = fs::read_dir(directory)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort();
+    for path in entries {
+        let is_sheet = bundle::is_bundle(&path)
+            || path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|extension| extension == SHEET_EXTENSION);
+        if is_sheet {
+            let name = path
+                .file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            out.push(LibrarySheet { name, path });
+        } else if path.is_dir() {
+            walk(&path, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// The sheets in library directories and their subdirectories, sorted by path within each.
+pub fn sheets(directories: &[PathBuf]) -> Result<Vec<LibrarySheet>> {
+    let mut out = Vec::new();
+    for directory in directories {
+        walk(directory, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// Find the path of the sheet with a name, ignoring case.
+pub fn find(directories: &[PathBuf], name: &str) -> Result<PathBuf> {
+    if directories.is_empty() {
+        bail!("could not find the Soulver library (set `library` in the config file)");
+    }
+    let mut matches: Vec<LibrarySheet> = sheets(directories)?
+        .into_iter()
+        .filter(|sheet| sheet.name.to_lowercase() == name.to_lowercase())
+        .collect();
+    match matches.len() {
+        0 => bail!("there is no sheet named {name:?} in the Soulver library"),
+        1 => Ok(matches.remove(0).path),
+        _ => {
+            let paths: Vec<String> = matches
+                .iter()
+                .map(|sheet| sheet.path.display().to_string())
+                .collect();
+            bail!(
+                "there are multiple sheets named {name:?}: {}",
+                paths.join(", ")
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find() {
+        let directory =
+            std::env::temp_dir().join(format!("soulver-library-{}", std::process::id()));
+        fs::create_dir_all(directory.join("Finances/Monthly Budget.sheet")).unwrap();
+        fs::create_dir_all(directory.join("Work")).unwrap();
+        fs::write(directory.join("Work/Invoice.soulver"), "1 + 2").unwrap();
+        fs::write(directory.join("Work/Notes.txt"), "").unwrap();
+        let directories = [directory.clone()];
+
+        let names: Vec<String> = sheets(&directories)
+            .unwrap()
+            .into_iter()
+            .map(|sheet| sheet.name)
+            .collect();
+        assert_eq!(names, ["Monthly Budget", "Invoice"]);
+        assert_eq!(
+            find(&directories, "monthly budget").unwrap(),
+            directory.join("Finances/Monthly Budget.sheet"),
+        );
+        assert!(find(&directories, "Notes").is_err());
+        assert_eq!(
+            format_sheets(&sheets(&directories).unwrap()[1..], ListFormat::Plain),
+            format!(
+                "Invoice\t{}",
+                directory.join("Work/Invoice.soulver").display()
+            ),
+        );
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,84 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail, ensure};
+
+/// An inclusive range of 1-based line numbers, written as `N` or `N-M`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl LineRange {
+    pub fn contains(&self, line_number: usize) -> bool {
+        (self.start..=self.end).contains(&line_number)
+    }
+}
+
+impl FromStr for LineRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse = |number: &str| -> Result<usize> {
+            match number.trim().parse() {
+                Ok(0) | Err(_) => bail!("invalid line number {number:?}"),
+                Ok(number) => Ok(number),
+            }
+        };
+        let range = match s.split_once('-') {
+            Some((start, end)) => Self {
+                start: parse(start)?,
+                end: parse(end)?,
+            },
+            None => {
+                let line_number = parse(s)?;
+                Self {
+                    start: line_number,
+                    end: line_number,
+                }
+            }
+        };
+        ensure!(range.start <= range.end, "line range {s:?} is backwards");
+        Ok(range)
+    }
+}
+
+impl fmt::Display for LineRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.start == self.end {
+            write!(f, "{}", self.start)
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single() {
+        assert_eq!(
+            "3".parse::<LineRange>().unwrap(),
+            LineRange { start: 3, end: 3 }
+        )
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            "20-35".parse::<LineRange>().unwrap(),
+            LineRange { start: 20, end: 35 }
+        )
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("0".parse::<LineRange>().is_err());
+        assert!("a".parse::<LineRange>().is_err());
+        assert!("5-3".parse::<LineRange>().is_err());
+        assert!("3-".parse::<LineRange>().is_err());
+    }
+}
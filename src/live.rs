@@ -0,0 +1,138 @@
+use crate::soulver;
+use crate::vars;
+
+/// Currency symbols, and ISO codes of commonly converted currencies.
+const CURRENCIES: [(&str, &str); 24] = [
+    ("$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+    ("", "CNY"),
+    ("", "AUD"),
+    ("", "CAD"),
+    ("", "CHF"),
+    ("", "HKD"),
+    ("", "NZD"),
+    ("", "SEK"),
+    ("", "NOK"),
+    ("", "DKK"),
+    ("", "SGD"),
+    ("", "MXN"),
+    ("", "BRL"),
+    ("", "ZAR"),
+    ("", "TRY"),
+    ("", "PLN"),
+    ("", "THB"),
+    ("", "IDR"),
+];
+
+/// Cryptocurrency codes and names, whose prices are always live.
+const CRYPTOCURRENCIES: [&str; 11] = [
+    "BTC", "ETH", "XRP", "LTC", "DOGE", "SOL", "ADA", "bitcoin", "bitcoins", "ethereum", "satoshi",
+];
+
+/// Whether a line mentions a stock, like `$AAPL` or `AAPL stock`.
+fn mentions_stock(line: &str) -> bool {
+    let ticker = line.match_indices('$').any(|(index, _)| {
+        let symbol: String = line[index + 1..]
+            .chars()
+            .take_while(char::is_ascii_alphanumeric)
+            .collect();
+        (1..=5).contains(&symbol.len()) && symbol.chars().all(|c| c.is_ascii_uppercase())
+    });
+    let lowercase = line.to_ascii_lowercase();
+    ticker
+        || ["stock", "stocks", "share price"]
+            .iter()
+            .any(|word| !vars::find_name(&lowercase, word).is_empty())
+}
+
+/// Whether a line converts between currencies, by mentioning more than one or converting `in`,
+/// `to`, or `as` one.
+fn converts_currency(line: &str) -> bool {
+    let mentioned = CURRENCIES
+        .iter()
+        .filter(|(symbol, code)| {
+            (!symbol.is_empty() && line.contains(symbol)) || !vars::find_name(line, code).is_empty()
+        })
+        .count();
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let converts_to = words.windows(2).any(|pair| {
+        ["in", "to", "as"].contains(&pair[0])
+            && CURRENCIES.iter().any(|(symbol, code)| {
+                pair[1] == *code || (!symbol.is_empty() && pair[1] == *symbol)
+            })
+    });
+    mentioned > 1 || converts_to
+}
+
+fn mentions_cryptocurrency(line: &str) -> bool {
+    let lowercase = line.to_ascii_lowercase();
+    CRYPTOCURRENCIES.iter().any(|word| {
+        let found = if word.chars().all(|c| c.is_ascii_uppercase()) {
+            vars::find_name(line, word)
+        } else {
+            vars::find_name(&lowercase, word)
+        };
+        !found.is_empty()
+    })
+}
+
+/// The indices of the lines of a sheet whose results rely on live data that changes between runs:
+/// currency conversions, stocks, cryptocurrencies, and variables assigned from them.
+///
+/// Currency conversions are not live if `fixed_rates` is set.
+pub fn live_lines(lines: &[&str], fixed_rates: bool) -> Vec<usize> {
+    let mut live: Vec<usize> = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| {
+            !soulver::is_comment(line.trim_start())
+                && ((!fixed_rates && converts_currency(line))
+                    || mentions_stock(line)
+                    || mentions_cryptocurrency(line))
+        })
+        .map(|(index, _)| index)
+        .collect();
+    // References are in order, so variables assigned from live variables are found in one pass
+    for reference in vars::references(lines) {
+        let (definition, index) = (
+            reference.definition_line_number - 1,
+            reference.line_number - 1,
+        );
+        if live.contains(&definition) && !live.contains(&index) {
+            live.push(index);
+        }
+    }
+    live.sort_unstable();
+    live
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_live_lines() {
+        let lines = [
+            "Rent = £1000",
+            "Rent in USD",
+            "$10 + $5",
+            "10 EUR to GBP",
+            "# BTC",
+            "Savings = 0.5 BTC",
+            "Savings * 2",
+            "$AAPL * 10",
+            "Retirement = Savings + Rent",
+        ];
+        assert_eq!(live_lines(&lines, false), vec![1, 3, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_live_lines_fixed_rates() {
+        assert_eq!(live_lines(&["£10 in USD", "1 ETH in £"], true), vec![1]);
+    }
+}
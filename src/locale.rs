@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+
+/// How a locale writes numbers. `soulver` reads and writes numbers like [`Locale::SOULVER`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    decimal_separator: char,
+    group_separator: char,
+}
+
+impl Locale {
+    pub const SOULVER: Self = Self {
+        decimal_separator: '.',
+        group_separator: ',',
+    };
+
+    /// Rewrite the numbers in a sheet written in this locale the way `soulver` reads them.
+    pub fn delocalize(self, text: &str) -> String {
+        convert_numbers(text, self, Self::SOULVER)
+    }
+
+    /// Rewrite the numbers in a result from `soulver` the way this locale writes them.
+    pub fn localize(self, text: &str) -> String {
+        convert_numbers(text, Self::SOULVER, self)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = Error;
+
+    /// Parse a locale name like `de_DE`, `de-DE`, `de_DE.UTF-8`, or `de`.
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.split('.').next().unwrap_or(s).replace('-', "_");
+        let (language, region) = match name.split_once('_') {
+            Some((language, region)) => {
+                (language.to_ascii_lowercase(), region.to_ascii_uppercase())
+            }
+            None => (name.to_ascii_lowercase(), String::new()),
+        };
+        let (decimal_separator, group_separator) = match (language.as_str(), region.as_str()) {
+            ("de" | "fr" | "it", "CH") => ('.', '\''),
+            ("en" | "ja" | "zh" | "ko" | "he" | "th" | "hi" | "ms", _) => ('.', ','),
+            (
+                "de" | "es" | "it" | "nl" | "pt" | "id" | "tr" | "da" | "el" | "ro" | "hr" | "sl"
+                | "sr" | "vi",
+                _,
+            ) => (',', '.'),
+            (
+                "fr" | "sv" | "nb" | "nn" | "no" | "fi" | "cs" | "sk" | "pl" | "ru" | "uk" | "hu"
+                | "bg" | "et" | "lv" | "lt",
+                _,
+            ) => (',', ' '),
+            _ => bail!("unsupported locale {s:?}"),
+        };
+        Ok(Self {
+            decimal_separator,
+            group_separator,
+        })
+    }
+}
+
+/// Reformat a number written in the `from` locale in the `to` locale, or return `None` if it
+/// isn't a valid number in the `from` locale.
+fn convert_number(number: &str, from: Locale, to: Locale) -> Option<String> {
+    let (integer, fraction) = match number.split_once(from.decimal_separator) {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+    if fraction.is_some_and(|fraction| !fraction.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let groups: Vec<&str> = integer.split(from.group_separator).collect();
+    let valid_groups = groups.iter().enumerate().all(|(index, group)| {
+        group.chars().all(|c| c.is_ascii_digit())
+            && match (index, groups.len()) {
+                (_, 1) => !group.is_empty(),
+                (0, _) => (1..=3).contains(&group.len()),
+                _ => group.len() == 3,
+            }
+    });
+    if !valid_groups {
+        return None;
+    }
+
+    let mut converted = groups.join(&to.group_separator.to_string());
+    if let Some(fraction) = fraction {
+        converted.push(to.decimal_separator);
+        converted.push_str(fraction);
+    }
+    Some(converted)
+}
+
+/// Reformat the numbers in text from the `from` locale to the `to` locale, leaving anything that
+/// isn't a valid number in the `from` locale, such as a date, unchanged.
+fn convert_numbers(text: &str, from: Locale, to: Locale) -> String {
+    if from == to {
+        return text.to_owned();
+    }
+    let is_number_char =
+        |c: char| c.is_ascii_digit() || c == from.decimal_separator || c == from.group_separator;
+    let mut converted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        converted.push_str(&rest[..start]);
+        let length = rest[start..]
+            .find(|c: char| !is_number_char(c))
+            .unwrap_or(rest.len() - start);
+        // Separators after the last digit are punctuation rather than part of the number
+        let number = rest[start..start + length].trim_end_matches(|c: char| !c.is_ascii_digit());
+        match convert_number(number, from, to) {
+            Some(number) => converted.push_str(&number),
+            None => converted.push_str(number),
+        }
+        rest = &rest[start + number.len()..];
+    }
+    converted.push_str(rest);
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locale(name: &str) -> Locale {
+        name.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            locale("de_DE.UTF-8"),
+            Locale {
+                decimal_separator: ',',
+                group_separator: '.',
+            },
+        );
+        assert_eq!(locale("en-GB"), Locale::SOULVER);
+        assert_eq!(locale("de_CH").group_separator, '\'');
+        assert!("xx_XX".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_delocalize() {
+        assert_eq!(
+            locale("de_DE").delocalize("1.234,5 € + 2,25 € + 3"),
+            "1,234.5 € + 2.25 € + 3",
+        )
+    }
+
+    #[test]
+    fn test_delocalize_not_numbers() {
+        assert_eq!(
+            locale("de_DE").delocalize("1.6.2024, 1.23"),
+            "1.6.2024, 1.23"
+        )
+    }
+
+    #[test]
+    fn test_localize() {
+        assert_eq!(locale("de_DE").localize("€1,234.50"), "€1.234,50");
+        assert_eq!(
+            locale("fr_FR").localize("1,234,567.8 kg."),
+            "1 234 567,8 kg."
+        );
+        assert_eq!(locale("de_CH").localize("1,234.5"), "1'234.5");
+    }
+}
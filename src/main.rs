@@ -1,23 +1,545 @@
+#[macro_use]
+mod output;
+
+mod accessible;
+mod archive;
+mod assertions;
+mod batch;
+mod bench;
+mod bundle;
+mod calculate;
+mod check;
+mod config;
+mod csv;
+mod daemon;
+mod dates;
+mod diagnostics;
+mod diff;
+mod files;
+mod font;
+mod history;
+mod hooks;
+mod json;
+mod latex;
+mod library;
+mod line_range;
+mod live;
+mod locale;
+mod metrics;
+#[cfg(feature = "mock-backend")]
+mod mock;
+mod pdf;
+mod plugin;
+mod png;
+mod preview;
+mod profile;
+mod progress;
+mod quantity;
+mod query;
+mod rates;
+mod regex;
+mod repl;
+mod sanitize;
+mod scaffold;
+mod service;
+mod snapshot;
+mod snippets;
 mod soulver;
+mod stats;
+mod task;
+mod template;
+mod terminal;
+mod totals;
+mod tui;
+mod vars;
+mod watch;
+mod xlsx;
 
-use anyhow::Result;
-use clap::{CommandFactory, Parser, Subcommand};
-use std::io::{self, Read};
+use anyhow::{Result, anyhow, bail};
+use clap::error::ErrorKind;
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::env;
+use std::fs;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 
 #[derive(Parser)]
-#[command(version, author, about, long_about = None)]
+#[command(
+    version,
+    author,
+    about,
+    long_about = None,
+    arg_required_else_help = true
+)]
 struct Cli {
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+
+    /// Calculate an expression and output its result, instead of running a subcommand, e.g.
+    /// `soulver-cli-zipper "2 weeks from today"`
+    #[arg(value_name = "EXPRESSION")]
+    expression: Option<String>,
+
+    /// Do not output results or report progress, and given twice, do not report errors either
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    quiet: u8,
+
+    /// Trace the path, arguments, duration, and exit status of each `soulver` run to stderr
+    #[arg(short, long, global = true)]
+    verbose: bool,
+
+    /// How to log errors, and events traced with `--verbose`, to stderr
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        default_value_t
+    )]
+    log_format: output::LogFormat,
+
+    /// Report each line that should have a result but does not to stderr in a format editors can
+    /// jump to, e.g. `gnu` for `file:line: message` or `quickfix` for Vim's quickfix list
+    #[arg(long, global = true, value_enum, value_name = "FORMAT")]
+    error_format: Option<output::ErrorFormat>,
+
+    /// Report how long reading, preprocessing, running `soulver`, and zipping took to stderr
+    #[arg(long, global = true)]
+    profile: bool,
+
+    /// Calculate `today`, `tomorrow`, and `yesterday` relative to a date in the form `YYYY-MM-DD`
+    /// so results are the same on any day
+    #[arg(long, global = true, value_name = "DATE")]
+    today: Option<dates::Date>,
+
+    /// Recognise lines starting with a prefix as producing no output instead of `#` and `//`, e.g.
+    /// `--comment-prefix '#' --comment-prefix ';'`
+    #[arg(long = "comment-prefix", global = true, value_name = "PREFIX")]
+    comment_prefixes: Vec<String>,
+
+    /// Remove control characters and zero-width codepoints, like those pasted along with rich
+    /// text, from sheets before calculating them, or escape them so they can be seen
+    #[arg(long, global = true, value_enum, value_name = "MODE")]
+    sanitize: Option<sanitize::Sanitize>,
+
+    /// Run `soulver` on a host over SSH, e.g. `--remote user@mac` to calculate sheets on a Mac
+    /// from another machine
+    #[arg(long, global = true, value_name = "HOST")]
+    remote: Option<String>,
+
+    /// Calculate sheets with a `daemon` on another machine, e.g. `--remote-daemon mac.local:7700`,
+    /// sending the token from `SOULVER_CLI_ZIPPER_DAEMON_TOKEN` or `daemon_token` in the config
+    /// file
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOST:PORT",
+        conflicts_with = "remote"
+    )]
+    remote_daemon: Option<String>,
+
+    /// When to exit with a non-zero exit code
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        value_name = "POLICY",
+        default_value_t
+    )]
+    exit_code: ExitCodePolicy,
+}
+
+/// When to exit with a non-zero exit code.
+#[derive(Clone, Copy, Default, ValueEnum)]
+enum ExitCodePolicy {
+    /// If a sheet cannot be calculated, or a command like `check` fails
+    #[default]
+    Default,
+    /// Never, even if there are errors, which are still reported
+    Always,
+    /// Also if any line that should have a result does not, like a typo
+    FailedLines,
+    /// Also if no line has a result
+    Empty,
+    /// With the number of sheets that failed, or of their lines for `check` and `test`, up to 125
+    Count,
+}
+
+/// The highest exit code `--exit-code count` exits with, below those shells give special meanings.
+const MAX_FAILURES_EXIT_CODE: usize = 125;
+
+/// The number of sheets, or lines of them, that failed so far, for `--exit-code count`.
+static FAILURES: AtomicUsize = AtomicUsize::new(0);
+
+fn record_failures(count: usize) {
+    FAILURES.fetch_add(count, Ordering::Relaxed);
+}
+
+impl ExitCodePolicy {
+    fn apply(self, result: Result<ExitCode>) -> Result<ExitCode> {
+        match self {
+            Self::Default => result,
+            Self::Always => {
+                if let Err(error) = result {
+                    output::log_error(None, &error);
+                }
+                Ok(ExitCode::SUCCESS)
+            }
+            Self::FailedLines if soulver::any_line_failed() => result.map(|_| ExitCode::FAILURE),
+            Self::Empty if !soulver::any_result() => result.map(|_| ExitCode::FAILURE),
+            Self::FailedLines | Self::Empty => result,
+            Self::Count => result.map(|exit_code| match FAILURES.load(Ordering::Relaxed) {
+                0 => exit_code,
+                failures => ExitCode::from(failures.min(MAX_FAILURES_EXIT_CODE) as u8),
+            }),
+        }
+    }
+}
+
+/// Sheets to read from paths, or stdin if there are none.
+#[derive(Clone, Args)]
+struct SheetArgs {
+    /// The sheets to read instead of stdin
+    paths: Vec<PathBuf>,
+
+    /// Read the sheets in directories and their subdirectories, skipping any paths matched by a
+    /// `.soulverignore` file
+    #[arg(short, long)]
+    recursive: bool,
+
+    /// Only read files in directories matching a glob, e.g. `*.soulver`
+    #[arg(long, requires = "recursive")]
+    glob: Option<String>,
+
+    /// The maximum number of sheets to calculate at the same time
+    #[arg(short, long, default_value = "1")]
+    jobs: NonZeroUsize,
+
+    /// Read multiple NUL-separated sheets from stdin
+    #[arg(long, conflicts_with = "paths")]
+    stdin0: bool,
+}
+
+impl SheetArgs {
+    fn read(&self) -> Result<Vec<(files::Sheet, Result<()>)>> {
+        profile::time(profile::Phase::Read, || {
+            files::read_sheets(
+                &self.paths,
+                self.recursive,
+                self.glob.as_deref(),
+                self.stdin0,
+            )
+        })
+    }
+
+    /// Read the sheets and process them in parallel, reporting progress unless `quiet` is set.
+    ///
+    /// A sheet that could not be read is not processed, and its result is why it could not be.
+    /// `succeeded` decides whether a processed sheet counts as succeeded in the progress report.
+    fn process<R, F, S>(
+        &self,
+        quiet: bool,
+        f: F,
+        succeeded: S,
+    ) -> Result<Vec<(files::Sheet, Result<R>)>>
+    where
+        R: Send,
+        F: Fn(&files::Sheet) -> Result<R> + Sync,
+        S: Fn(&R) -> bool + Sync,
+    {
+        let sheets = self.read()?;
+        let progress = progress::Progress::new(sheets.len(), quiet);
+        let results = batch::map(&sheets, self.jobs, |(sheet, read)| {
+            let result = read.is_ok().then(|| f(sheet));
+            let success = result
+                .as_ref()
+                .is_some_and(|result| result.as_ref().is_ok_and(&succeeded));
+            progress.finish_sheet(&sheet.name(), success);
+            result
+        });
+        progress.finish();
+        Ok(sheets
+            .into_iter()
+            .zip(results)
+            .map(|((sheet, read), result)| {
+                let result = match read {
+                    Ok(()) => result.expect("every readable sheet is processed"),
+                    Err(error) => Err(error),
+                };
+                (sheet, result)
+            })
+            .collect())
+    }
+}
+
+/// Report an error for a sheet in a batch without stopping the rest of the batch, counting it for
+/// `--exit-code count`.
+fn report_sheet_error(sheet: &files::Sheet, error: &anyhow::Error) {
+    record_failures(1);
+    output::log_error(Some(&sheet.name()), error);
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Calculate a sheet from stdin with `soulver` and include the input on each line
-    Calculate {
-        /// Do not add the input to the output
+    ///
+    /// When calculating multiple sheets, each is preceded by a `==> path <==` header.
+    Calculate(Box<calculate::CalculateArgs>),
+
+    /// Output the zipped form of a sheet file, for use as a Git `textconv` driver
+    Textconv {
+        /// The sheet to calculate
+        file: PathBuf,
+    },
+
+    /// Recalculate sheets annotated with `expression => answer` and report any answers that differ
+    Check {
+        #[command(flatten)]
+        sheets: SheetArgs,
+    },
+
+    /// Run the `assert: <expression> == <expected>` lines of sheets and report which pass
+    ///
+    /// Sheets with a sidecar `.expected` file are instead checked line by line against the results
+    /// in that file.
+    Test {
+        #[command(flatten)]
+        sheets: SheetArgs,
+    },
+
+    /// Record or verify snapshots of the zipped output of sheets
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommands,
+    },
+
+    /// Calculate two sheets and report which lines were added, removed, or changed result
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+
+        /// Output the changes as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Report the count, sum, mean, minimum, and maximum of the numeric results of sheets
+    ///
+    /// Only results in the most common unit or currency are included.
+    Stats {
+        #[command(flatten)]
+        sheets: SheetArgs,
+
+        /// Report statistics for each `#` heading's section
         #[arg(long)]
-        no_zip: bool,
+        by_heading: bool,
+
+        /// Output the statistics as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// List the variables assigned in sheets along with their final values
+    Vars {
+        #[command(flatten)]
+        sheets: SheetArgs,
+
+        /// Output the variables as a JSON array
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show which lines of a sheet reference which variables
+    Deps {
+        /// The sheet to read instead of stdin
+        file: Option<PathBuf>,
+
+        /// Output a Graphviz DOT graph instead of a tree
+        #[arg(long)]
+        dot: bool,
+    },
+
+    /// Rename a variable in a sheet, checking that every result is unchanged
+    RenameVar {
+        old: String,
+        new: String,
+        file: PathBuf,
+    },
+
+    /// Manage snapshots of exchange rates for `calculate --rates`
+    Rates {
+        #[command(subcommand)]
+        command: RatesCommands,
+    },
+
+    /// Look through past `calculate` runs, which are recorded if `history = true` is set in the
+    /// config file
+    History {
+        #[command(subcommand)]
+        command: HistoryCommands,
+    },
+
+    /// Calculate a sheet from a file or stdin and write it with its answers to a Soulver 3 `.sheet`
+    /// bundle to open in the app, or typeset it to a PDF or image
+    Export {
+        file: Option<PathBuf>,
+
+        /// The bundle to write, which is created if it doesn't exist
+        #[arg(long = "sheet", value_name = "PATH", required_unless_present_any = ["pdf", "png"])]
+        sheet: Option<PathBuf>,
+
+        /// Write the zipped sheet to a PDF, in a monospace font with `#` headings in bold
+        #[arg(long, value_name = "PATH")]
+        pdf: Option<PathBuf>,
+
+        /// Render the zipped sheet to a PNG image, in a monospace font with the theme's colours
+        #[arg(long, value_name = "PATH")]
+        png: Option<PathBuf>,
+
+        /// The colours of the image
+        #[arg(long, value_enum, default_value = "dark", requires = "png")]
+        theme: png::Theme,
+    },
+
+    /// Convert other formats into sheets and calculate them
+    Import {
+        #[command(subcommand)]
+        command: ImportCommands,
+    },
+
+    /// List the sheets in the Soulver library, or the `library` directory set in the config file,
+    /// e.g. to pick one with `fzf`
+    List {
+        /// The directories to list sheets in instead
+        directories: Vec<PathBuf>,
+
+        #[arg(long, value_enum, default_value = "plain")]
+        format: library::ListFormat,
+    },
+
+    /// Create a sheet from a template, filling in `{{DATE}}`, `{{MONTH}}`, and `{{YEAR}}`
+    /// placeholders
+    New {
+        path: PathBuf,
+
+        /// The template to use, from the `templates` directory next to the config file, or the
+        /// built-in `monthly-budget` or `invoice`
+        #[arg(long)]
+        template: String,
+
+        /// Replace the file if it already exists
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Save reusable sheets, like a tip calculator, and calculate them on demand
+    Snippet {
+        #[command(subcommand)]
+        command: SnippetCommands,
+    },
+
+    /// Serve a page on localhost showing a sheet's zipped results, which updates whenever the sheet
+    /// is saved
+    Preview {
+        file: PathBuf,
+
+        /// The port to serve the page on, or any free port if not given
+        #[arg(long, default_value = "0")]
+        port: u16,
+    },
+
+    /// Calculate a sheet and print its zipped results again whenever it is saved
+    Watch {
+        file: PathBuf,
+
+        /// How long the sheet must go without changing before it is calculated, so a burst of
+        /// writes, like an editor's atomic save, is only calculated once
+        #[arg(long, value_name = "MS", default_value = "100")]
+        debounce: u64,
+
+        /// How to highlight the results that changed since the sheet was last calculated
+        #[arg(long, value_enum, value_name = "WHEN", default_value = "auto")]
+        highlight: watch::Highlight,
+
+        /// Keep a file up to date with the zipped results instead of printing them, replacing it
+        /// atomically, e.g. for another program to show
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Calculate lines as they are typed, which can use the variables defined earlier, with tab
+    /// completing variables and common units
+    Repl {
+        /// Continue a session saved with `.save FILE`, or any sheet, with its variables
+        #[arg(long, value_name = "FILE")]
+        resume: Option<PathBuf>,
+    },
+
+    /// Browse a calculated sheet full screen, with its inputs and results scrolling together,
+    /// searching both with `/` and jumping between headings with `[` and `]`
+    Tui {
+        /// The sheet to calculate
+        file: PathBuf,
+    },
+
+    /// Calculate sheets with `soulver` for clients on other machines using `--remote-daemon`, who
+    /// must send the token from `SOULVER_CLI_ZIPPER_DAEMON_TOKEN` or `daemon_token` in the config
+    /// file, or for clients on this machine using `calculate --socket`
+    Daemon {
+        /// The address to listen on, e.g. `0.0.0.0:7700` for the whole network
+        #[arg(long, default_value = "127.0.0.1:7700")]
+        listen: String,
+
+        /// Listen on a Unix socket only the current user can connect to instead, which clients
+        /// do not send the token to
+        #[arg(long, value_name = "PATH", conflicts_with = "listen")]
+        socket: Option<PathBuf>,
+
+        /// Listen on the socket systemd or launchd opened for the daemon, so it is only started
+        /// once a client connects, as installed by `service install --activate-on`
+        #[arg(long, conflicts_with_all = ["listen", "socket"])]
+        socket_activated: bool,
+
+        /// Serve Prometheus metrics of requests, their durations, and failures at `/metrics` on
+        /// another address, e.g. `127.0.0.1:9770`, to clients sending the token as a bearer token
+        #[arg(long, value_name = "ADDRESS")]
+        metrics: Option<String>,
+
+        /// The largest sheet in bytes clients can send
+        #[arg(long, value_name = "BYTES", default_value = "1048576")]
+        max_sheet_size: usize,
+
+        /// How many sheets each client address can send in a minute, to stop a runaway client
+        /// from running `soulver` without end
+        #[arg(long, value_name = "N", default_value = "600")]
+        max_requests_per_minute: NonZeroUsize,
+
+        /// How many clients can be connected at once, which bounds how many `soulver` processes
+        /// run at once, as each client's sheets are calculated one at a time
+        #[arg(long, value_name = "N", default_value = "16")]
+        max_connections: NonZeroUsize,
+    },
+
+    /// Install the daemon as a launchd or systemd user service that starts at login
+    Service {
+        #[command(subcommand)]
+        command: ServiceCommands,
+    },
+
+    /// Time how long `soulver` takes to calculate a sheet from a file or stdin
+    Bench {
+        file: Option<PathBuf>,
+
+        /// How many times to calculate the sheet
+        #[arg(short = 'n', long, default_value = "10")]
+        runs: NonZeroUsize,
+
+        /// A `soulver` executable to time, which can be given multiple times to compare them
+        #[arg(long = "soulver", value_name = "PATH", default_value = "soulver")]
+        programs: Vec<PathBuf>,
     },
 
     /// Generate shell completions
@@ -28,23 +550,735 @@ enum Commands {
     },
 }
 
-fn main() -> Result<()> {
+#[derive(Subcommand)]
+enum HistoryCommands {
+    /// List past runs, numbered from the oldest
+    List {
+        /// Only list the most recent runs
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+
+    /// Show the sheet and output of a past run
+    Show {
+        /// The run's number in `history list`
+        number: usize,
+    },
+
+    /// List past runs whose sheet or output contains a term, ignoring case
+    Search { term: String },
+
+    /// Calculate the sheet of a past run again, with the current rates and dates
+    Rerun {
+        /// The run's number in `history list`
+        number: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ImportCommands {
+    /// Calculate a line for each row of a CSV file or stdin, e.g. to move from a spreadsheet
+    Csv {
+        file: Option<PathBuf>,
+
+        /// The column with the expressions, counting from 1
+        #[arg(long = "expr-column", value_name = "N", default_value = "1")]
+        expression_column: NonZeroUsize,
+
+        /// A column to label each line with, e.g. `Rent: 1000`
+        #[arg(long, value_name = "N")]
+        label_column: Option<NonZeroUsize>,
+
+        /// Assign each expression to a variable named by the label column instead, e.g.
+        /// `Rent = 1000`
+        #[arg(long, requires = "label_column")]
+        variables: bool,
+
+        /// Skip the first row, which names the columns
+        #[arg(long)]
+        header: bool,
+
+        #[arg(long, value_enum, default_value = "zipped")]
+        format: csv::ImportFormat,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnippetCommands {
+    /// Save a sheet from a file or stdin as a snippet in the config directory
+    Save { name: String, file: Option<PathBuf> },
+
+    /// Calculate a snippet
+    Run {
+        name: String,
+
+        /// Assign a variable, replacing its value if the snippet assigns it, e.g.
+        /// `--define Bill=$80`
+        #[arg(long = "define", value_name = "NAME=VALUE")]
+        definitions: Vec<vars::Definition>,
+    },
+
+    /// List the saved snippets
+    List,
+}
+
+#[derive(Subcommand)]
+enum ServiceCommands {
+    /// Install and start the service, passing any arguments after `--` to `daemon`, e.g.
+    /// `service install -- --listen 0.0.0.0:7700`
+    Install {
+        /// Only start the daemon once a client connects to a TCP address like `127.0.0.1:7700`,
+        /// or the absolute path of a Unix socket, which the service manager listens on instead
+        #[arg(long, value_name = "ADDRESS")]
+        activate_on: Option<String>,
+
+        #[arg(last = true)]
+        daemon_arguments: Vec<String>,
+    },
+
+    /// Stop and remove the service
+    Uninstall,
+
+    /// Show whether the service is running
+    Status,
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommands {
+    /// Write the zipped output of each sheet to a `.snap` file next to it
+    Update {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+
+    /// Compare the zipped output of each sheet against its `.snap` file
+    Verify {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum RatesCommands {
+    /// Download the latest exchange rates as a snapshot
+    Fetch {
+        /// The currency the rates are relative to
+        #[arg(long, default_value = "USD")]
+        base: String,
+
+        /// Write the snapshot to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+fn main() -> Result<ExitCode> {
     let cli = Cli::parse();
+    // `args_conflicts_with_subcommands` would also stop global arguments before a subcommand, like
+    // `--verbose calculate`, from being used with it
+    if cli.command.is_some()
+        && let Some(expression) = &cli.expression
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!("the expression `{expression}` cannot be used with a subcommand"),
+            )
+            .exit();
+    }
+    output::set_quiet(cli.quiet);
+    output::set_verbose(cli.verbose);
+    output::set_log_format(cli.log_format);
+    if let Some(format) = cli.error_format {
+        output::set_error_format(format);
+    }
+    if cli.profile {
+        profile::enable();
+    }
+    let start = Instant::now();
+    let arguments: Vec<String> = env::args().collect();
+    output::trace(
+        "invocation",
+        &format!("soulver-cli-zipper: argv {arguments:?}"),
+        vec![("argv", json::Value::from(arguments.clone()))],
+    );
+    let exit_code = cli.exit_code;
+    let result = exit_code.apply(run(cli));
+    let duration = start.elapsed();
+    output::trace(
+        "finished",
+        &format!("soulver-cli-zipper: finished after {duration:.1?}"),
+        vec![(
+            "duration_ms",
+            json::Value::from(duration.as_secs_f64() * 1000.0),
+        )],
+    );
+    if let Some(report) = profile::report(duration) {
+        eprintln!("{report}");
+    }
+    result.or_else(|error| {
+        output::log_error(None, &error);
+        Ok(ExitCode::FAILURE)
+    })
+}
+
+/// How many single character insertions, deletions, or substitutions turn one word into another.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The error for an expression without a result, suggesting the subcommands it might be a typo
+/// of, e.g. `check` for `chek`.
+fn no_result_error(expression: &str) -> anyhow::Error {
+    let similar: Vec<String> = Cli::command()
+        .get_subcommands()
+        .map(|command| command.get_name().to_owned())
+        .filter(|name| edit_distance(expression, name) <= 2)
+        .map(|name| format!("`{name}`"))
+        .collect();
+    match similar.as_slice() {
+        [] => anyhow!("no result for `{expression}`"),
+        [name] => anyhow!("no result for `{expression}`; did you mean the subcommand {name}?"),
+        names => anyhow!(
+            "no result for `{expression}`; did you mean one of the subcommands {}?",
+            names.join(", "),
+        ),
+    }
+}
+
+fn run(cli: Cli) -> Result<ExitCode> {
+    let config = config::Config::load()?;
+    if !cli.comment_prefixes.is_empty() {
+        soulver::set_comment_prefixes(cli.comment_prefixes);
+    } else if let Some(prefixes) = &config.comment_prefixes {
+        soulver::set_comment_prefixes(prefixes.clone());
+    }
+    if let Some(sanitize) = cli.sanitize.or(config.sanitize) {
+        sanitize::set(sanitize);
+    }
+    if let Some(today) = cli.today {
+        soulver::pin_today(today);
+    }
+    hooks::set(config.hooks.clone());
+    let socket = match &cli.command {
+        Some(Commands::Calculate(args)) => args.socket().map(PathBuf::from),
+        _ => None,
+    };
+    if let Some(path) = socket {
+        // clap only sees the conflict when the global arguments come after `calculate`
+        if cli.remote.is_some() || cli.remote_daemon.is_some() {
+            bail!("`--socket` cannot be used with `--remote` or `--remote-daemon`");
+        }
+        soulver::set_daemon(daemon::Client::unix(path));
+    } else if let Some(address) = cli.remote_daemon {
+        soulver::set_daemon(daemon::Client::tcp(address, daemon::token(&config)?));
+    } else if let Some(remote) = cli.remote.or_else(|| config.remote.clone()) {
+        soulver::set_remote(remote);
+    }
+
+    let Some(command) = cli.command else {
+        // clap shows the help when there is neither a subcommand nor an expression
+        let expression = cli.expression.unwrap_or_default();
+        let lines = soulver::calculate_lines(&expression)?;
+        let Some(line) = lines.iter().rev().find(|line| !line.output.is_empty()) else {
+            return Err(no_result_error(&expression));
+        };
+        outputln!("{}", line.output);
+        return Ok(ExitCode::SUCCESS);
+    };
+    match command {
+        Commands::Calculate(args) => return calculate::calculate(&args, &config, cli.quiet > 0),
+        Commands::Textconv { file } => {
+            let input = fs::read_to_string(file)?;
+            let result = soulver::run_soulver_zipped(&input)?;
+            outputln!("{result}");
+        }
+        Commands::Check { sheets } => {
+            let results = sheets.process(
+                cli.quiet > 0,
+                |sheet| check::check(&sheet.contents),
+                |mismatches| mismatches.is_empty(),
+            )?;
+
+            let mut success = true;
+            for (sheet, mismatches) in results {
+                let mismatches = match mismatches {
+                    Ok(mismatches) => mismatches,
+                    Err(error) => {
+                        success = false;
+                        report_sheet_error(&sheet, &error);
+                        continue;
+                    }
+                };
+                record_failures(mismatches.len());
+                for mismatch in mismatches {
+                    success = false;
+                    outputln!("{}:{}", sheet.name(), mismatch.line_number);
+                    outputln!("{}", mismatch.diff());
+                }
+            }
+            if !success {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Test { sheets } => {
+            let results = sheets.process(
+                cli.quiet > 0,
+                |sheet| {
+                    let expected_file = sheet
+                        .path
+                        .as_ref()
+                        .map(|path| path.with_extension("expected"))
+                        .filter(|path| path.is_file());
+                    match expected_file {
+                        Some(expected_file) => assertions::run_expected(
+                            &sheet.contents,
+                            &fs::read_to_string(expected_file)?,
+                        ),
+                        None => assertions::run_assertions(&sheet.contents),
+                    }
+                },
+                |assertions| assertions.iter().all(assertions::Assertion::passed),
+            )?;
+
+            let (mut passed, mut failed, mut errored) = (0, 0, false);
+            for (sheet, assertions) in results {
+                let name = sheet.name();
+                let assertions = match assertions {
+                    Ok(assertions) => assertions,
+                    Err(error) => {
+                        errored = true;
+                        report_sheet_error(&sheet, &error);
+                        continue;
+                    }
+                };
+                for assertion in assertions {
+                    if assertion.passed() {
+                        passed += 1;
+                        outputln!(
+                            "PASS {name}:{} {}",
+                            assertion.line_number,
+                            assertion.description
+                        );
+                    } else {
+                        failed += 1;
+                        outputln!(
+                            "FAIL {name}:{} {} (expected {:?}, got {:?})",
+                            assertion.line_number,
+                            assertion.description,
+                            assertion.expected,
+                            assertion.actual,
+                        );
+                    }
+                }
+            }
+            outputln!("\n{passed} passed, {failed} failed");
+            record_failures(failed);
+            if failed > 0 || errored {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Snapshot { command } => match command {
+            SnapshotCommands::Update { files } => {
+                for file in files {
+                    snapshot::update(&file)?;
+                }
+            }
+            SnapshotCommands::Verify { files } => {
+                let mut success = true;
+                for file in files {
+                    match snapshot::verify(&file)? {
+                        snapshot::Verification::Matches => {}
+                        snapshot::Verification::Missing => {
+                            success = false;
+                            record_failures(1);
+                            outputln!("{}: missing snapshot", file.display());
+                        }
+                        snapshot::Verification::Differs(diff) => {
+                            success = false;
+                            record_failures(1);
+                            outputln!("{}: snapshot differs", file.display());
+                            outputln!("{diff}");
+                        }
+                    }
+                }
+                if !success {
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        },
+        Commands::Diff { old, new, json } => {
+            let changes = diff::diff(&fs::read_to_string(old)?, &fs::read_to_string(new)?)?;
+            if json {
+                let changes: Vec<json::Value> = changes.iter().map(diff::Change::to_json).collect();
+                outputln!("{}", json::Value::from(changes));
+            } else {
+                for change in changes {
+                    outputln!("{change}");
+                }
+            }
+        }
+        Commands::Stats {
+            sheets,
+            by_heading,
+            json,
+        } => {
+            let results = sheets.process(
+                cli.quiet > 0,
+                |sheet| soulver::calculate_lines(&sheet.contents),
+                |_| true,
+            )?;
+            let multiple = results.len() > 1;
 
-    match cli.command {
-        Commands::Calculate { no_zip } => {
-            let mut input = String::new();
-            io::stdin().read_to_string(&mut input)?;
-            let result = if no_zip {
-                soulver::run_soulver(&input)?
+            let mut success = true;
+            for (index, (sheet, lines)) in results.into_iter().enumerate() {
+                let lines = match lines {
+                    Ok(lines) => lines,
+                    Err(error) => {
+                        success = false;
+                        report_sheet_error(&sheet, &error);
+                        continue;
+                    }
+                };
+                if json {
+                    let report = stats::report_json(&lines, by_heading);
+                    let report = if multiple {
+                        json::Value::object([
+                            ("sheet", json::Value::from(sheet.name())),
+                            ("stats", report),
+                        ])
+                    } else {
+                        report
+                    };
+                    outputln!("{report}");
+                } else {
+                    if multiple {
+                        if index > 0 {
+                            outputln!();
+                        }
+                        outputln!("==> {} <==", sheet.name());
+                    }
+                    outputln!("{}", stats::report_text(&lines, by_heading));
+                }
+            }
+            if !success {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Vars { sheets, json } => {
+            let results = sheets.process(
+                cli.quiet > 0,
+                |sheet| soulver::calculate_lines(&sheet.contents),
+                |_| true,
+            )?;
+            let multiple = results.len() > 1;
+
+            let mut success = true;
+            for (index, (sheet, lines)) in results.into_iter().enumerate() {
+                let lines = match lines {
+                    Ok(lines) => lines,
+                    Err(error) => {
+                        success = false;
+                        report_sheet_error(&sheet, &error);
+                        continue;
+                    }
+                };
+                let variables = vars::variables(&lines);
+                if json {
+                    let variables: Vec<json::Value> =
+                        variables.iter().map(vars::Variable::to_json).collect();
+                    let report = if multiple {
+                        json::Value::object([
+                            ("sheet", json::Value::from(sheet.name())),
+                            ("variables", json::Value::from(variables)),
+                        ])
+                    } else {
+                        json::Value::from(variables)
+                    };
+                    outputln!("{report}");
+                } else {
+                    if multiple {
+                        if index > 0 {
+                            outputln!();
+                        }
+                        outputln!("==> {} <==", sheet.name());
+                    }
+                    if !variables.is_empty() {
+                        outputln!("{}", vars::to_text(&variables));
+                    }
+                }
+            }
+            if !success {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Commands::Deps { file, dot } => {
+            let input = match file {
+                Some(file) => fs::read_to_string(file)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            let lines: Vec<&str> = input.lines().collect();
+            let output = if dot {
+                vars::dependency_dot(&lines)
             } else {
-                soulver::run_soulver_zipped(&input)?
+                vars::dependency_tree(&lines)
             };
-            println!("{result}");
+            if !output.is_empty() {
+                outputln!("{output}");
+            }
+        }
+        Commands::RenameVar { old, new, file } => {
+            let count = vars::rename_in_file(&file, &old, &new)?;
+            outputln!("Renamed {count} occurrences of {old:?} to {new:?}");
+        }
+        Commands::Rates { command } => match command {
+            RatesCommands::Fetch { base, output } => {
+                let rates = rates::Rates::fetch(&base)?.to_json().to_string();
+                match output {
+                    Some(output) => files::write_atomic(&output, format!("{rates}\n"))?,
+                    None => outputln!("{rates}"),
+                }
+            }
+        },
+        Commands::History { command } => match command {
+            HistoryCommands::List { limit } => {
+                let entries = history::read()?;
+                let skip = limit.map_or(0, |limit| entries.len().saturating_sub(limit));
+                for (index, entry) in entries.iter().enumerate().skip(skip) {
+                    outputln!("{}", history::summary(index + 1, entry));
+                }
+            }
+            HistoryCommands::Show { number } => {
+                let entry = history::get(history::read()?, number)?;
+                outputln!("Date: {}", history::format_timestamp(entry.timestamp));
+                outputln!("Directory: {}\n", entry.directory);
+                outputln!("{}", entry.output);
+            }
+            HistoryCommands::Search { term } => {
+                let entries = history::read()?;
+                for number in history::search(&entries, &term) {
+                    outputln!("{}", history::summary(number, &entries[number - 1]));
+                }
+            }
+            HistoryCommands::Rerun { number } => {
+                let entry = history::get(history::read()?, number)?;
+                outputln!("{}", soulver::run_soulver_zipped(&entry.input)?);
+            }
+        },
+        Commands::Export {
+            file,
+            sheet,
+            pdf,
+            png,
+            theme,
+        } => {
+            let input = match file {
+                Some(file) => fs::read_to_string(file)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            let lines = soulver::calculate_lines(&input)?;
+            if let Some(sheet) = sheet {
+                bundle::write(&sheet, &lines)?;
+            }
+            if let Some(pdf) = pdf {
+                files::write_atomic(&pdf, pdf::document(&lines))?;
+            }
+            if let Some(png) = png {
+                files::write_atomic(&png, png::render(&lines, theme))?;
+            }
+        }
+        Commands::Import { command } => match command {
+            ImportCommands::Csv {
+                file,
+                expression_column,
+                label_column,
+                variables,
+                header,
+                format,
+            } => {
+                let text = match file {
+                    Some(file) => fs::read_to_string(file)?,
+                    None => std::io::read_to_string(std::io::stdin())?,
+                };
+                let mut rows = csv::parse(&text)?;
+                let header = if header && !rows.is_empty() {
+                    Some(rows.remove(0))
+                } else {
+                    None
+                };
+                let import = csv::Import {
+                    expression_column: expression_column.get() - 1,
+                    label_column: label_column.map(|column| column.get() - 1),
+                    variables,
+                };
+                let sheet: Vec<String> = rows.iter().map(|row| import.line(row)).collect();
+                let lines = soulver::calculate_lines(&sheet.join("\n"))?;
+                match format {
+                    csv::ImportFormat::Zipped => outputln!("{}", soulver::zip(&lines)),
+                    csv::ImportFormat::Csv => {
+                        if let Some(mut header) = header {
+                            header.push("result".to_owned());
+                            outputln!("{}", csv::format_row(&header));
+                        }
+                        for (index, mut row) in rows.into_iter().enumerate() {
+                            row.push(
+                                lines
+                                    .get(index)
+                                    .map_or(String::new(), |line| line.output.clone()),
+                            );
+                            outputln!("{}", csv::format_row(&row));
+                        }
+                    }
+                }
+            }
+        },
+        Commands::List {
+            mut directories,
+            format,
+        } => {
+            if directories.is_empty() {
+                directories = library::directories(&config);
+            }
+            let sheets = library::sheets(&directories)?;
+            if !sheets.is_empty() || format == library::ListFormat::Json {
+                outputln!("{}", library::format_sheets(&sheets, format));
+            }
+        }
+        Commands::New {
+            path,
+            template,
+            force,
+        } => {
+            let today = cli.today.unwrap_or_else(dates::Date::today);
+            scaffold::new_sheet(&path, &template, today, force)?;
+        }
+        Commands::Snippet { command } => match command {
+            SnippetCommands::Save { name, file } => {
+                let sheet = match file {
+                    Some(file) => fs::read_to_string(file)?,
+                    None => std::io::read_to_string(std::io::stdin())?,
+                };
+                snippets::save(&name, &sheet)?;
+            }
+            SnippetCommands::Run { name, definitions } => {
+                let (sheet, prelude) =
+                    snippets::apply_definitions(&snippets::load(&name)?, &definitions);
+                let prelude: Vec<&str> = prelude.iter().map(String::as_str).collect();
+                let lines = soulver::calculate_lines_with_prelude(&prelude, &sheet)?;
+                outputln!("{}", soulver::zip(&lines));
+            }
+            SnippetCommands::List => {
+                for name in snippets::list()? {
+                    outputln!("{name}");
+                }
+            }
+        },
+        Commands::Preview { file, port } => preview::serve(&file, port)?,
+        Commands::Repl { resume } => repl::repl(resume.as_deref())?,
+        Commands::Tui { file } => tui::tui(&file)?,
+        Commands::Watch {
+            file,
+            debounce,
+            highlight,
+            output,
+        } => watch::watch(
+            &file,
+            Duration::from_millis(debounce),
+            highlight,
+            output.as_deref(),
+        )?,
+        Commands::Service { command } => match command {
+            ServiceCommands::Install {
+                activate_on,
+                daemon_arguments,
+            } => service::install(activate_on.as_deref(), &daemon_arguments)?,
+            ServiceCommands::Uninstall => service::uninstall()?,
+            ServiceCommands::Status => return service::status(),
+        },
+        Commands::Daemon {
+            listen,
+            socket,
+            socket_activated,
+            metrics,
+            max_sheet_size,
+            max_requests_per_minute,
+            max_connections,
+        } => {
+            let limits = daemon::Limits {
+                max_sheet_size,
+                max_requests_per_minute,
+                max_connections,
+            };
+            let listener = match socket {
+                _ if socket_activated => daemon::Listener::inherited()?,
+                Some(path) => daemon::Listener::bind_unix(&path)?,
+                None => daemon::Listener::bind_tcp(&listen)?,
+            };
+            let token = if listener.requires_token() || metrics.is_some() {
+                Some(daemon::token(&config)?)
+            } else {
+                None
+            };
+            daemon::serve(&listener, token.as_deref(), metrics.as_deref(), &limits)?
+        }
+        Commands::Bench {
+            file,
+            runs,
+            programs,
+        } => {
+            let sheet = match file {
+                Some(file) => fs::read_to_string(file)?,
+                None => std::io::read_to_string(std::io::stdin())?,
+            };
+            let width = programs
+                .iter()
+                .map(|program| program.display().to_string().chars().count())
+                .max()
+                .unwrap_or(0);
+            for program in programs {
+                let summary = bench::bench(&program, &sheet, runs.get())?;
+                let program = program.display().to_string();
+                outputln!("{program:<width$}  {summary}");
+            }
         }
         Commands::Completions { shell } => {
             shell.generate(&mut Cli::command(), &mut std::io::stdout());
         }
     }
-    Ok(())
+    Ok(ExitCode::SUCCESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("check", "check"), 0);
+        assert_eq!(edit_distance("chek", "check"), 1);
+        assert_eq!(edit_distance("calcutale", "calculate"), 2);
+        assert_eq!(edit_distance("", "tui"), 3);
+    }
+
+    #[test]
+    fn test_no_result_error() {
+        assert_eq!(
+            no_result_error("chek").to_string(),
+            "no result for `chek`; did you mean the subcommand `check`?",
+        );
+        assert_eq!(
+            no_result_error("2 weeks from tomorow").to_string(),
+            "no result for `2 weeks from tomorow`",
+        );
+    }
 }
@@ -0,0 +1,118 @@
+use std::fmt::Write as _;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The upper bounds in seconds of the buckets request durations are counted in.
+const DURATION_BUCKETS: [f64; 8] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Counts of what the daemon has done, to expose to Prometheus.
+#[derive(Default)]
+pub struct Metrics {
+    requests: AtomicU64,
+    soulver_failures: AtomicU64,
+    rejected_connections: AtomicU64,
+    rate_limited_requests: AtomicU64,
+    /// The number of requests that took at most each of [`DURATION_BUCKETS`]
+    duration_buckets: [AtomicU64; DURATION_BUCKETS.len()],
+    duration_sum_micros: AtomicU64,
+}
+
+impl Metrics {
+    /// Count a request for a sheet, which took `duration` and failed if `soulver` did.
+    pub fn record_request(&self, duration: Duration, failed: bool) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        if failed {
+            self.soulver_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        let seconds = duration.as_secs_f64();
+        for (bucket, bound) in self.duration_buckets.iter().zip(DURATION_BUCKETS) {
+            if seconds <= bound {
+                bucket.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        self.duration_sum_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// Count a connection rejected for sending the wrong token.
+    pub fn record_rejected_connection(&self) {
+        self.rejected_connections.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a request refused for exceeding the client's rate limit.
+    pub fn record_rate_limited_request(&self) {
+        self.rate_limited_requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let requests = self.requests.load(Ordering::Relaxed);
+        let mut text = String::new();
+        for (name, help, value) in [
+            (
+                "soulver_cli_zipper_daemon_requests_total",
+                "Sheets calculated for clients.",
+                requests,
+            ),
+            (
+                "soulver_cli_zipper_daemon_soulver_failures_total",
+                "Sheets soulver failed to calculate.",
+                self.soulver_failures.load(Ordering::Relaxed),
+            ),
+            (
+                "soulver_cli_zipper_daemon_rejected_connections_total",
+                "Connections rejected for an invalid token.",
+                self.rejected_connections.load(Ordering::Relaxed),
+            ),
+            (
+                "soulver_cli_zipper_daemon_rate_limited_requests_total",
+                "Sheets refused for exceeding the client's rate limit.",
+                self.rate_limited_requests.load(Ordering::Relaxed),
+            ),
+        ] {
+            writeln!(
+                text,
+                "# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}"
+            )
+            .unwrap();
+        }
+
+        let name = "soulver_cli_zipper_daemon_request_duration_seconds";
+        writeln!(
+            text,
+            "# HELP {name} How long sheets took to calculate.\n# TYPE {name} histogram"
+        )
+        .unwrap();
+        for (bucket, bound) in self.duration_buckets.iter().zip(DURATION_BUCKETS) {
+            let count = bucket.load(Ordering::Relaxed);
+            writeln!(text, "{name}_bucket{{le=\"{bound}\"}} {count}").unwrap();
+        }
+        let sum = self.duration_sum_micros.load(Ordering::Relaxed) as f64 / 1e6;
+        writeln!(text, "{name}_bucket{{le=\"+Inf\"}} {requests}").unwrap();
+        writeln!(text, "{name}_sum {sum}").unwrap();
+        writeln!(text, "{name}_count {requests}").unwrap();
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics.record_request(Duration::from_millis(200), false);
+        metrics.record_request(Duration::from_secs(3), true);
+        metrics.record_rejected_connection();
+        let text = metrics.render();
+        assert!(text.contains("\nsoulver_cli_zipper_daemon_requests_total 2\n"));
+        assert!(text.contains("\nsoulver_cli_zipper_daemon_soulver_failures_total 1\n"));
+        assert!(text.contains("\nsoulver_cli_zipper_daemon_rejected_connections_total 1\n"));
+        assert!(text.contains("_bucket{le=\"0.1\"} 0\n"));
+        assert!(text.contains("_bucket{le=\"0.25\"} 1\n"));
+        assert!(text.contains("_bucket{le=\"5\"} 2\n"));
+        assert!(text.contains("_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("_sum 3.2\n"));
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+
+use crate::soulver;
+
+/// The environment variable with the path of a file of `input => output` lines, giving the
+/// results of inputs the mock backend cannot calculate itself.
+pub const RESPONSES_VARIABLE: &str = "SOULVER_CLI_ZIPPER_MOCK_RESPONSES";
+
+/// The scripted results, by their trimmed inputs.
+static RESPONSES: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+fn parse_responses(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once(" => "))
+        .map(|(input, output)| (input.trim().to_owned(), output.trim().to_owned()))
+        .collect()
+}
+
+fn responses() -> Result<&'static HashMap<String, String>> {
+    if let Some(responses) = RESPONSES.get() {
+        return Ok(responses);
+    }
+    let responses = match env::var_os(RESPONSES_VARIABLE) {
+        Some(path) => {
+            let text = fs::read_to_string(&path).with_context(|| {
+                format!(
+                    "failed to read the mock responses {}",
+                    path.to_string_lossy()
+                )
+            })?;
+            parse_responses(&text)
+        }
+        None => HashMap::new(),
+    };
+    Ok(RESPONSES.get_or_init(|| responses))
+}
+
+/// A calculated value, with the currency symbol it is in, if any.
+#[derive(Clone, Copy)]
+struct Value {
+    number: f64,
+    currency: Option<char>,
+}
+
+/// Group the digits of the integer part of a number with commas, e.g. `1234.5` to `1,234.5`.
+fn group_digits(formatted: &str) -> String {
+    let (integer, fraction) = formatted
+        .split_once('.')
+        .map_or((formatted, None), |(integer, fraction)| {
+            (integer, Some(fraction))
+        });
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    grouped
+}
+
+/// Format a value the way `soulver` does: currencies with two decimal places, and other numbers
+/// with up to two.
+fn format_value(value: Value) -> String {
+    let sign = if value.number < 0.0 { "-" } else { "" };
+    let magnitude = value.number.abs();
+    match value.currency {
+        Some(symbol) => format!("{sign}{symbol}{}", group_digits(&format!("{magnitude:.2}"))),
+        None if (magnitude - magnitude.round()).abs() < 1e-9 => {
+            format!("{sign}{}", group_digits(&format!("{magnitude:.0}")))
+        }
+        None => {
+            let formatted = format!("{magnitude:.2}");
+            let formatted = formatted.trim_end_matches('0').trim_end_matches('.');
+            format!("{sign}{}", group_digits(formatted))
+        }
+    }
+}
+
+/// A recursive-descent evaluator of `+`, `-`, `*`, `/`, and parentheses over numbers, currency
+/// amounts, and variables.
+struct Evaluator<'a> {
+    rest: &'a str,
+    variables: &'a HashMap<String, Value>,
+    currency: Option<char>,
+}
+
+impl Evaluator<'_> {
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        match self.rest.strip_prefix(c) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expression(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        loop {
+            if self.eat('+') {
+                value += self.term()?;
+            } else if self.eat('-') {
+                value -= self.term()?;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.factor()?;
+        loop {
+            if self.eat('*') {
+                value *= self.factor()?;
+            } else if self.eat('/') {
+                let divisor = self.factor()?;
+                if divisor == 0.0 {
+                    return None;
+                }
+                value /= divisor;
+            } else {
+                return Some(value);
+            }
+        }
+    }
+
+    fn factor(&mut self) -> Option<f64> {
+        if self.eat('-') {
+            return self.factor().map(|value| -value);
+        }
+        if self.eat('(') {
+            let value = self.expression()?;
+            return self.eat(')').then_some(value);
+        }
+        self.skip_whitespace();
+        if let Some(symbol) = self.rest.chars().next().filter(|c| "£$€".contains(*c)) {
+            self.currency = self.currency.or(Some(symbol));
+            self.rest = &self.rest[symbol.len_utf8()..];
+        }
+        let length = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+            .unwrap_or(self.rest.len());
+        if length > 0 {
+            let number = self.rest[..length].replace(',', "").parse().ok()?;
+            self.rest = &self.rest[length..];
+            return Some(number);
+        }
+        self.variable()
+    }
+
+    /// The value of the longest variable name the rest of the expression starts with.
+    fn variable(&mut self) -> Option<f64> {
+        let (name, value) = self
+            .variables
+            .iter()
+            .filter(|(name, _)| {
+                self.rest.strip_prefix(name.as_str()).is_some_and(|rest| {
+                    !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_')
+                })
+            })
+            .max_by_key(|(name, _)| name.len())?;
+        self.rest = &self.rest[name.len()..];
+        self.currency = self.currency.or(value.currency);
+        Some(value.number)
+    }
+}
+
+/// Evaluate an expression, if it is only arithmetic over numbers and `variables`.
+fn evaluate(expression: &str, variables: &HashMap<String, Value>) -> Option<Value> {
+    let mut evaluator = Evaluator {
+        rest: expression,
+        variables,
+        currency: None,
+    };
+    let number = evaluator.expression()?;
+    evaluator.skip_whitespace();
+    evaluator.rest.is_empty().then_some(Value {
+        number,
+        currency: evaluator.currency,
+    })
+}
+
+/// Split an assignment like `Foo bar = 1 + 2` into its variable's name and expression.
+fn parse_assignment(line: &str) -> Option<(&str, &str)> {
+    let (name, expression) = line.split_once('=')?;
+    let name = name.trim();
+    let is_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ' ');
+    is_name.then_some((name, expression.trim()))
+}
+
+/// Calculate a sheet deterministically, returning what `soulver` would output for it.
+///
+/// Only arithmetic, currency amounts, variables, and labels are calculated, along with the
+/// scripted responses from [`RESPONSES_VARIABLE`].
+pub fn calculate(sheet: &str) -> Result<String> {
+    let responses = responses()?;
+    let mut variables = HashMap::new();
+    let mut outputs = Vec::new();
+    for line in sheet.lines() {
+        let trimmed = line.trim();
+        let output = if let Some(output) = responses.get(trimmed) {
+            output.clone()
+        } else if trimmed.is_empty() || soulver::is_comment(trimmed) {
+            String::new()
+        } else if let Some((name, expression)) = parse_assignment(trimmed) {
+            match evaluate(expression, &variables) {
+                Some(value) => {
+                    variables.insert(name.to_owned(), value);
+                    format_value(value)
+                }
+                None => String::new(),
+            }
+        } else {
+            let expression = trimmed
+                .split_once(':')
+                .map_or(trimmed, |(_, expression)| expression);
+            evaluate(expression, &variables)
+                .map(format_value)
+                .unwrap_or_default()
+        };
+        // `soulver` outputs nothing for the lines before the first that could have a result
+        if outputs.is_empty()
+            && output.is_empty()
+            && (trimmed.is_empty() || soulver::is_comment(trimmed) || soulver::is_divider(trimmed))
+        {
+            continue;
+        }
+        outputs.push(output);
+    }
+    if outputs.is_empty() {
+        return Ok(String::new());
+    }
+    Ok(format!("{}\n", outputs.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_value() {
+        let value = |number, currency| format_value(Value { number, currency });
+        assert_eq!(value(3.0, None), "3");
+        assert_eq!(value(1234567.0, None), "1,234,567");
+        assert_eq!(value(1.0 / 3.0, None), "0.33");
+        assert_eq!(value(2.5, None), "2.5");
+        assert_eq!(value(-1500.0, Some('£')), "-£1,500.00");
+    }
+
+    #[test]
+    fn test_calculate() {
+        assert_eq!(
+            calculate("\n# Costs\nRent: £1,000\nFoo bar = 2 * (3 + 4)\nFoo bar / 7\nfoo\n---")
+                .unwrap(),
+            "£1,000.00\n14\n2\n\n\n",
+        );
+        assert_eq!(calculate("# Heading\n").unwrap(), "");
+    }
+
+    #[test]
+    fn test_parse_responses() {
+        assert_eq!(
+            parse_responses("£10 in USD => $12.70\nno arrow"),
+            HashMap::from([("£10 in USD".to_owned(), "$12.70".to_owned())]),
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+use clap::ValueEnum;
+
+use crate::json;
+
+/// How many times `--quiet` was given: once silences normal output, and twice errors too.
+static QUIET: AtomicU8 = AtomicU8::new(0);
+
+/// Whether `--verbose` was given.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+/// Whether `--log-format json` was given.
+static LOG_JSON: AtomicBool = AtomicBool::new(false);
+
+/// How events are logged to stderr.
+#[derive(Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// A JSON object on each line, with the name of the event as `event`
+    Json,
+}
+
+/// How `--error-format` reports lines that failed to calculate, if it was given.
+static ERROR_FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+
+/// How lines that failed to calculate are reported to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ErrorFormat {
+    /// `file:line: message`, for Emacs `compilation-mode` and other tools following the GNU
+    /// coding standards
+    Gnu,
+    /// `file:line:message`, for Vim's quickfix list with its default `errorformat`
+    Quickfix,
+}
+
+pub fn set_quiet(level: u8) {
+    QUIET.store(level, Ordering::Relaxed);
+}
+
+pub fn set_verbose(verbose: bool) {
+    VERBOSE.store(verbose, Ordering::Relaxed);
+}
+
+pub fn set_log_format(format: LogFormat) {
+    LOG_JSON.store(format == LogFormat::Json, Ordering::Relaxed);
+}
+
+pub fn set_error_format(format: ErrorFormat) {
+    ERROR_FORMAT
+        .set(format)
+        .expect("the error format is only set once");
+}
+
+/// Whether lines that failed to calculate are reported with `--error-format`.
+pub fn reports_line_errors() -> bool {
+    ERROR_FORMAT.get().is_some()
+}
+
+/// Whether to trace how `soulver` is run to stderr.
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+/// Whether normal output to stdout is silenced.
+pub fn is_output_silenced() -> bool {
+    QUIET.load(Ordering::Relaxed) >= 1
+}
+
+/// Whether errors written to stderr are silenced.
+pub fn are_errors_silenced() -> bool {
+    QUIET.load(Ordering::Relaxed) >= 2
+}
+
+/// Like `print!`, unless normal output is silenced.
+macro_rules! output {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_output_silenced() {
+            print!($($arg)*);
+        }
+    };
+}
+
+/// Like `println!`, unless normal output is silenced.
+macro_rules! outputln {
+    ($($arg:tt)*) => {
+        if !$crate::output::is_output_silenced() {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Log an event to stderr, as its text or, with `--log-format json`, as an object of its name and
+/// fields.
+pub fn log(event: &str, text: &str, fields: Vec<(&str, json::Value)>) {
+    if LOG_JSON.load(Ordering::Relaxed) {
+        let fields = [("event", json::Value::from(event))]
+            .into_iter()
+            .chain(fields);
+        eprintln!("{}", json::Value::object(fields));
+    } else {
+        eprintln!("{text}");
+    }
+}
+
+/// Log an event with `--verbose`.
+pub fn trace(event: &str, text: &str, fields: Vec<(&str, json::Value)>) {
+    if is_verbose() {
+        log(event, text, fields);
+    }
+}
+
+/// Log an error, optionally with the sheet it is about, unless errors are silenced.
+pub fn log_error(sheet: Option<&str>, error: &anyhow::Error) {
+    if are_errors_silenced() {
+        return;
+    }
+    let text = match sheet {
+        Some(sheet) => format!("error: {sheet}: {error:#}"),
+        None => format!("Error: {error:?}"),
+    };
+    log(
+        "error",
+        &text,
+        vec![
+            ("sheet", json::Value::from(sheet)),
+            ("message", json::Value::from(format!("{error:#}"))),
+        ],
+    );
+}
+
+/// Report a line of a sheet that failed to calculate in the `--error-format`, if it was given and
+/// errors are not silenced.
+pub fn log_line_error(sheet: &str, line_number: usize, message: &str) {
+    let Some(format) = ERROR_FORMAT.get() else {
+        return;
+    };
+    if are_errors_silenced() {
+        return;
+    }
+    let text = match format {
+        ErrorFormat::Gnu => format!("{sheet}:{line_number}: {message}"),
+        ErrorFormat::Quickfix => format!("{sheet}:{line_number}:{message}"),
+    };
+    log(
+        "line_error",
+        &text,
+        vec![
+            ("sheet", json::Value::from(sheet)),
+            ("line", json::Value::from(line_number)),
+            ("message", json::Value::from(message)),
+        ],
+    );
+}
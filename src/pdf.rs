@@ -0,0 +1,173 @@
+use crate::soulver::{self, Line};
+use crate::totals;
+
+/// The size of an A4 page in points.
+const PAGE_WIDTH: f64 = 595.0;
+const PAGE_HEIGHT: f64 = 842.0;
+const MARGIN: f64 = 50.0;
+
+/// The largest font size lines are typeset at, which is reduced for wide sheets to fit the page.
+const MAX_FONT_SIZE: f64 = 10.0;
+const MIN_FONT_SIZE: f64 = 5.0;
+/// The width of each character of Courier as a fraction of the font size.
+const CHARACTER_WIDTH: f64 = 0.6;
+const LINE_HEIGHT: f64 = 1.2;
+
+/// Encode text in the `WinAnsiEncoding` of the standard fonts, replacing characters it cannot
+/// encode with `?`.
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            '\u{20}'..='\u{7E}' | '\u{A0}'..='\u{FF}' => c as u8,
+            '€' => 0x80,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+/// A PDF string of text, with bytes outside ASCII escaped so content streams stay ASCII.
+fn string(text: &str) -> String {
+    let mut string = String::from("(");
+    for byte in encode(text) {
+        match byte {
+            b'(' | b')' | b'\\' => {
+                string.push('\\');
+                string.push(byte as char);
+            }
+            0x20..=0x7E => string.push(byte as char),
+            _ => string.push_str(&format!("\\{byte:03o}")),
+        }
+    }
+    string.push(')');
+    string
+}
+
+/// Typeset the zipped lines of a sheet in Courier on A4 pages, with `#` headings in bold.
+pub fn document(lines: &[Line]) -> Vec<u8> {
+    let zipped = soulver::zip(lines);
+    let rows: Vec<(&str, bool)> = zipped
+        .lines()
+        .zip(lines)
+        .map(|(row, line)| (row, totals::heading(line).is_some()))
+        .collect();
+
+    let widest_row = rows
+        .iter()
+        .map(|(row, _)| row.chars().count())
+        .max()
+        .unwrap_or(0);
+    let font_size = (PAGE_WIDTH - 2.0 * MARGIN) / (CHARACTER_WIDTH * widest_row.max(1) as f64);
+    let font_size = font_size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE);
+    let leading = font_size * LINE_HEIGHT;
+    let rows_per_page = ((PAGE_HEIGHT - 2.0 * MARGIN) / leading) as usize;
+
+    let pages: Vec<String> = if rows.is_empty() {
+        vec![String::new()]
+    } else {
+        rows.chunks(rows_per_page)
+            .map(|rows| {
+                let mut content = format!(
+                    "BT\n{leading:.2} TL\n{MARGIN} {:.2} Td\n",
+                    PAGE_HEIGHT - MARGIN - font_size,
+                );
+                for (row, bold) in rows {
+                    let font = if *bold { "F2" } else { "F1" };
+                    content.push_str(&format!(
+                        "/{font} {font_size:.2} Tf {} Tj T*\n",
+                        string(row)
+                    ));
+                }
+                content.push_str("ET\n");
+                content
+            })
+            .collect()
+    };
+
+    // The catalog, page tree, and fonts come first, then each page and its content stream
+    let page_ids: Vec<usize> = (0..pages.len()).map(|index| 5 + 2 * index).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            page_ids
+                .iter()
+                .map(|id| format!("{id} 0 R"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            pages.len(),
+        ),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>"
+            .to_owned(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier-Bold /Encoding /WinAnsiEncoding >>"
+            .to_owned(),
+    ];
+    for (content, page_id) in pages.iter().zip(&page_ids) {
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {PAGE_WIDTH} {PAGE_HEIGHT}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+            page_id + 1,
+        ));
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{content}endstream",
+            content.len(),
+        ));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{object}\nendobj\n", index + 1));
+    }
+    let xref_offset = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\n0000000000 65535 f \n",
+        objects.len() + 1
+    ));
+    for offset in offsets {
+        pdf.push_str(&format!("{offset:010} 00000 n \n"));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n",
+        objects.len() + 1,
+    ));
+    pdf.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string() {
+        assert_eq!(string("f(x) = \\"), r"(f\(x\) = \\)");
+        assert_eq!(string("£5 + €5 = ✓"), r"(\2435 + \2005 = ?)");
+    }
+
+    #[test]
+    fn test_document() {
+        let pdf = document(&[
+            Line::new("# Costs", ""),
+            Line::new("lunch: $12 + $3", "$15.00"),
+        ]);
+        let pdf = String::from_utf8(pdf).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.contains("/F2 10.00 Tf (# Costs         |) Tj T*\n"));
+        assert!(pdf.contains("/F1 10.00 Tf (lunch: $12 + $3 | $15.00) Tj T*\n"));
+        // The cross-reference table points at each object
+        let xref_offset: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[xref_offset..].starts_with("xref\n0 7\n"));
+        let catalog_offset: usize = pdf[xref_offset..].lines().nth(3).unwrap()[..10]
+            .parse()
+            .unwrap();
+        assert!(pdf[catalog_offset..].starts_with("1 0 obj\n<< /Type /Catalog"));
+    }
+
+    #[test]
+    fn test_document_pages() {
+        let lines = vec![Line::new("1 + 1", "2"); 100];
+        let pdf = String::from_utf8(document(&lines)).unwrap();
+        assert!(pdf.contains("/Count 2 >>"));
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{self, Write};
+use std::process::{Child, Command, Output, Stdio};
+use std::thread;
+
+use anyhow::{Context, Result, bail};
+
+use crate::json;
+use crate::soulver::Line;
+
+/// The prefix of the executables in `PATH` that output formats `--format` has no built-in for.
+pub const FORMATTER_PREFIX: &str = "soulver-zipper-format-";
+
+/// The rows a formatter is given on stdin, as a JSON array of objects with `input` and `output`.
+pub fn rows(lines: &[Line]) -> json::Value {
+    json::Value::from(
+        lines
+            .iter()
+            .map(|line| {
+                json::Value::object([
+                    ("input", json::Value::from(line.input.as_str())),
+                    ("output", json::Value::from(line.output.as_str())),
+                ])
+            })
+            .collect::<Vec<_>>(),
+    )
+}
+
+/// Give a child with piped stdin and stdout its input while reading its output, then wait for it to
+/// exit.
+///
+/// The input is written from another thread, so a child that outputs as it reads, like `cat`, does
+/// not block forever once the pipes' buffers fill.
+pub fn communicate(mut child: Child, input: &[u8]) -> io::Result<Output> {
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            // The child may not read all of stdin, which is not an error
+            let _ = stdin.write_all(input);
+        });
+        child.wait_with_output()
+    })
+}
+
+/// Format a sheet's rows with the `soulver-zipper-format-NAME` executable, returning its stdout.
+pub fn format(name: &str, lines: &[Line]) -> Result<String> {
+    let program = format!("{FORMATTER_PREFIX}{name}");
+    let child = match Command::new(&program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            bail!("unknown format {name:?}, and no {program} formatter in PATH")
+        }
+        Err(error) => return Err(error).with_context(|| format!("failed to run {program}")),
+    };
+    let output = communicate(child, rows(lines).to_string().as_bytes())?;
+    if !output.status.success() {
+        bail!("{program} failed with {}", output.status);
+    }
+    let stdout = String::from_utf8(output.stdout)
+        .with_context(|| format!("{program} output invalid UTF-8"))?;
+    Ok(stdout.strip_suffix('\n').unwrap_or(&stdout).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows() {
+        let lines = [Line {
+            input: "1 + 1".to_owned(),
+            output: "2".to_owned(),
+        }];
+        assert_eq!(
+            rows(&lines).to_string(),
+            r#"[{"input":"1 + 1","output":"2"}]"#
+        );
+    }
+
+    #[test]
+    fn test_communicate() {
+        // Much more than fits in the pipes' buffers
+        let input = "1 + 1\n".repeat(100_000);
+        let child = Command::new("cat")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let output = communicate(child, input.as_bytes()).unwrap();
+        assert_eq!(output.stdout, input.as_bytes());
+    }
+
+    #[test]
+    fn test_format_unknown() {
+        let error = format("definitely-not-installed", &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown format \"definitely-not-installed\", and no \
+             soulver-zipper-format-definitely-not-installed formatter in PATH",
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use clap::ValueEnum;
+
+use crate::archive;
+use crate::font::{self, GLYPH_HEIGHT, GLYPH_WIDTH};
+use crate::soulver::{self, Line};
+use crate::totals;
+
+/// How many pixels wide and tall each pixel of a glyph is drawn.
+const SCALE: usize = 2;
+/// The space around the sheet in pixels.
+const PADDING: usize = 16;
+
+type Colour = [u8; 3];
+
+/// The colours an image is rendered in.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+struct Palette {
+    background: Colour,
+    input: Colour,
+    heading: Colour,
+    result: Colour,
+    /// The colour of the ` | ` between inputs and results, and of dividers
+    rule: Colour,
+}
+
+impl Theme {
+    fn palette(self) -> Palette {
+        match self {
+            Self::Dark => Palette {
+                background: [0x1E, 0x1E, 0x2E],
+                input: [0xCD, 0xD6, 0xF4],
+                heading: [0xF9, 0xE2, 0xAF],
+                result: [0xA6, 0xE3, 0xA1],
+                rule: [0x6C, 0x70, 0x86],
+            },
+            Self::Light => Palette {
+                background: [0xFA, 0xFA, 0xFA],
+                input: [0x38, 0x3A, 0x42],
+                heading: [0xC1, 0x84, 0x01],
+                result: [0x40, 0xA0, 0x2B],
+                rule: [0xA0, 0xA1, 0xA7],
+            },
+        }
+    }
+}
+
+/// An image of RGB pixels.
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    fn new(width: usize, height: usize, background: Colour) -> Self {
+        Self {
+            width,
+            height,
+            pixels: background.repeat(width * height),
+        }
+    }
+
+    /// Draw a character with its top left corner at a pixel, also covering the pixel to the right
+    /// of each of its pixels in bold.
+    fn draw(&mut self, c: char, x: usize, y: usize, colour: Colour, bold: bool) {
+        let glyph = font::glyph(c);
+        for (row, coverages) in glyph.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH + usize::from(bold) {
+                let mut coverage = coverages.get(column).copied().unwrap_or(0);
+                if bold && column > 0 {
+                    coverage = coverage.max(coverages[column - 1]);
+                }
+                if coverage == 0 {
+                    continue;
+                }
+                for dy in 0..SCALE {
+                    for dx in 0..SCALE {
+                        self.blend(
+                            x + column * SCALE + dx,
+                            y + row * SCALE + dy,
+                            colour,
+                            coverage,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Blend a colour into a pixel by a coverage from 0 to 15.
+    fn blend(&mut self, x: usize, y: usize, colour: Colour, coverage: u8) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+        let index = 3 * (y * self.width + x);
+        for (channel, target) in self.pixels[index..index + 3].iter_mut().zip(colour) {
+            *channel = ((u16::from(*channel) * u16::from(15 - coverage)
+                + u16::from(target) * u16::from(coverage))
+                / 15) as u8;
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let mut header = Vec::new();
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        // 8 bits per channel of RGB, with the standard compression and filters and no interlacing
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        // Each row starts with its filter, which is always none
+        let mut rows = Vec::with_capacity(self.pixels.len() + self.height);
+        for row in self.pixels.chunks(3 * self.width) {
+            rows.push(0);
+            rows.extend_from_slice(row);
+        }
+        for (kind, data) in [
+            (b"IHDR", header),
+            (b"IDAT", archive::zlib(&rows)),
+            (b"IEND", Vec::new()),
+        ] {
+            png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            let start = png.len();
+            png.extend_from_slice(kind);
+            png.extend_from_slice(&data);
+            let crc = archive::crc32(&png[start..]);
+            png.extend_from_slice(&crc.to_be_bytes());
+        }
+        png
+    }
+}
+
+/// Render the zipped lines of a sheet to a PNG image in a monospace font, with inputs, results,
+/// and `#` headings in the theme's colours.
+pub fn render(lines: &[Line], theme: Theme) -> Vec<u8> {
+    let palette = theme.palette();
+    let zipped = soulver::zip(lines);
+    let rows: Vec<Vec<char>> = zipped.lines().map(|row| row.chars().collect()).collect();
+    let input_width = lines
+        .iter()
+        .map(|line| line.input.chars().count())
+        .max()
+        .unwrap_or(0);
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let cell_width = GLYPH_WIDTH * SCALE;
+    let cell_height = GLYPH_HEIGHT * SCALE;
+
+    let mut image = Image::new(
+        2 * PADDING + columns * cell_width,
+        2 * PADDING + rows.len() * cell_height,
+        palette.background,
+    );
+    for (index, (row, line)) in rows.iter().zip(lines).enumerate() {
+        let is_heading = totals::heading(line).is_some();
+        let is_divider = line.output.is_empty() && soulver::is_divider(&line.input);
+        for (column, c) in row.iter().enumerate() {
+            let colour = if is_divider || (input_width..input_width + 3).contains(&column) {
+                palette.rule
+            } else if column > input_width {
+                palette.result
+            } else if is_heading {
+                palette.heading
+            } else {
+                palette.input
+            };
+            image.draw(
+                *c,
+                PADDING + column * cell_width,
+                PADDING + index * cell_height,
+                colour,
+                is_heading,
+            );
+        }
+    }
+    image.encode()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let lines = [
+            Line {
+                input: "# Costs".to_owned(),
+                output: String::new(),
+            },
+            Line {
+                input: "1 + 1".to_owned(),
+                output: "2".to_owned(),
+            },
+        ];
+        let png = render(&lines, Theme::Dark);
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR"));
+        // "1 + 1   | 2" is the widest row, 11 columns of 16 pixels, and there are 2 rows of 32
+        assert_eq!(png[16..20], (2 * 16 + 11 * 16_u32).to_be_bytes());
+        assert_eq!(png[20..24], (2 * 16 + 2 * 32_u32).to_be_bytes());
+        assert!(png.ends_with(b"IEND\xae\x42\x60\x82"));
+    }
+
+    #[test]
+    fn test_blend() {
+        let mut image = Image::new(1, 1, [0, 0, 0]);
+        image.blend(0, 0, [30, 60, 90], 5);
+        assert_eq!(image.pixels, [10, 20, 30]);
+        image.blend(0, 0, [30, 30, 30], 15);
+        assert_eq!(image.pixels, [30, 30, 30]);
+    }
+}
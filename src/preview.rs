@@ -0,0 +1,171 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use anyhow::{Context, Result};
+
+use crate::soulver;
+use crate::task;
+
+/// How often the sheet is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the preview waits to read a request from or send an event to a browser, so a browser
+/// that stopped reading cannot hold a thread forever.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many browser connections are served at once, counting each open page's event stream.
+const MAX_CONNECTIONS: NonZeroUsize = NonZeroUsize::new(64).unwrap();
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body { margin: 2em; background: #1e1e2e; color: #cdd6f4; }
+pre { font: 14px/1.4 ui-monospace, monospace; }
+@media (prefers-color-scheme: light) { body { background: #fafafa; color: #383a42; } }
+</style>
+</head>
+<body>
+<pre id="sheet">{sheet}</pre>
+<script>
+new EventSource("/events").onmessage = (event) => {
+  document.getElementById("sheet").textContent = event.data;
+};
+</script>
+</body>
+</html>
+"#;
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// A server-sent event with text as its data, which cannot contain newlines so is split into a
+/// `data` field for each line.
+fn event(text: &str) -> String {
+    let mut event: String = text
+        .split('\n')
+        .map(|line| format!("data: {line}\n"))
+        .collect();
+    event.push('\n');
+    event
+}
+
+/// The path of an HTTP `GET` request from its request line.
+pub fn request_path(request_line: &str) -> Option<&str> {
+    let mut parts = request_line.split_whitespace();
+    if parts.next()? != "GET" {
+        return None;
+    }
+    let target = parts.next()?;
+    Some(target.split_once('?').map_or(target, |(path, _)| path))
+}
+
+/// The zipped sheet, or the error calculating it.
+fn render(path: &Path) -> String {
+    fs::read_to_string(path)
+        .with_context(|| format!("failed to read {}", path.display()))
+        .and_then(|sheet| soulver::run_soulver_zipped(&sheet))
+        .unwrap_or_else(|error| format!("error: {error:#}"))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Send the zipped sheet as an event whenever it changes, until the browser disconnects.
+fn send_events(stream: &mut TcpStream, path: &Path) -> std::io::Result<()> {
+    stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n",
+    )?;
+    let mut last_modified = modified(path);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let modified = modified(path);
+        if modified != last_modified {
+            last_modified = modified;
+            stream.write_all(event(&render(path)).as_bytes())?;
+        } else {
+            // A comment, so a closed page is noticed
+            stream.write_all(b":\n\n")?;
+        }
+        stream.flush()?;
+    }
+}
+
+fn handle(mut stream: TcpStream, path: &Path) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    match request_path(&request_line) {
+        Some("/") => {
+            let page = PAGE
+                .replace("{title}", &escape_html(&path.display().to_string()))
+                .replace("{sheet}", &escape_html(&render(path)));
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{page}",
+                page.len(),
+            )
+        }
+        Some("/events") => send_events(&mut stream, path),
+        _ => stream
+            .write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    }
+}
+
+/// Serve a page showing the zipped sheet on localhost, which reloads the sheet whenever it
+/// changes.
+pub fn serve(path: &Path, port: u16) -> Result<()> {
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("failed to listen on port {port}"))?;
+    outputln!(
+        "Previewing {} at http://{}/",
+        path.display(),
+        listener.local_addr()?,
+    );
+    let incoming = listener.incoming();
+    task::serve_connections(incoming, CONNECTION_TIMEOUT, MAX_CONNECTIONS, |stream| {
+        // The browser closing the page is not an error
+        let _ = handle(stream, path);
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event() {
+        assert_eq!(event("1 + 1 | 2"), "data: 1 + 1 | 2\n\n");
+        assert_eq!(event("a |\nb | 1"), "data: a |\ndata: b | 1\n\n");
+    }
+
+    #[test]
+    fn test_request_path() {
+        assert_eq!(request_path("GET / HTTP/1.1\r\n"), Some("/"));
+        assert_eq!(
+            request_path("GET /events?retry=1 HTTP/1.1\r\n"),
+            Some("/events")
+        );
+        assert_eq!(request_path("POST / HTTP/1.1\r\n"), None);
+        assert_eq!(request_path(""), None);
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape_html("<b> & \"c\""), "&lt;b&gt; &amp; &quot;c&quot;");
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Whether `--profile` was given.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// The nanoseconds spent in each [`Phase`], summed across sheets calculated in parallel.
+static NANOSECONDS: [AtomicU64; 3] = [const { AtomicU64::new(0) }; 3];
+
+/// A part of the work of calculating sheets timed by `--profile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Reading sheets from files or stdin
+    Read,
+    /// Preparing sheets for `soulver`, like applying templates, includes, and dates
+    Preprocess,
+    /// Running `soulver`
+    Soulver,
+}
+
+impl Phase {
+    const ALL: [Self; 3] = [Self::Read, Self::Preprocess, Self::Soulver];
+
+    fn description(self) -> &'static str {
+        match self {
+            Self::Read => "reading sheets",
+            Self::Preprocess => "preprocessing",
+            Self::Soulver => "running soulver",
+        }
+    }
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Run `f`, adding the time it takes to a phase with `--profile`.
+pub fn time<T>(phase: Phase, f: impl FnOnce() -> T) -> T {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return f();
+    }
+    let start = Instant::now();
+    let result = f();
+    let nanoseconds = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    NANOSECONDS[phase as usize].fetch_add(nanoseconds, Ordering::Relaxed);
+    result
+}
+
+/// How long each phase took in a run that took `total`, with anything else like zipping and
+/// formatting, if `--profile` was given.
+pub fn report(total: Duration) -> Option<String> {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let phases: Vec<(&str, Duration)> = Phase::ALL
+        .iter()
+        .map(|phase| {
+            let nanoseconds = NANOSECONDS[*phase as usize].load(Ordering::Relaxed);
+            (phase.description(), Duration::from_nanos(nanoseconds))
+        })
+        .collect();
+    let timed: Duration = phases.iter().map(|(_, duration)| *duration).sum();
+    let mut report: Vec<String> = phases
+        .iter()
+        .map(|(description, duration)| format!("{description:<24} {duration:>10.1?}"))
+        .collect();
+    report.push(format!(
+        "{:<24} {:>10.1?}",
+        "zipping and formatting",
+        total.saturating_sub(timed),
+    ));
+    report.push(format!("{:<24} {total:>10.1?}", "total"));
+    Some(report.join("\n"))
+}
@@ -0,0 +1,69 @@
+use std::io::{self, IsTerminal, Write};
+use std::sync::Mutex;
+
+/// Reports progress through a batch of sheets on stderr.
+///
+/// A status line and the final summary are only shown when stderr is a terminal, except that the
+/// summary is always shown for batches of more than one sheet where any failed, unless reporting
+/// is disabled, so scripts only see it when something went wrong.
+pub struct Progress {
+    total: usize,
+    enabled: bool,
+    status_line: bool,
+    counts: Mutex<Counts>,
+}
+
+#[derive(Default)]
+struct Counts {
+    succeeded: usize,
+    failed: usize,
+}
+
+impl Progress {
+    pub fn new(total: usize, quiet: bool) -> Self {
+        let enabled = !quiet && total > 1;
+        Self {
+            total,
+            enabled,
+            status_line: enabled && io::stderr().is_terminal(),
+            counts: Mutex::default(),
+        }
+    }
+
+    /// Record that a sheet has finished.
+    pub fn finish_sheet(&self, name: &str, success: bool) {
+        let mut counts = self.counts.lock().unwrap();
+        if success {
+            counts.succeeded += 1;
+        } else {
+            counts.failed += 1;
+        }
+        if self.status_line {
+            let done = counts.succeeded + counts.failed;
+            let mut stderr = io::stderr().lock();
+            let _ = write!(
+                stderr,
+                "\r\x1b[K[{done}/{}] {} succeeded, {} failed: {name}",
+                self.total, counts.succeeded, counts.failed,
+            );
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Clear the status line and print a summary.
+    pub fn finish(&self) {
+        let counts = self.counts.lock().unwrap();
+        if !self.enabled || !self.status_line && counts.failed == 0 {
+            return;
+        }
+        let mut stderr = io::stderr().lock();
+        if self.status_line {
+            let _ = write!(stderr, "\r\x1b[K");
+        }
+        let _ = writeln!(
+            stderr,
+            "{} sheets: {} succeeded, {} failed",
+            self.total, counts.succeeded, counts.failed,
+        );
+    }
+}
@@ -0,0 +1,415 @@
+use std::fmt;
+
+use clap::ValueEnum;
+
+/// How to round a value to a number of decimal places.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RoundingMode {
+    /// Round to the nearest value, rounding halfway values away from zero
+    HalfAwayFromZero,
+    /// Round to the nearest value, rounding halfway values to the nearest even digit
+    HalfEven,
+    /// Round towards positive infinity
+    Up,
+    /// Round towards negative infinity
+    Down,
+    /// Round towards zero, truncating the value
+    TowardZero,
+}
+
+impl RoundingMode {
+    /// Round a value as it is written in decimal rather than as the nearest binary float, which
+    /// for `1.005` is slightly less, so halfway values round the way they read.
+    fn round(self, value: f64, decimals: usize) -> f64 {
+        // The shortest digits that read back as the value, which are the digits `soulver` wrote
+        let digits = format!("{}", value.abs());
+        let rounded: f64 = self
+            .round_digits(&digits, value < 0.0, decimals)
+            .parse()
+            .expect("rounded digits are a number");
+        if value < 0.0 { -rounded } else { rounded }
+    }
+
+    /// Round the digits of a value's magnitude, like `1.005`, to a number of decimal places.
+    fn round_digits(self, digits: &str, negative: bool, decimals: usize) -> String {
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        let mut kept: Vec<u8> = integer.bytes().collect();
+        kept.extend(
+            fraction
+                .bytes()
+                .chain(std::iter::repeat(b'0'))
+                .take(decimals),
+        );
+        let dropped = fraction.as_bytes().get(decimals..).unwrap_or_default();
+        let round_away = match dropped.split_first() {
+            None => false,
+            Some(_) if dropped.iter().all(|digit| *digit == b'0') => false,
+            Some((first, rest)) => match self {
+                Self::HalfAwayFromZero => *first >= b'5',
+                Self::HalfEven if *first == b'5' && rest.iter().all(|digit| *digit == b'0') => {
+                    kept.last().is_some_and(|digit| (digit - b'0') % 2 == 1)
+                }
+                Self::HalfEven => *first >= b'5',
+                Self::Up => !negative,
+                Self::Down => negative,
+                Self::TowardZero => false,
+            },
+        };
+        if round_away {
+            // Add one to the last digit kept, carrying into the digits before it
+            let mut index = kept.len();
+            loop {
+                if index == 0 {
+                    kept.insert(0, b'1');
+                    break;
+                }
+                index -= 1;
+                if kept[index] == b'9' {
+                    kept[index] = b'0';
+                } else {
+                    kept[index] += 1;
+                    break;
+                }
+            }
+        }
+        let mut rounded = String::from_utf8(kept).expect("digits are ASCII");
+        if decimals > 0 {
+            rounded.insert(rounded.len() - decimals, '.');
+        }
+        rounded
+    }
+}
+
+/// A numeric result parsed from `soulver`'s output, e.g. `£1,234.50` or `12.5 kg`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    /// Text before the number such as a currency symbol, e.g. `£` or `US$`
+    pub prefix: String,
+    /// Text after the number such as a unit, e.g. ` kg` or `%`
+    pub suffix: String,
+    /// The number of decimal places the number was written with
+    pub decimals: usize,
+    /// Whether digits before the decimal point were grouped with `,`
+    pub grouped: bool,
+}
+
+/// Whether text around a number looks like a unit or currency rather than, e.g., part of a date.
+fn is_unit(text: &str, is_prefix: bool) -> bool {
+    let text = text.trim();
+    if text.chars().any(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    if is_prefix {
+        // Currency symbols (`$`, `£`) and codes (`USD`, `US$`), but not words like month names
+        text.chars().all(|c| !c.is_alphabetic() || c.is_uppercase())
+    } else {
+        !text.contains([':', '/'])
+    }
+}
+
+impl Quantity {
+    /// Parse a result, returning `None` if it isn't a single number with an optional unit.
+    pub fn parse(result: &str) -> Option<Self> {
+        let result = result.trim();
+        let (negative, rest) = match result.strip_prefix(['-', '−']) {
+            Some(rest) => (true, rest),
+            None => (false, result),
+        };
+
+        let number_start = rest.find(|c: char| c.is_ascii_digit())?;
+        let (prefix, rest) = rest.split_at(number_start);
+        let (negative, prefix) = match prefix.strip_suffix(['-', '−']) {
+            Some(prefix) if !negative => (true, prefix),
+            _ => (negative, prefix),
+        };
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == ',' || c == '.'))
+            .unwrap_or(rest.len());
+        let number = rest[..number_end].trim_end_matches([',', '.']);
+        let suffix = &rest[number.len()..];
+        if !is_unit(prefix, true) || !is_unit(suffix, false) || number.matches('.').count() > 1 {
+            return None;
+        }
+
+        let value: f64 = number.replace(',', "").parse().ok()?;
+        Some(Self {
+            value: if negative { -value } else { value },
+            prefix: prefix.to_owned(),
+            suffix: suffix.trim_end().to_owned(),
+            decimals: number
+                .split_once('.')
+                .map_or(0, |(_, decimals)| decimals.len()),
+            grouped: number.contains(','),
+        })
+    }
+
+    /// The prefix and suffix, identifying the quantity's unit or currency.
+    pub fn unit(&self) -> (&str, &str) {
+        (&self.prefix, &self.suffix)
+    }
+
+    /// Format a value in the same style as this quantity.
+    pub fn format_value(&self, value: f64) -> String {
+        let number = format!("{:.*}", self.decimals, value.abs());
+        let number = if self.grouped {
+            group_digits(&number)
+        } else {
+            number
+        };
+        let sign = if value < 0.0 && number.chars().any(|c| c.is_ascii_digit() && c != '0') {
+            "-"
+        } else {
+            ""
+        };
+        format!("{sign}{}{number}{}", self.prefix, self.suffix)
+    }
+
+    /// Round the quantity to a number of decimal places.
+    pub fn round(&self, decimals: usize, mode: RoundingMode) -> Self {
+        Self {
+            value: mode.round(self.value, decimals),
+            decimals,
+            ..self.clone()
+        }
+    }
+
+    /// The quantity without its unit or digit grouping.
+    pub fn plain(&self) -> Self {
+        Self {
+            prefix: String::new(),
+            suffix: String::new(),
+            grouped: false,
+            ..self.clone()
+        }
+    }
+}
+
+/// Formats the quantity in the style it was parsed from.
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.format_value(self.value))
+    }
+}
+
+/// Group the digits before the decimal point of a formatted number in threes with `,`.
+fn group_digits(number: &str) -> String {
+    let (integer, fraction) = match number.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (number, None),
+    };
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    if let Some(fraction) = fraction {
+        grouped.push('.');
+        grouped.push_str(fraction);
+    }
+    grouped
+}
+
+/// Parse the results in the most common unit, returning a quantity to format values in that unit
+/// like them and the value of each result in it.
+///
+/// Results in other units and non-numeric results are ignored. Returns `None` if there are no
+/// numeric results.
+pub fn dominant_unit<'a, I>(results: I) -> Option<(Quantity, Vec<f64>)>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let quantities: Vec<Quantity> = results.into_iter().filter_map(Quantity::parse).collect();
+    let dominant = quantities.iter().max_by_key(|quantity| {
+        let count = quantities
+            .iter()
+            .filter(|other| other.unit() == quantity.unit())
+            .count();
+        // Prefer the first unit to appear if there's a tie
+        let first_index = quantities
+            .iter()
+            .position(|other| other.unit() == quantity.unit());
+        (count, std::cmp::Reverse(first_index))
+    })?;
+
+    let in_unit: Vec<&Quantity> = quantities
+        .iter()
+        .filter(|quantity| quantity.unit() == dominant.unit())
+        .collect();
+    let style = Quantity {
+        decimals: in_unit.iter().map(|quantity| quantity.decimals).max()?,
+        grouped: in_unit.iter().any(|quantity| quantity.grouped),
+        ..dominant.clone()
+    };
+    Some((
+        style,
+        in_unit.iter().map(|quantity| quantity.value).collect(),
+    ))
+}
+
+/// Sum the results in the most common unit, formatted like them.
+///
+/// Results in other units and non-numeric results are ignored. Returns `None` if there are no
+/// numeric results.
+pub fn sum_dominant_unit<'a, I>(results: I) -> Option<String>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let (style, values) = dominant_unit(results)?;
+    Some(style.format_value(values.iter().sum()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(
+        value: f64,
+        prefix: &str,
+        suffix: &str,
+        decimals: usize,
+        grouped: bool,
+    ) -> Quantity {
+        Quantity {
+            value,
+            prefix: prefix.to_owned(),
+            suffix: suffix.to_owned(),
+            decimals,
+            grouped,
+        }
+    }
+
+    #[test]
+    fn test_parse_plain() {
+        assert_eq!(Quantity::parse("3"), Some(quantity(3.0, "", "", 0, false)))
+    }
+
+    #[test]
+    fn test_parse_currency() {
+        assert_eq!(
+            Quantity::parse("£1,234.50"),
+            Some(quantity(1234.5, "£", "", 2, true)),
+        )
+    }
+
+    #[test]
+    fn test_parse_negative_currency() {
+        assert_eq!(
+            Quantity::parse("-$3.00"),
+            Some(quantity(-3.0, "$", "", 2, false))
+        );
+        assert_eq!(
+            Quantity::parse("$-3.00"),
+            Some(quantity(-3.0, "$", "", 2, false))
+        );
+    }
+
+    #[test]
+    fn test_parse_unit() {
+        assert_eq!(
+            Quantity::parse("12.5 kg"),
+            Some(quantity(12.5, "", " kg", 1, false))
+        );
+        assert_eq!(
+            Quantity::parse("45%"),
+            Some(quantity(45.0, "", "%", 0, false))
+        );
+    }
+
+    #[test]
+    fn test_parse_not_numeric() {
+        assert_eq!(Quantity::parse(""), None);
+        assert_eq!(Quantity::parse("12/03/2024"), None);
+        assert_eq!(Quantity::parse("10:30 am"), None);
+        assert_eq!(Quantity::parse("March 12"), None);
+        assert_eq!(Quantity::parse("1.2.3"), None);
+    }
+
+    #[test]
+    fn test_format_value() {
+        let style = quantity(0.0, "£", "", 2, true);
+        assert_eq!(style.format_value(1234567.891), "£1,234,567.89");
+        assert_eq!(style.format_value(-5.0), "-£5.00");
+        assert_eq!(style.format_value(-0.001), "£0.00");
+    }
+
+    #[test]
+    fn test_round() {
+        let value = Quantity::parse("£2.345").unwrap();
+        assert_eq!(
+            value.round(2, RoundingMode::HalfAwayFromZero).to_string(),
+            "£2.35"
+        );
+        assert_eq!(value.round(0, RoundingMode::Up).to_string(), "£3");
+        assert_eq!(value.round(1, RoundingMode::Down).to_string(), "£2.3");
+        assert_eq!(
+            Quantity::parse("-2.5 kg")
+                .unwrap()
+                .round(0, RoundingMode::HalfEven)
+                .to_string(),
+            "-2 kg"
+        );
+        assert_eq!(
+            Quantity::parse("-2.5")
+                .unwrap()
+                .round(0, RoundingMode::TowardZero)
+                .to_string(),
+            "-2"
+        );
+    }
+
+    #[test]
+    fn test_round_decimal_halves() {
+        let round = |value: &str, mode| Quantity::parse(value).unwrap().round(2, mode).to_string();
+        assert_eq!(round("1.005", RoundingMode::HalfAwayFromZero), "1.01");
+        assert_eq!(round("0.285", RoundingMode::HalfAwayFromZero), "0.29");
+        assert_eq!(round("-0.285", RoundingMode::HalfAwayFromZero), "-0.29");
+        // Halves round to the even digit, which for these is the one kept
+        assert_eq!(round("1.005", RoundingMode::HalfEven), "1.00");
+        assert_eq!(round("0.285", RoundingMode::HalfEven), "0.28");
+        assert_eq!(round("1.015", RoundingMode::HalfEven), "1.02");
+        assert_eq!(round("0.2851", RoundingMode::HalfEven), "0.29");
+        assert_eq!(
+            round("£9,999.995", RoundingMode::HalfAwayFromZero),
+            "£10,000.00"
+        );
+        assert_eq!(round("-1.001", RoundingMode::Up), "-1.00");
+        assert_eq!(round("-1.001", RoundingMode::Down), "-1.01");
+    }
+
+    #[test]
+    fn test_plain() {
+        assert_eq!(
+            Quantity::parse("-£1,234.50").unwrap().plain().to_string(),
+            "-1234.50"
+        );
+        assert_eq!(Quantity::parse("12 kg").unwrap().plain().to_string(), "12");
+    }
+
+    #[test]
+    fn test_group_digits() {
+        assert_eq!(group_digits("123"), "123");
+        assert_eq!(group_digits("1234"), "1,234");
+        assert_eq!(group_digits("123456.789"), "123,456.789");
+    }
+
+    #[test]
+    fn test_sum_dominant_unit() {
+        assert_eq!(
+            sum_dominant_unit(["£1.50", "", "3 kg", "£2", "£1,000.00"]),
+            Some("£1,003.50".to_owned()),
+        )
+    }
+
+    #[test]
+    fn test_sum_dominant_unit_tie() {
+        assert_eq!(sum_dominant_unit(["3 kg", "£2"]), Some("3 kg".to_owned()))
+    }
+
+    #[test]
+    fn test_sum_dominant_unit_none() {
+        assert_eq!(sum_dominant_unit(["", "March 12"]), None)
+    }
+}
@@ -0,0 +1,512 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+
+use crate::json::Value;
+use crate::regex::Regex;
+
+/// A token of a query.
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Identifier(String),
+    String(String),
+    Number(f64),
+    OpenBracket,
+    CloseBracket,
+    OpenParen,
+    CloseParen,
+    Pipe,
+    Comparison(Comparison),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '.' => Token::Dot,
+            '[' => Token::OpenBracket,
+            ']' => Token::CloseBracket,
+            '(' => Token::OpenParen,
+            ')' => Token::CloseParen,
+            '|' => Token::Pipe,
+            '=' | '!' | '<' | '>' => {
+                let or_equal = chars.next_if_eq(&'=').is_some();
+                Token::Comparison(match (c, or_equal) {
+                    ('=', true) => Comparison::Equal,
+                    ('!', true) => Comparison::NotEqual,
+                    ('<', false) => Comparison::Less,
+                    ('<', true) => Comparison::LessOrEqual,
+                    ('>', false) => Comparison::Greater,
+                    ('>', true) => Comparison::GreaterOrEqual,
+                    _ => bail!("unexpected `{c}` in query (use `==` or `!=`)"),
+                })
+            }
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        None => bail!("unterminated string in query"),
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some(c @ ('"' | '\\')) => string.push(c),
+                            c => bail!("invalid escape {c:?} in query"),
+                        },
+                        Some(c) => string.push(c),
+                    }
+                }
+                Token::String(string)
+            }
+            c if c.is_ascii_digit() || c == '-' => {
+                let mut number = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+                    number.push(c);
+                }
+                match number.parse() {
+                    Ok(number) => Token::Number(number),
+                    Err(_) => bail!("invalid number {number:?} in query"),
+                }
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut identifier = c.to_string();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    identifier.push(c);
+                }
+                Token::Identifier(identifier)
+            }
+            c => bail!("unexpected `{c}` in query"),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// A parsed query, which turns a value into any number of values.
+#[derive(Debug, Clone)]
+enum Filter {
+    /// `.`
+    Identity,
+    /// `.name`
+    Field(String),
+    /// `.[n]`, counting from the end if negative
+    Index(i64),
+    /// `.[]`
+    Iterate,
+    Literal(Value),
+    Pipe(Box<Filter>, Box<Filter>),
+    Compare(Box<Filter>, Comparison, Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    /// `select(f)`, keeping its input if `f` is truthy
+    Select(Box<Filter>),
+    /// `test("regex")`
+    Test(Regex),
+    Not,
+    Length,
+}
+
+/// A parser for the tokens of a query.
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &Token, description: &str) -> Result<()> {
+        if !self.eat(token) {
+            bail!("expected {description} in query");
+        }
+        Ok(())
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        self.eat(&Token::Identifier(keyword.to_owned()))
+    }
+
+    fn parse_pipe(&mut self) -> Result<Filter> {
+        let mut filter = self.parse_or()?;
+        while self.eat(&Token::Pipe) {
+            filter = Filter::Pipe(Box::new(filter), Box::new(self.parse_or()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_or(&mut self) -> Result<Filter> {
+        let mut filter = self.parse_and()?;
+        while self.eat_keyword("or") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter> {
+        let mut filter = self.parse_comparison()?;
+        while self.eat_keyword("and") {
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_comparison()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Filter> {
+        let filter = self.parse_postfix()?;
+        if let Some(Token::Comparison(comparison)) = self.peek().cloned() {
+            self.next();
+            let right = self.parse_postfix()?;
+            return Ok(Filter::Compare(
+                Box::new(filter),
+                comparison,
+                Box::new(right),
+            ));
+        }
+        Ok(filter)
+    }
+
+    /// Parse the path after a `.`, like `name` or `[0]`, returning `None` if there is none.
+    fn parse_path_segment(&mut self) -> Result<Option<Filter>> {
+        match self.peek().cloned() {
+            Some(Token::Identifier(name)) => {
+                self.next();
+                Ok(Some(Filter::Field(name)))
+            }
+            Some(Token::String(name)) => {
+                self.next();
+                Ok(Some(Filter::Field(name)))
+            }
+            Some(Token::OpenBracket) => {
+                self.next();
+                let filter = match self.next() {
+                    Some(Token::CloseBracket) => return Ok(Some(Filter::Iterate)),
+                    Some(Token::Number(index)) if index.fract() == 0.0 => {
+                        Filter::Index(index as i64)
+                    }
+                    Some(Token::String(name)) => Filter::Field(name),
+                    _ => bail!("expected an index, a string, or `]` after `[` in query"),
+                };
+                self.expect(&Token::CloseBracket, "`]`")?;
+                Ok(Some(filter))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn parse_postfix(&mut self) -> Result<Filter> {
+        let mut filter = self.parse_primary()?;
+        loop {
+            let segment = if self.eat(&Token::Dot) {
+                match self.parse_path_segment()? {
+                    Some(segment) => segment,
+                    None => bail!("expected a field after `.` in query"),
+                }
+            } else if self.peek() == Some(&Token::OpenBracket) {
+                self.parse_path_segment()?
+                    .expect("brackets start a path segment")
+            } else {
+                return Ok(filter);
+            };
+            filter = Filter::Pipe(Box::new(filter), Box::new(segment));
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Filter> {
+        match self.next() {
+            Some(Token::Dot) => Ok(self.parse_path_segment()?.unwrap_or(Filter::Identity)),
+            Some(Token::String(string)) => Ok(Filter::Literal(Value::String(string))),
+            Some(Token::Number(number)) => Ok(Filter::Literal(Value::Number(number))),
+            Some(Token::OpenParen) => {
+                let filter = self.parse_pipe()?;
+                self.expect(&Token::CloseParen, "`)`")?;
+                Ok(filter)
+            }
+            Some(Token::Identifier(identifier)) => match identifier.as_str() {
+                "true" => Ok(Filter::Literal(Value::Bool(true))),
+                "false" => Ok(Filter::Literal(Value::Bool(false))),
+                "null" => Ok(Filter::Literal(Value::Null)),
+                "not" => Ok(Filter::Not),
+                "length" => Ok(Filter::Length),
+                "select" => {
+                    self.expect(&Token::OpenParen, "`(` after `select`")?;
+                    let condition = self.parse_pipe()?;
+                    self.expect(&Token::CloseParen, "`)`")?;
+                    Ok(Filter::Select(Box::new(condition)))
+                }
+                "test" => {
+                    self.expect(&Token::OpenParen, "`(` after `test`")?;
+                    let Some(Token::String(pattern)) = self.next() else {
+                        bail!("expected a string regex in `test`");
+                    };
+                    self.expect(&Token::CloseParen, "`)`")?;
+                    Ok(Filter::Test(pattern.parse()?))
+                }
+                _ => bail!("unknown function `{identifier}` in query"),
+            },
+            Some(token) => bail!("unexpected {token:?} in query"),
+            None => bail!("unexpected end of query"),
+        }
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    !matches!(value, Value::Null | Value::Bool(false))
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn compare(left: &Value, comparison: Comparison, right: &Value) -> Result<bool> {
+    let ordering = match (left, right) {
+        _ if comparison == Comparison::Equal => return Ok(left == right),
+        _ if comparison == Comparison::NotEqual => return Ok(left != right),
+        (Value::Number(left), Value::Number(right)) => left.total_cmp(right),
+        (Value::String(left), Value::String(right)) => left.cmp(right),
+        _ => bail!(
+            "cannot compare {} with {}",
+            type_name(left),
+            type_name(right)
+        ),
+    };
+    Ok(match comparison {
+        Comparison::Less => ordering == Ordering::Less,
+        Comparison::LessOrEqual => ordering != Ordering::Greater,
+        Comparison::Greater => ordering == Ordering::Greater,
+        Comparison::GreaterOrEqual => ordering != Ordering::Less,
+        Comparison::Equal | Comparison::NotEqual => unreachable!(),
+    })
+}
+
+impl Filter {
+    fn apply(&self, input: &Value) -> Result<Vec<Value>> {
+        Ok(match self {
+            Self::Identity => vec![input.clone()],
+            Self::Field(name) => match input {
+                Value::Object(entries) => vec![
+                    entries
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map_or(Value::Null, |(_, value)| value.clone()),
+                ],
+                Value::Null => vec![Value::Null],
+                _ => bail!("cannot get .{name} of {}", type_name(input)),
+            },
+            Self::Index(index) => match input {
+                Value::Array(values) => {
+                    let index = if *index < 0 {
+                        values.len().checked_sub(index.unsigned_abs() as usize)
+                    } else {
+                        Some(*index as usize)
+                    };
+                    vec![
+                        index
+                            .and_then(|index| values.get(index))
+                            .cloned()
+                            .unwrap_or(Value::Null),
+                    ]
+                }
+                Value::Null => vec![Value::Null],
+                _ => bail!("cannot index {}", type_name(input)),
+            },
+            Self::Iterate => match input {
+                Value::Array(values) => values.clone(),
+                Value::Object(entries) => entries.iter().map(|(_, value)| value.clone()).collect(),
+                _ => bail!("cannot iterate over {}", type_name(input)),
+            },
+            Self::Literal(value) => vec![value.clone()],
+            Self::Pipe(left, right) => {
+                let mut outputs = Vec::new();
+                for value in left.apply(input)? {
+                    outputs.extend(right.apply(&value)?);
+                }
+                outputs
+            }
+            Self::Compare(left, comparison, right) => {
+                let mut outputs = Vec::new();
+                for right_value in right.apply(input)? {
+                    for left_value in left.apply(input)? {
+                        outputs.push(Value::Bool(compare(
+                            &left_value,
+                            *comparison,
+                            &right_value,
+                        )?));
+                    }
+                }
+                outputs
+            }
+            Self::And(left, right) | Self::Or(left, right) => {
+                let is_and = matches!(self, Self::And(..));
+                let mut outputs = Vec::new();
+                for left_value in left.apply(input)? {
+                    if is_truthy(&left_value) != is_and {
+                        outputs.push(Value::Bool(!is_and));
+                        continue;
+                    }
+                    for right_value in right.apply(input)? {
+                        outputs.push(Value::Bool(is_truthy(&right_value)));
+                    }
+                }
+                outputs
+            }
+            Self::Select(condition) => condition
+                .apply(input)?
+                .iter()
+                .filter(|value| is_truthy(value))
+                .map(|_| input.clone())
+                .collect(),
+            Self::Test(regex) => match input {
+                Value::String(string) => vec![Value::Bool(regex.is_match(string))],
+                _ => bail!("cannot test {}", type_name(input)),
+            },
+            Self::Not => vec![Value::Bool(!is_truthy(input))],
+            Self::Length => vec![match input {
+                Value::Null => Value::Number(0.0),
+                Value::Number(number) => Value::Number(number.abs()),
+                Value::String(string) => Value::from(string.chars().count()),
+                Value::Array(values) => Value::from(values.len()),
+                Value::Object(entries) => Value::from(entries.len()),
+                Value::Bool(_) => bail!("boolean has no length"),
+            }],
+        })
+    }
+}
+
+/// A query over JSON values using a subset of `jq`'s syntax: paths like `.name`, `.[0]`, and
+/// `.[]`, pipes, literals, comparisons, `and`/`or`, `select`, `test`, `not`, and `length`.
+#[derive(Debug, Clone)]
+pub struct Query(Filter);
+
+impl Query {
+    /// Run the query, returning each value it outputs.
+    pub fn run(&self, input: &Value) -> Result<Vec<Value>> {
+        self.0.apply(input)
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let filter = parser.parse_pipe()?;
+        if let Some(token) = parser.peek() {
+            bail!("unexpected {token:?} in query");
+        }
+        Ok(Self(filter))
+    }
+}
+
+/// Format a value output by a query, writing strings without quotes like `jq --raw-output`.
+pub fn format_output(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Value {
+        Value::from(vec![
+            Value::object([
+                ("line", Value::from(1_usize)),
+                ("input", Value::from("# Food")),
+                ("output", Value::from("")),
+            ]),
+            Value::object([
+                ("line", Value::from(2_usize)),
+                ("input", Value::from("£1 + £2")),
+                ("output", Value::from("£3.00")),
+            ]),
+        ])
+    }
+
+    fn run(query: &str) -> Vec<String> {
+        query
+            .parse::<Query>()
+            .unwrap()
+            .run(&rows())
+            .unwrap()
+            .iter()
+            .map(format_output)
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        assert_eq!(run(r#".[] | select(.output != "") | .output"#), ["£3.00"]);
+        assert_eq!(
+            run(r#".[] | select(.line > 1 and (.input | test("£"))) | .line"#),
+            ["2"]
+        );
+        assert_eq!(
+            run(".[] | select(.output | length == 0 | not) | .input"),
+            ["£1 + £2"]
+        );
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(run(".[0].input"), ["# Food"]);
+        assert_eq!(run(".[-1][\"output\"]"), ["£3.00"]);
+        assert_eq!(run(".[].line"), ["1", "2"]);
+        assert_eq!(run("length"), ["2"]);
+        assert_eq!(run(".[5].input"), ["null"]);
+        assert_eq!(
+            run(".[0]"),
+            [r##"{"line":1,"input":"# Food","output":""}"##]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(".[] | frobnicate".parse::<Query>().is_err());
+        assert!(".[".parse::<Query>().is_err());
+        assert!(". = 1".parse::<Query>().is_err());
+        let query: Query = ".[].input.x".parse().unwrap();
+        assert!(query.run(&rows()).is_err());
+    }
+}
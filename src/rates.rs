@@ -0,0 +1,128 @@
+use std::process::Command;
+
+use anyhow::{Context, Result, bail};
+
+use crate::json;
+
+/// The API `rates fetch` downloads the latest exchange rates from, followed by the base currency.
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/";
+
+/// A snapshot of exchange rates, read from and written as
+/// `{"base": "USD", "rates": {"GBP": 0.79, ...}}`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rates {
+    pub base: String,
+    /// How much of each currency one unit of the base currency is worth
+    pub rates: Vec<(String, f64)>,
+}
+
+/// Read the numeric values of a JSON object of rates.
+fn parse_rates(value: json::Value) -> Result<Vec<(String, f64)>> {
+    let json::Value::Object(entries) = value else {
+        bail!("rates must be an object");
+    };
+    entries
+        .into_iter()
+        .map(|(currency, rate)| match rate {
+            json::Value::Number(rate) => Ok((currency, rate)),
+            _ => bail!("the rate for {currency} must be a number"),
+        })
+        .collect()
+}
+
+/// Take the value of a key out of a JSON object.
+fn take_key(value: &mut json::Value, key: &str) -> Option<json::Value> {
+    let json::Value::Object(entries) = value else {
+        return None;
+    };
+    let index = entries.iter().position(|(other, _)| other == key)?;
+    Some(entries.remove(index).1)
+}
+
+impl Rates {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut value = json::parse(text)?;
+        let Some(json::Value::String(base)) = take_key(&mut value, "base") else {
+            bail!("expected a string base currency");
+        };
+        let rates = parse_rates(take_key(&mut value, "rates").context("expected rates")?)?;
+        Ok(Self { base, rates })
+    }
+
+    pub fn to_json(&self) -> json::Value {
+        json::Value::object([
+            ("base", json::Value::from(self.base.as_str())),
+            (
+                "rates",
+                json::Value::object(
+                    self.rates
+                        .iter()
+                        .map(|(currency, rate)| (currency.as_str(), *rate)),
+                ),
+            ),
+        ])
+    }
+
+    /// Lines defining each rate, to calculate before a sheet so its conversions use them.
+    pub fn to_lines(&self) -> Vec<String> {
+        self.rates
+            .iter()
+            .filter(|(currency, _)| *currency != self.base)
+            .map(|(currency, rate)| format!("1 {} = {rate} {currency}", self.base))
+            .collect()
+    }
+
+    /// Download the latest rates using `curl`.
+    pub fn fetch(base: &str) -> Result<Self> {
+        let output = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail"])
+            .arg(format!("{RATES_URL}{base}"))
+            .output()
+            .context("failed to run curl")?;
+        if !output.status.success() {
+            bail!(
+                "failed to fetch rates: {}",
+                String::from_utf8_lossy(&output.stderr).trim(),
+            );
+        }
+        let mut response = json::parse(str::from_utf8(&output.stdout)?)?;
+        let rates = take_key(&mut response, "rates").context("the response has no rates")?;
+        Ok(Self {
+            base: base.to_owned(),
+            rates: parse_rates(rates)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let rates = Rates::parse(r#"{"base": "USD", "rates": {"USD": 1, "GBP": 0.79}}"#).unwrap();
+        assert_eq!(
+            rates,
+            Rates {
+                base: "USD".to_owned(),
+                rates: vec![("USD".to_owned(), 1.0), ("GBP".to_owned(), 0.79)],
+            },
+        );
+        assert_eq!(Rates::parse(&rates.to_json().to_string()).unwrap(), rates);
+    }
+
+    #[test]
+    fn test_parse_error() {
+        assert!(Rates::parse(r#"{"rates": {}}"#).is_err());
+        assert!(Rates::parse(r#"{"base": "USD", "rates": {"GBP": "0.79"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_to_lines() {
+        let rates = Rates {
+            base: "USD".to_owned(),
+            rates: vec![("USD".to_owned(), 1.0), ("GBP".to_owned(), 0.79)],
+        };
+        assert_eq!(rates.to_lines(), ["1 USD = 0.79 GBP"])
+    }
+}
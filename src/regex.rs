@@ -0,0 +1,569 @@
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+
+/// A part of a parsed regular expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    /// `.`, matching any character
+    Any,
+    /// A `[...]` class or escape like `\d`, matching characters in any of its inclusive ranges
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    /// `^`
+    Start,
+    /// `$`
+    End,
+    /// `\b`
+    WordBoundary,
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+const DIGIT: &[(char, char)] = &[('0', '9')];
+const WORD: &[(char, char)] = &[('0', '9'), ('A', 'Z'), ('_', '_'), ('a', 'z')];
+const SPACE: &[(char, char)] = &[('\t', '\r'), (' ', ' ')];
+
+/// An instruction of a compiled [`Regex`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Instruction {
+    Char(char),
+    Any,
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary,
+    /// Continue at both instructions, preferring the first
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// The most instructions a pattern can compile to, so repetitions like `(a{1000}){1000}` are
+/// refused instead of using up memory.
+const MAX_PROGRAM_LENGTH: usize = 100_000;
+
+/// Compiles a parsed regular expression to the instructions [`Regex::is_match`] runs.
+#[derive(Default)]
+struct Compiler {
+    program: Vec<Instruction>,
+}
+
+impl Compiler {
+    fn push(&mut self, instruction: Instruction) -> Result<usize> {
+        if self.program.len() >= MAX_PROGRAM_LENGTH {
+            bail!("the pattern is too large, it repeats more than {MAX_PROGRAM_LENGTH} parts");
+        }
+        self.program.push(instruction);
+        Ok(self.program.len() - 1)
+    }
+
+    /// A split between repeating the instructions after it and skipping to `skip`.
+    fn repeat_split(at: usize, skip: usize, greedy: bool) -> Instruction {
+        if greedy {
+            Instruction::Split(at + 1, skip)
+        } else {
+            Instruction::Split(skip, at + 1)
+        }
+    }
+
+    fn compile(&mut self, node: &Node) -> Result<()> {
+        match node {
+            Node::Char(c) => {
+                self.push(Instruction::Char(*c))?;
+            }
+            Node::Any => {
+                self.push(Instruction::Any)?;
+            }
+            Node::Class { ranges, negated } => {
+                self.push(Instruction::Class {
+                    ranges: ranges.clone(),
+                    negated: *negated,
+                })?;
+            }
+            Node::Start => {
+                self.push(Instruction::Start)?;
+            }
+            Node::End => {
+                self.push(Instruction::End)?;
+            }
+            Node::WordBoundary => {
+                self.push(Instruction::WordBoundary)?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.compile(node)?;
+                }
+            }
+            Node::Alternate(alternatives) => {
+                let mut jumps = Vec::new();
+                let (last, rest) = alternatives
+                    .split_last()
+                    .expect("alternates have two nodes");
+                for alternative in rest {
+                    let split = self.push(Instruction::Split(0, 0))?;
+                    self.compile(alternative)?;
+                    jumps.push(self.push(Instruction::Jump(0))?);
+                    self.program[split] = Instruction::Split(split + 1, self.program.len());
+                }
+                self.compile(last)?;
+                for jump in jumps {
+                    self.program[jump] = Instruction::Jump(self.program.len());
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    let length = self.program.len();
+                    self.compile(node)?;
+                    // Repeating something that matches nothing, like `(){9999}`, does nothing
+                    if self.program.len() == length {
+                        return Ok(());
+                    }
+                }
+                match max {
+                    None => {
+                        let split = self.push(Instruction::Split(0, 0))?;
+                        self.compile(node)?;
+                        self.push(Instruction::Jump(split))?;
+                        self.program[split] =
+                            Self::repeat_split(split, self.program.len(), *greedy);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Instruction::Split(0, 0))?);
+                            self.compile(node)?;
+                        }
+                        for split in splits {
+                            self.program[split] =
+                                Self::repeat_split(split, self.program.len(), *greedy);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The instructions a character could be matched with next, each only once.
+struct Threads {
+    instructions: Vec<usize>,
+    /// Which instructions were reached at this position, including jumps and splits
+    reached: Vec<bool>,
+    reached_list: Vec<usize>,
+}
+
+impl Threads {
+    fn new(program_length: usize) -> Self {
+        Self {
+            instructions: Vec::new(),
+            reached: vec![false; program_length],
+            reached_list: Vec::new(),
+        }
+    }
+
+    fn clear(&mut self) {
+        self.instructions.clear();
+        for instruction in self.reached_list.drain(..) {
+            self.reached[instruction] = false;
+        }
+    }
+}
+
+/// A regular expression supporting the common syntax: `.`, `[...]` classes, `\d`/`\w`/`\s`
+/// escapes, `^`/`$`/`\b` anchors, groups, `|`, greedy and lazy `*`/`+`/`?`/`{n,m}`, and a leading
+/// `(?i)` for case-insensitive matching.
+///
+/// It is matched by following every way through its compiled instructions at once, a character at
+/// a time, so matching takes time proportional to the length of the text and never recurses,
+/// however the pattern nests repetitions.
+#[derive(Debug, Clone)]
+pub struct Regex {
+    program: Vec<Instruction>,
+    case_insensitive: bool,
+}
+
+/// A parser for the syntax of [`Regex`].
+struct Parser<'a> {
+    pattern: &'a str,
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn parse_alternate(&mut self) -> Result<Node> {
+        let mut alternatives = vec![self.parse_concat()?];
+        while self.chars.next_if_eq(&'|').is_some() {
+            alternatives.push(self.parse_concat()?);
+        }
+        Ok(if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Node::Alternate(alternatives)
+        })
+    }
+
+    fn parse_concat(&mut self) -> Result<Node> {
+        let mut nodes = Vec::new();
+        while let Some(&c) = self.chars.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(Node::Concat(nodes))
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let mut digits = String::new();
+        while let Some(c) = self.chars.next_if(char::is_ascii_digit) {
+            digits.push(c);
+        }
+        digits.parse().ok()
+    }
+
+    fn parse_quantifier(&mut self, node: Node) -> Result<Node> {
+        let (min, max) = match self.chars.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.chars.next();
+                let Some(min) = self.parse_number() else {
+                    bail!("expected a number after `{{` in {:?}", self.pattern);
+                };
+                let max = if self.chars.next_if_eq(&',').is_some() {
+                    self.parse_number()
+                } else {
+                    Some(min)
+                };
+                if self.chars.next() != Some('}') || max.is_some_and(|max| max < min) {
+                    bail!("invalid repetition in {:?}", self.pattern);
+                }
+                let greedy = self.chars.next_if_eq(&'?').is_none();
+                return self.repeat(node, min, max, greedy);
+            }
+            _ => return Ok(node),
+        };
+        self.chars.next();
+        let greedy = self.chars.next_if_eq(&'?').is_none();
+        self.repeat(node, min, max, greedy)
+    }
+
+    fn repeat(&self, node: Node, min: usize, max: Option<usize>, greedy: bool) -> Result<Node> {
+        if matches!(node, Node::Start | Node::End | Node::WordBoundary) {
+            bail!("nothing to repeat in {:?}", self.pattern);
+        }
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    fn parse_escape(&mut self) -> Result<Node> {
+        let class = |ranges: &[(char, char)], negated| Node::Class {
+            ranges: ranges.to_vec(),
+            negated,
+        };
+        Ok(match self.chars.next() {
+            Some('d') => class(DIGIT, false),
+            Some('D') => class(DIGIT, true),
+            Some('w') => class(WORD, false),
+            Some('W') => class(WORD, true),
+            Some('s') => class(SPACE, false),
+            Some('S') => class(SPACE, true),
+            Some('b') => Node::WordBoundary,
+            Some('n') => Node::Char('\n'),
+            Some('t') => Node::Char('\t'),
+            Some(c) if !c.is_alphanumeric() => Node::Char(c),
+            Some(c) => bail!("unknown escape `\\{c}` in {:?}", self.pattern),
+            None => bail!("trailing `\\` in {:?}", self.pattern),
+        })
+    }
+
+    fn parse_class(&mut self) -> Result<Node> {
+        let negated = self.chars.next_if_eq(&'^').is_some();
+        let mut ranges = Vec::new();
+        let mut first = true;
+        loop {
+            let start = match self.chars.next() {
+                None => bail!("unterminated `[` in {:?}", self.pattern),
+                Some(']') if !first => break,
+                Some('\\') => match self.parse_escape()? {
+                    Node::Char(c) => c,
+                    Node::Class {
+                        ranges: escaped,
+                        negated: false,
+                    } => {
+                        ranges.extend(escaped);
+                        first = false;
+                        continue;
+                    }
+                    _ => bail!("unsupported escape in class in {:?}", self.pattern),
+                },
+                Some(c) => c,
+            };
+            first = false;
+            let mut lookahead = self.chars.clone();
+            let end =
+                if lookahead.next() == Some('-') && lookahead.peek().is_some_and(|c| *c != ']') {
+                    self.chars.next();
+                    match self.chars.next() {
+                        Some('\\') => match self.parse_escape()? {
+                            Node::Char(c) => c,
+                            _ => bail!("invalid class range in {:?}", self.pattern),
+                        },
+                        Some(c) => c,
+                        None => bail!("unterminated `[` in {:?}", self.pattern),
+                    }
+                } else {
+                    start
+                };
+            if end < start {
+                bail!("invalid class range `{start}-{end}` in {:?}", self.pattern);
+            }
+            ranges.push((start, end));
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+
+    fn parse_atom(&mut self) -> Result<Node> {
+        Ok(match self.chars.next() {
+            Some('(') => {
+                // Groups don't capture, so `(?:...)` is the same as `(...)`
+                if self.chars.next_if_eq(&'?').is_some() && self.chars.next() != Some(':') {
+                    bail!("unsupported group syntax in {:?}", self.pattern);
+                }
+                let node = self.parse_alternate()?;
+                if self.chars.next() != Some(')') {
+                    bail!("unterminated `(` in {:?}", self.pattern);
+                }
+                node
+            }
+            Some('[') => self.parse_class()?,
+            Some('.') => Node::Any,
+            Some('^') => Node::Start,
+            Some('$') => Node::End,
+            Some('\\') => self.parse_escape()?,
+            Some(c @ ('*' | '+' | '?' | '{')) => {
+                bail!("nothing to repeat before `{c}` in {:?}", self.pattern)
+            }
+            Some(c) => Node::Char(c),
+            None => unreachable!("atoms are only parsed before the end of the pattern"),
+        })
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+impl Regex {
+    fn char_matches(&self, expected: char, c: char) -> bool {
+        expected == c || (self.case_insensitive && expected.to_lowercase().eq(c.to_lowercase()))
+    }
+
+    fn class_matches(&self, ranges: &[(char, char)], c: char) -> bool {
+        let in_ranges = |c: char| {
+            ranges
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c))
+        };
+        in_ranges(c)
+            || (self.case_insensitive
+                && (c.to_lowercase().any(in_ranges) || c.to_uppercase().any(in_ranges)))
+    }
+
+    /// Follow the instructions from `start` that match without a character at `position`, adding
+    /// the ones that need a character to `threads`, and returning whether they reach the match.
+    fn add(&self, threads: &mut Threads, start: usize, text: &[char], position: usize) -> bool {
+        let mut matched = false;
+        let mut stack = vec![start];
+        while let Some(instruction) = stack.pop() {
+            if threads.reached[instruction] {
+                continue;
+            }
+            threads.reached[instruction] = true;
+            threads.reached_list.push(instruction);
+            let holds = match &self.program[instruction] {
+                Instruction::Jump(target) => {
+                    stack.push(*target);
+                    continue;
+                }
+                Instruction::Split(preferred, other) => {
+                    stack.push(*other);
+                    stack.push(*preferred);
+                    continue;
+                }
+                Instruction::Match => {
+                    matched = true;
+                    continue;
+                }
+                Instruction::Start => position == 0,
+                Instruction::End => position == text.len(),
+                Instruction::WordBoundary => {
+                    let before = position > 0 && is_word_char(text[position - 1]);
+                    let after = text.get(position).is_some_and(|c| is_word_char(*c));
+                    before != after
+                }
+                Instruction::Char(_) | Instruction::Any | Instruction::Class { .. } => {
+                    threads.instructions.push(instruction);
+                    continue;
+                }
+            };
+            if holds {
+                stack.push(instruction + 1);
+            }
+        }
+        matched
+    }
+
+    /// Whether the regex matches anywhere in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        for position in 0..=text.len() {
+            // A match can start at any position
+            if self.add(&mut current, 0, &text, position) {
+                return true;
+            }
+            let Some(&c) = text.get(position) else {
+                break;
+            };
+            for &instruction in &current.instructions {
+                let matches = match &self.program[instruction] {
+                    Instruction::Char(expected) => self.char_matches(*expected, c),
+                    Instruction::Any => true,
+                    Instruction::Class { ranges, negated } => {
+                        self.class_matches(ranges, c) != *negated
+                    }
+                    _ => unreachable!("only instructions that match a character are threads"),
+                };
+                if matches && self.add(&mut next, instruction + 1, &text, position + 1) {
+                    return true;
+                }
+            }
+            current.clear();
+            std::mem::swap(&mut current, &mut next);
+        }
+        false
+    }
+}
+
+impl FromStr for Regex {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (pattern, case_insensitive) = match s.strip_prefix("(?i)") {
+            Some(pattern) => (pattern, true),
+            None => (s, false),
+        };
+        let mut parser = Parser {
+            pattern: s,
+            chars: pattern.chars().peekable(),
+        };
+        let node = parser.parse_alternate()?;
+        if parser.chars.next().is_some() {
+            bail!("unmatched `)` in {s:?}");
+        }
+        let mut compiler = Compiler::default();
+        compiler.compile(&node)?;
+        compiler.push(Instruction::Match)?;
+        Ok(Self {
+            program: compiler.program,
+            case_insensitive,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        pattern.parse::<Regex>().unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_literals_and_anchors() {
+        assert!(is_match("USD", "£10 in USD"));
+        assert!(!is_match("^USD", "£10 in USD"));
+        assert!(is_match("USD$", "£10 in USD"));
+        assert!(is_match(r"\bin\b", "£10 in USD"));
+        assert!(!is_match(r"\bin\b", "inches"));
+        assert!(is_match(r"\$\d+", "$10"));
+    }
+
+    #[test]
+    fn test_classes() {
+        assert!(is_match("[a-c]x", "bx"));
+        assert!(!is_match("[^a-c]x", "bx"));
+        assert!(is_match(r"[\d.]+%", "4.5%"));
+        assert!(is_match("[-a]", "-"));
+        assert!(!is_match(r"^\w+$", "two words"));
+    }
+
+    #[test]
+    fn test_repetition() {
+        assert!(is_match("^a{2,3}$", "aaa"));
+        assert!(!is_match("^a{2,3}$", "aaaa"));
+        assert!(is_match("^(ab)+c?$", "ababc"));
+        assert!(is_match("^a.*?b$", "axxb"));
+        assert!(is_match("^(a*)*$", "aaa"));
+        assert!(!is_match("^(a*)*$", "aab"));
+    }
+
+    #[test]
+    fn test_long_text() {
+        let text = "a".repeat(10_000);
+        assert!(is_match("a.*b", &format!("{text}b")));
+        assert!(!is_match("a.*b", &text));
+        assert!(is_match(r"^(\w+\s?)*$", &text));
+    }
+
+    #[test]
+    fn test_nested_repetition() {
+        let text = "a".repeat(30);
+        assert!(is_match("^(a+)+$", &text));
+        assert!(!is_match("^(a+)+$", &format!("{text}!")));
+        assert!(!is_match("^(a|aa)*$", &format!("{text}!")));
+        assert!(is_match("^(){99999}a$", "a"));
+        assert!("(a{1000}){1000}".parse::<Regex>().is_err());
+    }
+
+    #[test]
+    fn test_alternation_and_case() {
+        assert!(is_match("^(rent|bills)", "bills = £100"));
+        assert!(!is_match("^(rent|bills)", "Rent = £1000"));
+        assert!(is_match("(?i)^(rent|bills)", "Rent = £1000"));
+        assert!(is_match("(?i)[a-z]", "X"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        for pattern in ["(a", "a)", "[a", "*a", "a{2,1}", r"\q", "[z-a]"] {
+            assert!(pattern.parse::<Regex>().is_err(), "{pattern}");
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::files;
+use crate::output;
+use crate::soulver::{self, Line};
+use crate::terminal::{Key, KeyMode, Keys};
+use crate::vars;
+
+const PROMPT: &str = "> ";
+
+/// Common currencies offered as completions along with the session's variables.
+const CURRENCIES: &[&str] = &[
+    "AUD", "BTC", "CAD", "CHF", "CNY", "EUR", "GBP", "INR", "JPY", "USD",
+];
+
+/// Common units offered as completions along with the session's variables.
+const UNITS: &[&str] = &[
+    "bytes",
+    "celsius",
+    "centimeters",
+    "days",
+    "fahrenheit",
+    "feet",
+    "gallons",
+    "GB",
+    "grams",
+    "hours",
+    "inches",
+    "KB",
+    "kilograms",
+    "kilometers",
+    "liters",
+    "MB",
+    "meters",
+    "miles",
+    "milliliters",
+    "millimeters",
+    "minutes",
+    "months",
+    "ounces",
+    "percent",
+    "pounds",
+    "seconds",
+    "stone",
+    "TB",
+    "today",
+    "tomorrow",
+    "weeks",
+    "yards",
+    "years",
+    "yesterday",
+];
+
+const NUMBER_COLOR: &str = "\x1b[36m";
+const CURRENCY_COLOR: &str = "\x1b[32m";
+const UNIT_COLOR: &str = "\x1b[35m";
+const VARIABLE_COLOR: &str = "\x1b[1;34m";
+const COMMENT_COLOR: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether to highlight lines and results, unless `NO_COLOR` is set.
+fn use_color() -> bool {
+    env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+}
+
+/// Highlight the numbers, currencies, units, and variables in a line or result.
+fn highlight(line: &str, variables: &[String]) -> String {
+    if soulver::is_comment(line.trim_start()) {
+        return format!("{COMMENT_COLOR}{line}{RESET}");
+    }
+    let mut highlighted = String::new();
+    let mut rest = line;
+    while let Some(c) = rest.chars().next() {
+        let (length, color) = if c.is_ascii_digit() {
+            let length = rest
+                .find(|c: char| !c.is_ascii_digit() && c != '.' && c != ',')
+                .unwrap_or(rest.len());
+            (length, Some(NUMBER_COLOR))
+        } else if "£$€¥".contains(c) {
+            (c.len_utf8(), Some(CURRENCY_COLOR))
+        } else if is_word_char(c) {
+            let variable = variables
+                .iter()
+                .filter(|variable| {
+                    rest.strip_prefix(variable.as_str())
+                        .is_some_and(|after| !after.starts_with(is_word_char))
+                })
+                .max_by_key(|variable| variable.len());
+            let word_length = rest.find(|c: char| !is_word_char(c)).unwrap_or(rest.len());
+            let word = &rest[..word_length];
+            let is = |words: &[&str]| words.iter().any(|other| other.eq_ignore_ascii_case(word));
+            match variable {
+                Some(variable) => (variable.len(), Some(VARIABLE_COLOR)),
+                None if is(CURRENCIES) => (word_length, Some(CURRENCY_COLOR)),
+                None if is(UNITS) => (word_length, Some(UNIT_COLOR)),
+                None => (word_length, None),
+            }
+        } else {
+            (c.len_utf8(), None)
+        };
+        let (part, remaining) = rest.split_at(length);
+        match color {
+            Some(color) => highlighted.push_str(&format!("{color}{part}{RESET}")),
+            None => highlighted.push_str(part),
+        }
+        rest = remaining;
+    }
+    highlighted
+}
+
+/// The lines entered so far, which are calculated together so later lines can use earlier
+/// variables.
+#[derive(Debug, Default)]
+struct Session {
+    lines: Vec<String>,
+    calculated: Vec<Line>,
+    /// The sheet the session was resumed from or last saved to, which `.save` saves to
+    path: Option<PathBuf>,
+}
+
+impl Session {
+    /// Add a line to the sheet, returning its result, or leaving it out of the sheet if the sheet
+    /// cannot be calculated with it.
+    fn enter(&mut self, line: String) -> Result<Option<String>> {
+        self.lines.push(line);
+        match soulver::calculate_lines(&self.lines.join("\n")) {
+            Ok(calculated) => {
+                self.calculated = calculated;
+                Ok(self
+                    .calculated
+                    .get(self.lines.len() - 1)
+                    .map(|line| line.output.clone())
+                    .filter(|output| !output.is_empty()))
+            }
+            Err(error) => {
+                self.lines.pop();
+                Err(error)
+            }
+        }
+    }
+
+    fn sheet(&self) -> String {
+        let mut sheet = self.lines.join("\n");
+        sheet.push('\n');
+        sheet
+    }
+
+    /// Replace the lines with a sheet, keeping the lines if the sheet cannot be calculated.
+    fn replace(&mut self, sheet: &str) -> Result<()> {
+        self.calculated = soulver::calculate_lines(sheet)?;
+        self.lines = sheet.lines().map(str::to_owned).collect();
+        Ok(())
+    }
+
+    /// Run a command, then print the sheet so far, unless the command saved it.
+    fn run(&mut self, action: Action, terminal: Option<&Terminal>) -> Result<()> {
+        match action {
+            Action::Save(path) => {
+                let Some(path) = path.or_else(|| self.path.clone()) else {
+                    bail!("no file to save the session to, use .save FILE");
+                };
+                files::write_atomic(&path, self.sheet())?;
+                outputln!("Saved {}", path.display());
+                self.path = Some(path);
+                return Ok(());
+            }
+            Action::Show => {}
+            Action::Edit => {
+                let sheet = self.sheet();
+                let edited = match terminal {
+                    Some(terminal) => terminal.key_mode.suspended(|| edit(&sheet))?,
+                    None => edit(&sheet)?,
+                };
+                self.replace(&edited)?;
+            }
+        }
+        if !self.calculated.is_empty() {
+            outputln!("{}", soulver::zip(&self.calculated));
+        }
+        Ok(())
+    }
+
+    fn variables(&self) -> Vec<String> {
+        vars::variables(&self.calculated)
+            .into_iter()
+            .map(|variable| variable.name)
+            .collect()
+    }
+
+    /// The words tab completes, which are the session's variables and common units.
+    fn completions(&self) -> Vec<String> {
+        let mut completions = self.variables();
+        completions.extend(
+            CURRENCIES
+                .iter()
+                .chain(UNITS)
+                .map(|word| (*word).to_owned()),
+        );
+        completions
+    }
+}
+
+/// Whether a character can be part of a word that is completed.
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The byte offset where the completed text starts at the end of a line, and the completions
+/// starting with it, ignoring case.
+///
+/// The longest text starting at a word is completed, so variable names with multiple words like
+/// `Monthly r` complete to `Monthly rent`.
+fn complete<'a>(line: &str, completions: &'a [String]) -> Option<(usize, Vec<&'a str>)> {
+    let word_starts = line
+        .char_indices()
+        .filter(|&(index, c)| is_word_char(c) && !line[..index].ends_with(is_word_char));
+    for (start, _) in word_starts {
+        let text = line[start..].to_lowercase();
+        let matches: Vec<&str> = completions
+            .iter()
+            .map(String::as_str)
+            .filter(|completion| completion.to_lowercase().starts_with(&text))
+            .collect();
+        if !matches.is_empty() {
+            return Some((start, matches));
+        }
+    }
+    None
+}
+
+/// The longest prefix all of the words share.
+fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let Some((first, rest)) = words.split_first() else {
+        return "";
+    };
+    let mut length = first.len();
+    for word in rest {
+        length = first
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((index, c), _)| index + c.len_utf8())
+            .min(length);
+    }
+    &first[..length]
+}
+
+/// The terminal, read a key at a time instead of a line at a time until it is dropped.
+struct Terminal {
+    key_mode: KeyMode,
+    keys: Keys,
+    color: bool,
+}
+
+impl Terminal {
+    fn new() -> Result<Self> {
+        Ok(Self {
+            key_mode: KeyMode::new()?,
+            keys: Keys::default(),
+            color: use_color(),
+        })
+    }
+
+    fn redraw(&self, line: &str, variables: &[String]) -> io::Result<()> {
+        let mut stdout = io::stdout().lock();
+        if self.color {
+            write!(stdout, "\r\x1b[K{PROMPT}{}", highlight(line, variables))?;
+        } else {
+            write!(stdout, "\r\x1b[K{PROMPT}{line}")?;
+        }
+        stdout.flush()
+    }
+
+    /// Complete the word at the end of the line, or list the completions if there are several.
+    fn complete(line: &mut String, completions: &[String]) -> io::Result<()> {
+        let Some((start, matches)) = complete(line, completions) else {
+            return Ok(());
+        };
+        let prefix = common_prefix(&matches);
+        // A single completion replaces what was typed, even if only its case differs
+        if matches.len() == 1 || prefix.len() > line.len() - start {
+            line.replace_range(start.., prefix);
+        } else {
+            println!("\n{}", matches.join("  "));
+        }
+        Ok(())
+    }
+
+    /// Read a line, or `None` at the end of input or Ctrl-D on an empty line.
+    fn read_line(&mut self, session: &Session) -> io::Result<Option<String>> {
+        let variables = session.variables();
+        let completions = session.completions();
+        let mut line = String::new();
+        self.redraw(&line, &variables)?;
+        loop {
+            let Some(key) = self.keys.read()? else {
+                println!();
+                return Ok((!line.is_empty()).then_some(line));
+            };
+            match key {
+                Key::Enter => {
+                    println!();
+                    return Ok(Some(line));
+                }
+                Key::Ctrl('d') if line.is_empty() => {
+                    println!();
+                    return Ok(None);
+                }
+                Key::Ctrl('c') => {
+                    println!("^C");
+                    line.clear();
+                }
+                Key::Ctrl('u') => line.clear(),
+                Key::Backspace => {
+                    line.pop();
+                }
+                Key::Tab => Self::complete(&mut line, &completions)?,
+                Key::Char(c) => line.push(c),
+                _ => {}
+            }
+            self.redraw(&line, &variables)?;
+        }
+    }
+}
+
+/// A command entered instead of a line, which starts with `.` and a letter.
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    /// Print the zipped sheet so far
+    Show,
+    /// Edit the sheet so far with `$VISUAL` or `$EDITOR`, and continue with what it is saved as
+    Edit,
+    /// Save the sheet so far to a file, or the one the session was resumed from or last saved to
+    Save(Option<PathBuf>),
+}
+
+/// Parse a command, or `None` if the line is not one, e.g. `.5 * 2`.
+fn parse_action(line: &str) -> Option<Result<Action>> {
+    let command = line
+        .trim()
+        .strip_prefix('.')
+        .filter(|command| command.starts_with(|c: char| c.is_ascii_alphabetic()))?;
+    let (name, argument) = match command.split_once(char::is_whitespace) {
+        Some((name, argument)) => (name, Some(argument.trim())),
+        None => (command, None),
+    };
+    Some(match (name, argument) {
+        ("show", None) => Ok(Action::Show),
+        ("edit", None) => Ok(Action::Edit),
+        ("save", path) => Ok(Action::Save(path.map(PathBuf::from))),
+        _ => Err(anyhow!(
+            "unknown command .{command}, expected .show, .edit, or .save FILE"
+        )),
+    })
+}
+
+/// Edit a sheet with `$VISUAL` or `$EDITOR`, or `vi` if neither is set, returning what it was
+/// saved as.
+fn edit(sheet: &str) -> Result<String> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.is_empty())
+        .unwrap_or_else(|| "vi".to_owned());
+    let (path, mut file) = files::create_temporary("soulver-cli-zipper-repl", ".soulver")?;
+    let written = file.write_all(sheet.as_bytes());
+    drop(file);
+    if let Err(error) = written {
+        let _ = fs::remove_file(&path);
+        return Err(error).context("failed to write the sheet to edit");
+    }
+    // The editor can have arguments, like `code --wait`
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(format!("{editor} \"$1\""))
+        .arg("sh")
+        .arg(&path)
+        .status();
+    let edited = fs::read_to_string(&path);
+    let _ = fs::remove_file(&path);
+    let status = status.with_context(|| format!("failed to run {editor}"))?;
+    if !status.success() {
+        bail!("{editor} exited with {status}");
+    }
+    Ok(edited?)
+}
+
+/// Calculate lines as they are entered, printing each line's result, until the end of input.
+///
+/// On a terminal, lines are highlighted as they are typed, and tab completes the variables defined
+/// earlier in the session and common units. `.show` prints the sheet so far, `.edit` edits it, and
+/// `.save` saves it, to be resumed from later.
+pub fn repl(resume: Option<&Path>) -> Result<()> {
+    let mut session = Session::default();
+    if let Some(path) = resume {
+        let sheet = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        session.replace(&sheet)?;
+        session.path = Some(path.to_owned());
+        session.run(Action::Show, None)?;
+    }
+    let mut terminal = if io::stdin().is_terminal() {
+        Some(Terminal::new()?)
+    } else {
+        None
+    };
+    loop {
+        let line = match &mut terminal {
+            Some(terminal) => terminal.read_line(&session)?,
+            None => io::stdin().lock().lines().next().transpose()?,
+        };
+        let Some(line) = line else {
+            return Ok(());
+        };
+        let color = terminal.as_ref().is_some_and(|terminal| terminal.color);
+        let result = match parse_action(&line) {
+            Some(action) => action.and_then(|action| session.run(action, terminal.as_ref())),
+            None => session.enter(line).map(|result| match result {
+                Some(result) if color => {
+                    outputln!("= {}", highlight(&result, &session.variables()));
+                }
+                Some(result) => outputln!("= {result}"),
+                None => {}
+            }),
+        };
+        match result {
+            Ok(()) => {}
+            Err(error) if terminal.is_some() => output::log_error(None, &error),
+            Err(error) => bail!(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete() {
+        let completions = ["Monthly rent", "Monthly bills", "meters", "USD"].map(str::to_owned);
+        assert_eq!(complete("£10 in us", &completions), Some((8, vec!["USD"])));
+        assert_eq!(
+            complete("Monthly r", &completions),
+            Some((0, vec!["Monthly rent"]))
+        );
+        assert_eq!(
+            complete("2 * mo", &completions),
+            Some((4, vec!["Monthly rent", "Monthly bills"]))
+        );
+        assert_eq!(complete("2 * x", &completions), None);
+        assert_eq!(complete("2 ", &completions), None);
+    }
+
+    #[test]
+    fn test_parse_action() {
+        assert_eq!(parse_action(".show").unwrap().unwrap(), Action::Show);
+        assert_eq!(parse_action(" .edit ").unwrap().unwrap(), Action::Edit);
+        assert_eq!(
+            parse_action(".save budget.soulver").unwrap().unwrap(),
+            Action::Save(Some(PathBuf::from("budget.soulver")))
+        );
+        assert_eq!(parse_action(".save").unwrap().unwrap(), Action::Save(None));
+        assert!(parse_action(".shw").unwrap().is_err());
+        assert!(parse_action(".show all").unwrap().is_err());
+        assert!(parse_action(".5 * 2").is_none());
+        assert!(parse_action("1 + 1").is_none());
+    }
+
+    #[test]
+    fn test_highlight() {
+        let variables = ["Monthly rent".to_owned()];
+        assert_eq!(
+            highlight("Monthly rent * 12 in usd", &variables),
+            "\x1b[1;34mMonthly rent\x1b[0m * \x1b[36m12\x1b[0m in \x1b[32musd\x1b[0m",
+        );
+        assert_eq!(
+            highlight("£1,000 in weeks", &[]),
+            "\x1b[32m£\x1b[0m\x1b[36m1,000\x1b[0m in \x1b[35mweeks\x1b[0m",
+        );
+        assert_eq!(highlight("# Costs", &[]), "\x1b[2m# Costs\x1b[0m");
+    }
+
+    #[test]
+    fn test_common_prefix() {
+        assert_eq!(
+            common_prefix(&["Monthly rent", "Monthly bills"]),
+            "Monthly "
+        );
+        assert_eq!(common_prefix(&["meters"]), "meters");
+        assert_eq!(common_prefix(&["£1", "£2"]), "£");
+        assert_eq!(common_prefix(&["a", "b"]), "");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
@@ -0,0 +1,84 @@
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+use clap::ValueEnum;
+
+/// What is done with control characters and zero-width codepoints in sheets, like those pasted
+/// along with rich text, before they are calculated and aligned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Sanitize {
+    /// Leave them in the sheet
+    Off,
+    /// Remove them
+    Strip,
+    /// Replace them with escapes like `\u{200b}`, so they can be seen
+    Escape,
+}
+
+static SANITIZE: OnceLock<Sanitize> = OnceLock::new();
+
+/// Sanitize every sheet calculated for the rest of the process.
+pub fn set(sanitize: Sanitize) {
+    SANITIZE.set(sanitize).expect("sanitizing is only set once");
+}
+
+/// Whether a character is invisible, or takes up no width, but is not a new line or tab.
+fn is_invisible(c: char) -> bool {
+    (c.is_control() && c != '\n' && c != '\t')
+        || matches!(
+            c,
+            '\u{00ad}'
+                | '\u{200b}'..='\u{200f}'
+                | '\u{202a}'..='\u{202e}'
+                | '\u{2060}'..='\u{2064}'
+                | '\u{2066}'..='\u{2069}'
+                | '\u{feff}'
+        )
+}
+
+/// Sanitize text, replacing tabs with spaces so they are aligned like any other character.
+///
+/// Carriage returns are always removed, so sheets with Windows line endings are not escaped.
+fn sanitize(text: &str, sanitize: Sanitize) -> Cow<'_, str> {
+    if sanitize == Sanitize::Off || !text.chars().any(|c| c == '\t' || is_invisible(c)) {
+        return Cow::Borrowed(text);
+    }
+    let mut sanitized = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\t' => sanitized.push(' '),
+            '\r' => {}
+            c if is_invisible(c) && sanitize == Sanitize::Escape => {
+                sanitized.push_str(&format!("\\u{{{:x}}}", u32::from(c)));
+            }
+            c if is_invisible(c) => {}
+            c => sanitized.push(c),
+        }
+    }
+    Cow::Owned(sanitized)
+}
+
+/// Sanitize a sheet the way `--sanitize` or `sanitize` in the config file chose, if either did.
+pub fn sheet(text: &str) -> Cow<'_, str> {
+    sanitize(text, SANITIZE.get().copied().unwrap_or(Sanitize::Off))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize() {
+        let text = "Rent\u{200b}: £1000\u{7}\r\n\tBills = 2";
+        assert_eq!(sanitize(text, Sanitize::Off), text);
+        assert_eq!(sanitize(text, Sanitize::Strip), "Rent: £1000\n Bills = 2");
+        assert_eq!(
+            sanitize(text, Sanitize::Escape),
+            "Rent\\u{200b}: £1000\\u{7}\n Bills = 2"
+        );
+        assert!(matches!(
+            sanitize("1 + 2\n3", Sanitize::Strip),
+            Cow::Borrowed(_)
+        ));
+    }
+}
@@ -0,0 +1,95 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::Config;
+use crate::dates::Date;
+use crate::files;
+use crate::template::Template;
+
+/// The templates `new` can create sheets from without a user template of the same name.
+const BUILT_IN_TEMPLATES: [(&str, &str); 2] = [
+    (
+        "monthly-budget",
+        "# {{MONTH}} {{YEAR}} budget\n\
+         Income = £0\n\
+         \n\
+         ## Bills\n\
+         Rent = £0\n\
+         Utilities = £0\n\
+         \n\
+         ## Spending\n\
+         Groceries = £0\n\
+         Transport = £0\n\
+         \n\
+         Remaining = Income - Rent - Utilities - Groceries - Transport\n",
+    ),
+    (
+        "invoice",
+        "# Invoice, {{DATE}}\n\
+         Hours = 0\n\
+         Rate = £0\n\
+         Subtotal = Hours * Rate\n\
+         VAT = Subtotal * 20%\n\
+         Total = Subtotal + VAT\n",
+    ),
+];
+
+/// Read a template from the `templates` directory next to the config file, or a built-in one.
+fn load_template(name: &str) -> Result<String> {
+    if let Some(directory) = Config::directory()
+        && !name.contains(['/', '\\'])
+    {
+        let path = directory.join("templates").join(format!("{name}.soulver"));
+        match fs::read_to_string(&path) {
+            Ok(template) => return Ok(template),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(error)
+                    .with_context(|| format!("failed to read template {}", path.display()));
+            }
+        }
+    }
+    match BUILT_IN_TEMPLATES.iter().find(|(other, _)| *other == name) {
+        Some((_, template)) => Ok((*template).to_owned()),
+        None => bail!(
+            "there is no template named {name:?} (the built-in templates are {})",
+            BUILT_IN_TEMPLATES.map(|(name, _)| name).join(", "),
+        ),
+    }
+}
+
+/// The values of the date placeholders filled in when creating a sheet on a date.
+fn date_values(today: Date) -> Template {
+    Template::new(vec![
+        ("DATE".to_owned(), today.to_string()),
+        ("MONTH".to_owned(), today.month_name().to_owned()),
+        ("YEAR".to_owned(), today.year().to_string()),
+    ])
+}
+
+/// Create a sheet from a template, filling in its `{{DATE}}`, `{{MONTH}}`, and `{{YEAR}}`
+/// placeholders, and any others from environment variables.
+pub fn new_sheet(path: &Path, template: &str, today: Date, force: bool) -> Result<()> {
+    if path.exists() && !force {
+        bail!("{} already exists", path.display());
+    }
+    let sheet = date_values(today).render(&load_template(template)?)?;
+    files::write_atomic(path, &sheet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_templates() {
+        let today = "2025-01-31".parse().unwrap();
+        let budget = date_values(today).render(BUILT_IN_TEMPLATES[0].1).unwrap();
+        assert!(budget.starts_with("# January 2025 budget\n"));
+        let invoice = date_values(today).render(BUILT_IN_TEMPLATES[1].1).unwrap();
+        assert!(invoice.starts_with("# Invoice, January 31, 2025\n"));
+    }
+}
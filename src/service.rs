@@ -0,0 +1,383 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+
+use anyhow::{Context, Result, anyhow, bail};
+
+use crate::config::CONFIG_PATH_VARIABLE;
+
+/// The name of the launchd job or systemd unit running the daemon.
+const LABEL: &str = "com.github.nihaals.soulver-cli-zipper";
+const UNIT_NAME: &str = "soulver-cli-zipper.service";
+const SOCKET_UNIT_NAME: &str = "soulver-cli-zipper.socket";
+
+/// Where the service manager listens for clients of a socket-activated daemon.
+#[derive(Debug, PartialEq, Eq)]
+enum Activation<'a> {
+    Tcp { host: &'a str, port: u16 },
+    Unix(&'a str),
+}
+
+impl<'a> Activation<'a> {
+    /// Parse a TCP address like `127.0.0.1:7700`, or the absolute path of a Unix socket.
+    fn parse(address: &'a str) -> Result<Self> {
+        if address.starts_with('/') {
+            return Ok(Self::Unix(address));
+        }
+        let (host, port) = address
+            .rsplit_once(':')
+            .and_then(|(host, port)| Some((host, port.parse().ok()?)))
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid address {address:?}, expected HOST:PORT or a socket's absolute path"
+                )
+            })?;
+        Ok(Self::Tcp { host, port })
+    }
+}
+
+/// The service manager of the current platform.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Manager {
+    Launchd,
+    Systemd,
+}
+
+impl Manager {
+    fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Launchd
+        } else {
+            Self::Systemd
+        }
+    }
+
+    /// Where the service's definition is installed for the current user.
+    fn definition_path(self) -> Result<PathBuf> {
+        let home = env::var_os("HOME").map(PathBuf::from);
+        let path = match self {
+            Self::Launchd => home.map(|home| {
+                home.join("Library/LaunchAgents")
+                    .join(format!("{LABEL}.plist"))
+            }),
+            Self::Systemd => env::var_os("XDG_CONFIG_HOME")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from)
+                .or_else(|| Some(home?.join(".config")))
+                .map(|config_home| config_home.join("systemd/user").join(UNIT_NAME)),
+        };
+        path.ok_or_else(|| anyhow!("HOME is not set"))
+    }
+}
+
+/// The environment the daemon runs with, so it finds `soulver` and the same config file.
+fn environment() -> Vec<(&'static str, String)> {
+    let mut environment = Vec::new();
+    if let Some(path) = env::var_os("PATH") {
+        environment.push(("PATH", path.to_string_lossy().into_owned()));
+    }
+    if let Some(path) = env::var_os(CONFIG_PATH_VARIABLE) {
+        environment.push((CONFIG_PATH_VARIABLE, path.to_string_lossy().into_owned()));
+    }
+    environment
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The `Sockets` of a launchd job, which launchd listens on to start the job once a client
+/// connects.
+fn launchd_sockets(activation: &Activation) -> String {
+    let listener = match activation {
+        Activation::Tcp { host, port } => format!(
+            "\t\t\t<key>SockNodeName</key>\n\t\t\t<string>{}</string>\n\t\t\t<key>SockServiceName</key>\n\t\t\t<string>{port}</string>\n",
+            escape_xml(host.trim_start_matches('[').trim_end_matches(']')),
+        ),
+        // 384 is the mode 0600, so only the current user can connect
+        Activation::Unix(path) => format!(
+            "\t\t\t<key>SockPathName</key>\n\t\t\t<string>{}</string>\n\t\t\t<key>SockPathMode</key>\n\t\t\t<integer>384</integer>\n",
+            escape_xml(path),
+        ),
+    };
+    format!(
+        "\t<key>Sockets</key>\n\t<dict>\n\t\t<key>Listeners</key>\n\t\t<dict>\n{listener}\t\t</dict>\n\t</dict>\n"
+    )
+}
+
+/// A launchd property list running a command at login and restarting it if it exits, or with
+/// socket activation, only once a client connects.
+fn launchd_plist(
+    command: &[String],
+    environment: &[(&str, String)],
+    activation: Option<&Activation>,
+) -> String {
+    let arguments: String = command
+        .iter()
+        .map(|argument| format!("\t\t<string>{}</string>\n", escape_xml(argument)))
+        .collect();
+    let variables: String = environment
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "\t\t<key>{name}</key>\n\t\t<string>{}</string>\n",
+                escape_xml(value)
+            )
+        })
+        .collect();
+    let start = match activation {
+        Some(activation) => launchd_sockets(activation),
+        None => "\t<key>RunAtLoad</key>\n\t<true/>\n\t<key>KeepAlive</key>\n\t<true/>\n".to_owned(),
+    };
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+	<key>Label</key>
+	<string>{LABEL}</string>
+	<key>ProgramArguments</key>
+	<array>
+{arguments}	</array>
+	<key>EnvironmentVariables</key>
+	<dict>
+{variables}	</dict>
+{start}</dict>
+</plist>
+"#
+    )
+}
+
+/// Quote an argument of a systemd `ExecStart` command if it needs to be.
+fn quote_systemd(argument: &str) -> String {
+    if !argument.is_empty()
+        && !argument
+            .chars()
+            .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '%' | '$'))
+    {
+        return argument.to_owned();
+    }
+    let escaped = argument
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%")
+        .replace('$', "$$");
+    format!("\"{escaped}\"")
+}
+
+/// A systemd user unit running a command at login and restarting it if it fails, or with socket
+/// activation, only once its socket unit is connected to.
+fn systemd_unit(command: &[String], environment: &[(&str, String)], activated: bool) -> String {
+    let exec_start: Vec<String> = command
+        .iter()
+        .map(|argument| quote_systemd(argument))
+        .collect();
+    let variables: String = environment
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "Environment={}\n",
+                quote_systemd(&format!("{name}={value}"))
+            )
+        })
+        .collect();
+    // A socket-activated service is started by its socket unit, which is installed instead
+    let install = if activated {
+        ""
+    } else {
+        "\n[Install]\nWantedBy=default.target\n"
+    };
+    format!(
+        "[Unit]\nDescription=soulver-cli-zipper daemon\n\n[Service]\nExecStart={}\n{variables}Restart=on-failure\n{install}",
+        exec_start.join(" "),
+    )
+}
+
+/// A systemd user socket unit listening for the daemon, which starts it once a client connects.
+fn systemd_socket(activation: &Activation) -> String {
+    let listen = match activation {
+        Activation::Tcp { host, port } => format!("ListenStream={host}:{port}\n"),
+        Activation::Unix(path) => {
+            format!("ListenStream={}\nSocketMode=0600\n", quote_systemd(path))
+        }
+    };
+    format!(
+        "[Unit]\nDescription=soulver-cli-zipper daemon socket\n\n[Socket]\n{listen}\n[Install]\nWantedBy=sockets.target\n"
+    )
+}
+
+/// Run a service manager command, failing if it does.
+fn run(program: &str, arguments: &[&str]) -> Result<()> {
+    let status = Command::new(program)
+        .args(arguments)
+        .status()
+        .with_context(|| format!("failed to run {program}"))?;
+    if !status.success() {
+        bail!("`{program} {}` failed with {status}", arguments.join(" "));
+    }
+    Ok(())
+}
+
+/// Install and start a service running the daemon with arguments at login, or with an address to
+/// activate it on, once a client connects to the address.
+pub fn install(activate_on: Option<&str>, daemon_arguments: &[String]) -> Result<()> {
+    let activation = activate_on.map(Activation::parse).transpose()?;
+    let manager = Manager::current();
+    let path = manager.definition_path()?;
+    let executable = env::current_exe().context("failed to find this executable")?;
+    let mut command = vec![executable.display().to_string(), "daemon".to_owned()];
+    if activation.is_some() {
+        command.push("--socket-activated".to_owned());
+    }
+    command.extend_from_slice(daemon_arguments);
+    let definition = match manager {
+        Manager::Launchd => launchd_plist(&command, &environment(), activation.as_ref()),
+        Manager::Systemd => systemd_unit(&command, &environment(), activation.is_some()),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, definition).with_context(|| format!("failed to write {}", path.display()))?;
+    let socket_path = path.with_file_name(SOCKET_UNIT_NAME);
+    if let Some(activation) = &activation
+        && manager == Manager::Systemd
+    {
+        fs::write(&socket_path, systemd_socket(activation))
+            .with_context(|| format!("failed to write {}", socket_path.display()))?;
+    }
+    let path_text = path.display().to_string();
+    match manager {
+        Manager::Launchd => run("launchctl", &["load", "-w", &path_text])?,
+        Manager::Systemd => {
+            run("systemctl", &["--user", "daemon-reload"])?;
+            let unit = if activation.is_some() {
+                SOCKET_UNIT_NAME
+            } else {
+                UNIT_NAME
+            };
+            run("systemctl", &["--user", "enable", "--now", unit])?;
+        }
+    }
+    outputln!("Installed {}", path.display());
+    Ok(())
+}
+
+/// Stop the daemon's service and remove it.
+pub fn uninstall() -> Result<()> {
+    let manager = Manager::current();
+    let path = manager.definition_path()?;
+    if !path.exists() {
+        bail!("the service is not installed at {}", path.display());
+    }
+    let path_text = path.display().to_string();
+    let socket_path = path.with_file_name(SOCKET_UNIT_NAME);
+    match manager {
+        Manager::Launchd => run("launchctl", &["unload", "-w", &path_text])?,
+        Manager::Systemd if socket_path.exists() => run(
+            "systemctl",
+            &["--user", "disable", "--now", SOCKET_UNIT_NAME, UNIT_NAME],
+        )?,
+        Manager::Systemd => run("systemctl", &["--user", "disable", "--now", UNIT_NAME])?,
+    }
+    fs::remove_file(&path)?;
+    if manager == Manager::Systemd {
+        if socket_path.exists() {
+            fs::remove_file(&socket_path)?;
+        }
+        run("systemctl", &["--user", "daemon-reload"])?;
+    }
+    outputln!("Uninstalled {}", path.display());
+    Ok(())
+}
+
+/// Show whether the daemon's service is running, exiting with the service manager's exit code.
+pub fn status() -> Result<ExitCode> {
+    let manager = Manager::current();
+    let path = manager.definition_path()?;
+    if !path.exists() {
+        outputln!("The service is not installed");
+        return Ok(ExitCode::FAILURE);
+    }
+    let status = match manager {
+        Manager::Launchd => Command::new("launchctl").args(["list", LABEL]).status(),
+        Manager::Systemd => Command::new("systemctl")
+            .args(["--user", "status", UNIT_NAME])
+            .status(),
+    }?;
+    Ok(if status.success() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn command() -> Vec<String> {
+        [
+            "/usr/local/bin/soulver-cli-zipper",
+            "daemon",
+            "--listen",
+            "0.0.0.0:7700",
+        ]
+        .map(str::to_owned)
+        .to_vec()
+    }
+
+    #[test]
+    fn test_launchd_plist() {
+        let plist = launchd_plist(&command(), &[("PATH", "/a&b:/usr/bin".to_owned())], None);
+        assert!(plist.contains(
+            "\t<array>\n\t\t<string>/usr/local/bin/soulver-cli-zipper</string>\n\t\t<string>daemon</string>\n"
+        ));
+        assert!(plist.contains("\t\t<key>PATH</key>\n\t\t<string>/a&amp;b:/usr/bin</string>\n"));
+    }
+
+    #[test]
+    fn test_systemd_unit() {
+        let unit = systemd_unit(
+            &command(),
+            &[("PATH", "/My Apps:/usr/bin".to_owned())],
+            false,
+        );
+        assert!(unit.contains(
+            "\nExecStart=/usr/local/bin/soulver-cli-zipper daemon --listen 0.0.0.0:7700\n"
+        ));
+        assert!(unit.contains("\nEnvironment=\"PATH=/My Apps:/usr/bin\"\n"));
+    }
+
+    #[test]
+    fn test_socket_activation() {
+        let activation = Activation::parse("127.0.0.1:7700").unwrap();
+        assert_eq!(
+            activation,
+            Activation::Tcp {
+                host: "127.0.0.1",
+                port: 7700
+            }
+        );
+        assert_eq!(
+            Activation::parse("/tmp/daemon.sock").unwrap(),
+            Activation::Unix("/tmp/daemon.sock")
+        );
+        assert!(Activation::parse("localhost").is_err());
+
+        let plist = launchd_plist(&command(), &[], Some(&activation));
+        assert!(plist.contains("\t\t\t<key>SockServiceName</key>\n\t\t\t<string>7700</string>\n"));
+        assert!(!plist.contains("KeepAlive"));
+        assert!(!systemd_unit(&command(), &[], true).contains("[Install]"));
+        assert!(systemd_socket(&activation).contains("\n[Socket]\nListenStream=127.0.0.1:7700\n"));
+    }
+
+    #[test]
+    fn test_quote_systemd() {
+        assert_eq!(quote_systemd("--listen"), "--listen");
+        assert_eq!(quote_systemd(""), "\"\"");
+        assert_eq!(quote_systemd("50% $x"), "\"50%% $$x\"");
+    }
+}
@@ -0,0 +1,100 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+
+use crate::files;
+use crate::soulver;
+
+/// The outcome of comparing a sheet against its snapshot.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verification {
+    Matches,
+    Missing,
+    /// The snapshot differs, with a diff of the snapshot against the current output
+    Differs(String),
+}
+
+/// The path of the snapshot for a sheet, e.g. `budget.soulver.snap` for `budget.soulver`.
+pub fn snapshot_path(file: &Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".snap");
+    PathBuf::from(path)
+}
+
+/// Diff two multi-line strings line by line, only including lines that differ.
+fn diff_lines(old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut out = Vec::new();
+    for index in 0..old_lines.len().max(new_lines.len()) {
+        let old_line = old_lines.get(index);
+        let new_line = new_lines.get(index);
+        if old_line == new_line {
+            continue;
+        }
+        out.push(format!("@@ line {} @@", index + 1));
+        if let Some(old_line) = old_line {
+            out.push(format!("-{old_line}"));
+        }
+        if let Some(new_line) = new_line {
+            out.push(format!("+{new_line}"));
+        }
+    }
+    out.join("\n")
+}
+
+/// Calculate a sheet and write its zipped output to its snapshot, replacing it atomically so an
+/// interrupted update never leaves half a snapshot.
+pub fn update(file: &Path) -> Result<()> {
+    let output = soulver::run_soulver_zipped(&fs::read_to_string(file)?)?;
+    files::write_atomic(&snapshot_path(file), format!("{output}\n"))
+}
+
+/// Calculate a sheet and compare its zipped output against its snapshot.
+pub fn verify(file: &Path) -> Result<Verification> {
+    let snapshot_path = snapshot_path(file);
+    if !snapshot_path.exists() {
+        return Ok(Verification::Missing);
+    }
+    let snapshot = fs::read_to_string(snapshot_path)?;
+    let output = soulver::run_soulver_zipped(&fs::read_to_string(file)?)?;
+
+    let snapshot = snapshot.strip_suffix('\n').unwrap_or(&snapshot);
+    if snapshot == output {
+        Ok(Verification::Matches)
+    } else {
+        Ok(Verification::Differs(diff_lines(snapshot, &output)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_path() {
+        assert_eq!(
+            snapshot_path(Path::new("notes/budget.soulver")),
+            PathBuf::from("notes/budget.soulver.snap"),
+        )
+    }
+
+    #[test]
+    fn test_diff_lines_same() {
+        assert_eq!(diff_lines("1 | 1\n2 | 2", "1 | 1\n2 | 2"), "")
+    }
+
+    #[test]
+    fn test_diff_lines_changed() {
+        assert_eq!(
+            diff_lines("1 | 1\n$1 in £ | £0.80", "1 | 1\n$1 in £ | £0.75"),
+            "@@ line 2 @@\n-$1 in £ | £0.80\n+$1 in £ | £0.75",
+        )
+    }
+
+    #[test]
+    fn test_diff_lines_added() {
+        assert_eq!(diff_lines("1 | 1", "1 | 1\n2 | 2"), "@@ line 2 @@\n+2 | 2")
+    }
+}
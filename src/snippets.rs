@@ -0,0 +1,114 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use anyhow::{Context, Result, bail};
+
+use crate::config::Config;
+use crate::{files, vars};
+
+/// The extension of snippet files.
+const EXTENSION: &str = "soulver";
+
+/// The directory snippets are stored in, next to the config file.
+fn directory() -> Result<PathBuf> {
+    let directory = Config::directory().context("could not find the config directory")?;
+    Ok(directory.join("snippets"))
+}
+
+/// The path a snippet is stored at.
+fn path(name: &str) -> Result<PathBuf> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        bail!("{name:?} is not a valid snippet name");
+    }
+    Ok(directory()?.join(format!("{name}.{EXTENSION}")))
+}
+
+/// Save a sheet as a snippet, replacing any snippet with the same name.
+pub fn save(name: &str, sheet: &str) -> Result<()> {
+    let path = path(name)?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    files::write_atomic(&path, sheet)
+}
+
+/// Read a snippet's sheet.
+pub fn load(name: &str) -> Result<String> {
+    let path = path(name)?;
+    match fs::read_to_string(&path) {
+        Ok(sheet) => Ok(sheet),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            bail!("there is no snippet named {name:?}")
+        }
+        Err(error) => {
+            Err(error).with_context(|| format!("failed to read snippet {}", path.display()))
+        }
+    }
+}
+
+/// The names of the saved snippets, sorted.
+pub fn list() -> Result<Vec<String>> {
+    let entries = match fs::read_dir(directory()?) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == EXTENSION)
+            && let Some(name) = path.file_stem()
+        {
+            names.push(name.to_string_lossy().into_owned());
+        }
+    }
+    names.sort_unstable();
+    Ok(names)
+}
+
+/// Apply definitions to a snippet, replacing the value of the first assignment of each defined
+/// variable so snippets can give defaults. Variables the snippet does not assign are returned as
+/// lines to calculate before it.
+pub fn apply_definitions(sheet: &str, definitions: &[vars::Definition]) -> (String, Vec<String>) {
+    let mut lines: Vec<String> = sheet.lines().map(str::to_owned).collect();
+    let mut prelude = Vec::new();
+    for definition in definitions {
+        let assignment = lines.iter().position(|line| {
+            vars::parse_assignment(line).is_some_and(|(name, _)| name == definition.name)
+        });
+        match assignment {
+            Some(index) => lines[index] = definition.to_line(),
+            None => prelude.push(definition.to_line()),
+        }
+    }
+    (lines.join("\n"), prelude)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        assert!(path("").is_err());
+        assert!(path("../secrets").is_err());
+        assert!(path(".hidden").is_err());
+    }
+
+    #[test]
+    fn test_apply_definitions() {
+        let definitions = ["Bill=$80".parse().unwrap(), "People=4".parse().unwrap()];
+        let (sheet, prelude) = apply_definitions(
+            "Bill = $50\nTip = 15%\nBill + Tip\n(Bill + Tip) / People",
+            &definitions,
+        );
+        assert_eq!(
+            sheet,
+            "Bill = $80\nTip = 15%\nBill + Tip\n(Bill + Tip) / People"
+        );
+        assert_eq!(prelude, ["People = 4"]);
+    }
+}
@@ -1,15 +1,274 @@
-use std::process::Command;
+use std::borrow::Cow;
+use std::env;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 use anyhow::{Result, bail, ensure};
+pub use soulver_cli_zipper::is_divider;
+use soulver_cli_zipper::{ZipOptions, zip_lines};
+
+use crate::dates::{self, Date};
+#[cfg(feature = "mock-backend")]
+use crate::mock;
+use crate::profile::{self, Phase};
+use crate::task;
+use crate::{daemon, json, output, sanitize, vars};
+
+/// Separates independent documents in a single stream.
+pub const DOCUMENT_SEPARATOR: &str = "---";
+
+/// The date relative dates like `today` are pinned to, if any.
+static TODAY: OnceLock<Date> = OnceLock::new();
+
+/// The prefixes of lines `soulver` outputs nothing for, if not the default ones.
+static COMMENT_PREFIXES: OnceLock<Vec<String>> = OnceLock::new();
+
+/// The prefixes of lines `soulver` outputs nothing for by default.
+pub const DEFAULT_COMMENT_PREFIXES: [&str; 2] = ["#", "//"];
+
+/// Recognise lines starting with `prefixes` as producing no output, instead of
+/// [`DEFAULT_COMMENT_PREFIXES`], for the rest of the process.
+pub fn set_comment_prefixes(prefixes: Vec<String>) {
+    COMMENT_PREFIXES
+        .set(prefixes)
+        .expect("comment prefixes are only set once");
+}
 
-fn run_raw_soulver(file: &str) -> Result<String> {
-    let output = Command::new("soulver").arg(file).output()?;
+/// The host to run `soulver` on over SSH, if not locally.
+static REMOTE: OnceLock<String> = OnceLock::new();
+
+/// Run `soulver` on a host over SSH instead of locally for the rest of the process.
+pub fn set_remote(host: String) {
+    REMOTE.set(host).expect("the remote is only set once");
+}
+
+/// The daemon to calculate sheets with instead of `soulver`, if any.
+static DAEMON: OnceLock<daemon::Client> = OnceLock::new();
+
+/// Calculate sheets with a daemon instead of `soulver` for the rest of the process.
+pub fn set_daemon(client: daemon::Client) {
+    DAEMON.set(client).expect("the daemon is only set once");
+}
+
+/// Quote text as a single argument for a POSIX shell, like the one SSH runs commands with.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
+
+/// The program and arguments to calculate a sheet with `soulver`, which with a remote runs `ssh`,
+/// sharing one connection between runs for a while so each run doesn't connect again.
+///
+/// Soulver has no Windows version, so on Windows the `soulver` installed in WSL is run instead.
+fn soulver_argv(file: &str, remote: Option<&str>) -> Vec<String> {
+    match remote {
+        Some(host) => {
+            let mut argv = vec!["ssh".to_owned()];
+            // Windows' OpenSSH cannot share connections
+            if !cfg!(windows) {
+                argv.extend(
+                    [
+                        "-o",
+                        "ControlMaster=auto",
+                        "-o",
+                        "ControlPath=~/.ssh/soulver-cli-zipper-%C",
+                        "-o",
+                        "ControlPersist=10m",
+                    ]
+                    .map(str::to_owned),
+                );
+            }
+            argv.extend([
+                "--".to_owned(),
+                host.to_owned(),
+                format!("soulver {}", shell_quote(file)),
+            ]);
+            argv
+        }
+        None if cfg!(windows) => vec![
+            "wsl.exe".to_owned(),
+            "--exec".to_owned(),
+            "soulver".to_owned(),
+            file.to_owned(),
+        ],
+        None => vec!["soulver".to_owned(), file.to_owned()],
+    }
+}
+
+/// Whether any line calculated so far should have had a result but did not.
+static ANY_LINE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Whether any line calculated so far had a result.
+static ANY_RESULT: AtomicBool = AtomicBool::new(false);
+
+/// Whether any line calculated so far in the process should have had a result but did not.
+pub fn any_line_failed() -> bool {
+    ANY_LINE_FAILED.load(Ordering::Relaxed)
+}
+
+/// Whether any line calculated so far in the process had a result.
+pub fn any_result() -> bool {
+    ANY_RESULT.load(Ordering::Relaxed)
+}
+
+/// Whether `soulver` is expected to output a result for a line, unlike blank lines, comments,
+/// dividers, and lines only containing a label.
+pub fn expects_result(line: &str) -> bool {
+    !line.trim().is_empty()
+        && !is_comment(line.trim_start())
+        && !is_divider(line)
+        && !vars::parse_label(line).is_some_and(|(_, expression)| expression.is_empty())
+}
+
+/// Record whether the lines of a sheet had the results they should have.
+fn record_results(lines: &[Line]) {
+    for line in lines {
+        if !line.output.is_empty() {
+            ANY_RESULT.store(true, Ordering::Relaxed);
+        } else if expects_result(&line.input) {
+            ANY_LINE_FAILED.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Calculate relative dates like `today` relative to `date` instead of the current date for the
+/// rest of the process.
+pub fn pin_today(date: Date) {
+    TODAY.set(date).expect("today is only pinned once");
+}
+
+/// Find the executable a command would run in `PATH`.
+fn find_program(name: &str) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|directory| directory.join(name))
+        .find(|path| path.is_file())
+}
+
+/// Replace relative dates in a sheet if today is pinned.
+fn pin_today_in(file: &str) -> Cow<'_, str> {
+    match TODAY.get() {
+        Some(today) => Cow::Owned(dates::pin_today(file, *today)),
+        None => Cow::Borrowed(file),
+    }
+}
+
+/// The exact sheet `soulver` is run with to calculate a sheet after the lines of a prelude.
+pub fn soulver_input(prelude: &[&str], file: &str) -> String {
+    let sheet = if prelude.is_empty() {
+        Cow::Borrowed(file)
+    } else {
+        Cow::Owned(format!("{}\n{file}", prelude.join("\n")))
+    };
+    pin_today_in(sheet.trim_end()).into_owned()
+}
+
+/// Run `soulver`, locally or over SSH, and return its output.
+// The mock backend calculates sheets instead
+#[cfg_attr(feature = "mock-backend", allow(dead_code))]
+fn run_program(file: &str, remote: Option<&str>) -> Result<String> {
+    let start = Instant::now();
+    let argv = soulver_argv(file, remote);
+    if output::is_verbose() {
+        let path = find_program(&argv[0]).map(|path| path.display().to_string());
+        let text = format!(
+            "soulver: running {}\nsoulver: argv {argv:?}",
+            path.as_deref().unwrap_or("not found in PATH"),
+        );
+        output::trace(
+            "soulver_start",
+            &text,
+            vec![
+                ("path", json::Value::from(path)),
+                ("argv", json::Value::from(argv.clone())),
+            ],
+        );
+    }
+    // Spawned rather than run with `output` so a cancelled task kills it
+    let output = profile::time(Phase::Soulver, || {
+        let child = Command::new(&argv[0])
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        task::wait_with_output(child)
+    })?;
+    let duration = start.elapsed();
+    output::trace(
+        "soulver_exit",
+        &format!("soulver: {} after {duration:.1?}", output.status),
+        vec![
+            (
+                "status",
+                json::Value::from(output.status.code().map(f64::from)),
+            ),
+            (
+                "duration_ms",
+                json::Value::from(duration.as_secs_f64() * 1000.0),
+            ),
+        ],
+    );
     if !output.status.success() {
         bail!("soulver exited with non-zero exit code");
     }
-    let stdout = str::from_utf8(&output.stdout)?;
-    let stdout_no_trailing = stdout.strip_suffix('\n').unwrap_or(stdout);
-    Ok(stdout_no_trailing.to_owned())
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Run `soulver` and return each line it output.
+///
+/// Unlike splitting [`run_raw_soulver`]'s output, this keeps trailing lines with empty results.
+fn run_raw_soulver_lines(file: &str) -> Result<Vec<String>> {
+    let file = profile::time(Phase::Preprocess, || pin_today_in(file));
+    let stdout = match DAEMON.get() {
+        Some(daemon) => {
+            output::trace(
+                "soulver_start",
+                &format!("soulver: calculating with the daemon at {}", daemon.address),
+                vec![("daemon", json::Value::from(daemon.address.to_string()))],
+            );
+            profile::time(Phase::Soulver, || daemon.calculate(&file))?
+        }
+        #[cfg(feature = "mock-backend")]
+        None => profile::time(Phase::Soulver, || mock::calculate(&file))?,
+        #[cfg(not(feature = "mock-backend"))]
+        None => run_program(&file, REMOTE.get().map(String::as_str))?,
+    };
+    if stdout.is_empty() {
+        return Ok(Vec::new());
+    }
+    let stdout_no_trailing = stdout.strip_suffix('\n').unwrap_or(&stdout);
+    Ok(stdout_no_trailing.split('\n').map(str::to_owned).collect())
+}
+
+/// Run the `soulver` on this machine, even if sheets are otherwise calculated remotely or with a
+/// daemon, e.g. for the daemon's clients, and return its output.
+pub fn run_local_soulver(file: &str) -> Result<String> {
+    let file = sanitize::sheet(file);
+    let file = profile::time(Phase::Preprocess, || pin_today_in(&file).into_owned());
+    #[cfg(feature = "mock-backend")]
+    return profile::time(Phase::Soulver, || mock::calculate(&file));
+    #[cfg(not(feature = "mock-backend"))]
+    run_program(&file, None)
+}
+
+fn run_raw_soulver(file: &str) -> Result<String> {
+    Ok(run_raw_soulver_lines(file)?.join("\n"))
+}
+
+fn starts_with_any<S: AsRef<str>>(line: &str, prefixes: &[S]) -> bool {
+    prefixes
+        .iter()
+        .any(|prefix| line.starts_with(prefix.as_ref()))
+}
+
+/// Whether `soulver` outputs nothing for a line because it starts with a comment prefix.
+pub fn is_comment(line: &str) -> bool {
+    match COMMENT_PREFIXES.get() {
+        Some(prefixes) => starts_with_any(line, prefixes),
+        None => starts_with_any(line, &DEFAULT_COMMENT_PREFIXES),
+    }
 }
 
 fn get_number_of_initial_newlines<I, S>(lines: I) -> usize
@@ -21,12 +280,13 @@ where
         .into_iter()
         .take_while(|line| {
             let line_str = line.as_ref();
-            line_str.is_empty() || line_str.starts_with('#') || line_str.starts_with("//")
+            line_str.is_empty() || is_comment(line_str) || is_divider(line_str)
         })
         .count()
 }
 
 pub fn run_soulver(file: &str) -> Result<String> {
+    let file = sanitize::sheet(file);
     let trimmed_input = file.trim_end();
     let mut output = run_raw_soulver(trimmed_input)?;
 
@@ -35,46 +295,226 @@ pub fn run_soulver(file: &str) -> Result<String> {
         output.insert_str(0, &"\n".repeat(initial_newlines));
     }
 
+    let lines: Vec<Line> = trimmed_input
+        .lines()
+        .zip(output.split('\n'))
+        .map(|(input, output)| Line {
+            input: input.to_owned(),
+            output: output.to_owned(),
+        })
+        .collect();
+    record_results(&lines);
     Ok(output)
 }
 
-pub fn run_soulver_zipped(file: &str) -> Result<String> {
+/// Split a stream into the documents separated by [`DOCUMENT_SEPARATOR`] lines.
+pub fn split_documents(file: &str) -> Vec<&str> {
+    let mut documents = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for line in file.split_inclusive('\n') {
+        if line.trim_end() == DOCUMENT_SEPARATOR {
+            documents.push(
+                file[start..offset]
+                    .strip_suffix('\n')
+                    .unwrap_or(&file[start..offset]),
+            );
+            start = offset + line.len();
+        }
+        offset += line.len();
+    }
+    documents.push(&file[start..]);
+    documents
+}
+
+/// A line of a sheet along with the result `soulver` calculated for it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub input: String,
+    pub output: String,
+}
+
+#[cfg(test)]
+impl Line {
+    pub fn new(input: &str, output: &str) -> Self {
+        Self {
+            input: input.to_owned(),
+            output: output.to_owned(),
+        }
+    }
+}
+
+/// Calculate a sheet and pair each input line with its result.
+pub fn calculate_lines(file: &str) -> Result<Vec<Line>> {
+    let lines = pair_lines(file)?;
+    record_results(&lines);
+    Ok(lines)
+}
+
+fn pair_lines(file: &str) -> Result<Vec<Line>> {
+    let file = sanitize::sheet(file);
     let trimmed_input = file.trim_end();
-    let output = run_soulver(trimmed_input)?;
-    let output_lines: Vec<String> = output.lines().map(|line| line.to_owned()).collect();
     let input_lines: Vec<&str> = trimmed_input.lines().collect();
-    let longest_input_line_length = input_lines
-        .iter()
-        .map(|line| line.chars().count())
-        .max()
-        .unwrap_or(0);
-
-    let mut out = String::with_capacity(trimmed_input.len() + output.len());
+    let initial_newlines = get_number_of_initial_newlines(&input_lines);
+    let mut output_lines = vec![String::new(); initial_newlines];
+    output_lines.extend(run_raw_soulver_lines(trimmed_input)?);
     ensure!(input_lines.len() == output_lines.len());
-    for (input_line, output_line) in input_lines.iter().zip(output_lines.iter()) {
-        if output_line.is_empty() {
-            out.push_str(&format!(
-                "{input_line:<width$} |\n",
-                width = longest_input_line_length,
-            ));
-        } else {
-            out.push_str(&format!(
-                "{input_line:<width$} | {output_line}\n",
-                width = longest_input_line_length,
-            ));
+
+    Ok(input_lines
+        .into_iter()
+        .zip(output_lines)
+        .map(|(input, output)| Line {
+            input: input.to_owned(),
+            output,
+        })
+        .collect())
+}
+
+/// Calculate a sheet after the lines of a prelude, which are not included in the result.
+///
+/// This allows the sheet to use variables defined in the prelude.
+pub fn calculate_lines_with_prelude(prelude: &[&str], file: &str) -> Result<Vec<Line>> {
+    if prelude.is_empty() {
+        return calculate_lines(file);
+    }
+    let sheet = format!("{}\n{file}", prelude.join("\n"));
+    let mut lines = pair_lines(&sheet)?;
+    let lines = lines.split_off(prelude.len().min(lines.len()));
+    record_results(&lines);
+    Ok(lines)
+}
+
+/// The result of lines that should have a result but do not with `--mark-errors`, or that
+/// `soulver` failed on with `--partial`.
+pub const ERROR_MARKER: &str = "⚠ error";
+
+/// Calculate as much of a sheet `calculate` fails on as possible.
+///
+/// The first line it fails on is found by calculating shorter and shorter prefixes of the sheet,
+/// then the sheet is calculated again with that line left blank, until it succeeds. The lines left
+/// blank have [`ERROR_MARKER`] as their result.
+pub fn calculate_partially<F>(sheet: &str, calculate: F) -> Result<Vec<Line>>
+where
+    F: Fn(&str) -> Result<Vec<Line>>,
+{
+    let error = match calculate(sheet) {
+        Ok(lines) => return Ok(lines),
+        Err(error) => error,
+    };
+    if calculate("").is_err() {
+        return Err(error);
+    }
+    let sheet_lines: Vec<&str> = sheet.lines().collect();
+    let mut inputs = sheet_lines.clone();
+    let mut failed = Vec::new();
+    loop {
+        // The first `succeeded` lines are calculated, and the first `failing` lines are not
+        let (mut succeeded, mut failing) = (0, inputs.len());
+        while failing - succeeded > 1 {
+            let middle = (succeeded + failing) / 2;
+            if calculate(&inputs[..middle].join("\n")).is_ok() {
+                succeeded = middle;
+            } else {
+                failing = middle;
+            }
         }
+        if failing == 0 || inputs[failing - 1].trim().is_empty() {
+            return Err(error);
+        }
+        inputs[failing - 1] = "";
+        failed.push(failing - 1);
+        let Ok(mut lines) = calculate(&inputs.join("\n")) else {
+            continue;
+        };
+        for index in failed {
+            // Trailing blank lines have no result, so the last line may be missing
+            if lines.len() <= index {
+                lines.resize_with(index + 1, || Line {
+                    input: String::new(),
+                    output: String::new(),
+                });
+            }
+            lines[index] = Line {
+                input: sanitize::sheet(sheet_lines[index]).into_owned(),
+                output: ERROR_MARKER.to_owned(),
+            };
+        }
+        ANY_LINE_FAILED.store(true, Ordering::Relaxed);
+        return Ok(lines);
     }
-    if out.ends_with('\n') {
-        out.pop();
-    }
+}
+
+/// Format lines as a table of inputs and results.
+///
+/// Divider lines without results are extended across the whole table.
+pub fn zip(lines: &[Line]) -> String {
+    let inputs: Vec<&str> = lines.iter().map(|line| line.input.as_str()).collect();
+    let outputs: Vec<Option<String>> = lines.iter().map(|line| Some(line.output.clone())).collect();
+    zip_lines(&inputs, &outputs, &ZipOptions::default())
+}
 
-    Ok(out)
+pub fn run_soulver_zipped(file: &str) -> Result<String> {
+    Ok(zip(&calculate_lines(file)?))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("1 + 1"), "'1 + 1'");
+        assert_eq!(shell_quote("it's $5"), r"'it'\''s $5'");
+    }
+
+    #[test]
+    fn test_calculate_partially() {
+        // Fails on any sheet with a `bad` line, like `soulver` failing on one construct
+        let calculate = |sheet: &str| {
+            if sheet.lines().any(|line| line == "bad") {
+                bail!("failed");
+            }
+            Ok(sheet
+                .lines()
+                .map(|line| Line {
+                    input: line.to_owned(),
+                    output: line.to_uppercase(),
+                })
+                .collect())
+        };
+        let line = |input: &str, output: &str| Line {
+            input: input.to_owned(),
+            output: output.to_owned(),
+        };
+        assert_eq!(
+            calculate_partially("a\nbad\nb\nbad", calculate).unwrap(),
+            [
+                line("a", "A"),
+                line("bad", ERROR_MARKER),
+                line("b", "B"),
+                line("bad", ERROR_MARKER),
+            ],
+        );
+        assert_eq!(
+            calculate_partially("a", calculate).unwrap(),
+            [line("a", "A")]
+        );
+        assert!(calculate_partially("a", |_| bail!("failed")).is_err());
+    }
+
+    #[test]
+    fn test_split_documents() {
+        assert_eq!(
+            split_documents("Foo = 1\n---\nFoo = 2\n---\n\n"),
+            vec!["Foo = 1", "Foo = 2", "\n"],
+        )
+    }
+
+    #[test]
+    fn test_split_documents_single() {
+        assert_eq!(split_documents("Foo = 1\n----\n"), vec!["Foo = 1\n----\n"])
+    }
+
     #[test]
     fn test_run_raw_soulver_variable() {
         assert_eq!(run_raw_soulver("Foo = 1\nFoo + 2").unwrap(), "1\n3")
@@ -156,6 +596,60 @@ mod tests {
         assert_eq!(get_number_of_initial_newlines(lines), expected);
     }
 
+    #[test]
+    fn test_get_number_of_initial_newlines_divider() {
+        assert_eq!(get_number_of_initial_newlines(["# Foo", "----", "1"]), 2);
+    }
+
+    #[test]
+    fn test_expects_result() {
+        assert!(expects_result("1 + 2"));
+        assert!(expects_result("Rent: £1000"));
+        assert!(!expects_result("  "));
+        assert!(!expects_result("  # Heading"));
+        assert!(!expects_result("----"));
+        assert!(!expects_result("Bills:"));
+    }
+
+    #[test]
+    fn test_is_divider() {
+        assert!(is_divider("----"));
+        assert!(is_divider("  ---  "));
+        assert!(!is_divider("--"));
+        assert!(!is_divider("-- 1"));
+    }
+
+    #[test]
+    fn test_zip_divider() {
+        let lines = [
+            Line {
+                input: "Foo = 10".to_owned(),
+                output: "10".to_owned(),
+            },
+            Line {
+                input: "----".to_owned(),
+                output: String::new(),
+            },
+            Line {
+                input: "Foo * 100".to_owned(),
+                output: "1,000".to_owned(),
+            },
+        ];
+        assert_eq!(
+            zip(&lines),
+            "Foo = 10  | 10\n-----------------\nFoo * 100 | 1,000",
+        );
+    }
+
+    #[test]
+    fn test_starts_with_any() {
+        let prefixes = ["#", ";", "----"];
+        assert!(starts_with_any("; Note", &prefixes));
+        assert!(starts_with_any("--------", &prefixes));
+        assert!(!starts_with_any("// Note", &prefixes));
+        assert!(!starts_with_any("1 - 2", &prefixes));
+    }
+
     #[test]
     fn test_run_soulver_variable() {
         assert_eq!(run_soulver("Foo = 1\nFoo + 2").unwrap(), "1\n3")
@@ -201,6 +695,18 @@ mod tests {
         assert_eq!(run_soulver("1\n\n\n").unwrap(), "1")
     }
 
+    #[test]
+    fn test_calculate_lines_with_prelude() {
+        let lines = calculate_lines_with_prelude(&["Foo = 1"], "Foo + 2").unwrap();
+        assert_eq!(
+            lines,
+            vec![Line {
+                input: "Foo + 2".to_owned(),
+                output: "3".to_owned(),
+            }],
+        )
+    }
+
     #[test]
     fn test_run_soulver_zipped_variable() {
         assert_eq!(
@@ -241,6 +747,14 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_run_soulver_zipped_trailing_headings() {
+        assert_eq!(
+            run_soulver_zipped("1\n# Foo\n# Bar").unwrap(),
+            "1     | 1\n# Foo |\n# Bar |",
+        )
+    }
+
     #[test]
     fn test_run_soulver_zipped_trailing_newlines_1() {
         assert_eq!(run_soulver_zipped("1\n").unwrap(), "1 | 1")
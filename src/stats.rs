@@ -0,0 +1,184 @@
+use crate::json;
+use crate::quantity::{self, Quantity};
+use crate::soulver::Line;
+use crate::totals;
+
+/// Statistics over the numeric results of a sheet in their most common unit.
+#[derive(Debug, PartialEq)]
+pub struct Stats {
+    pub count: usize,
+    pub sum: f64,
+    pub mean: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Formats values in the unit the statistics are in
+    style: Quantity,
+}
+
+impl Stats {
+    /// Calculate statistics over lines, returning `None` if none have numeric results.
+    pub fn from_lines(lines: &[Line]) -> Option<Self> {
+        let (style, values) =
+            quantity::dominant_unit(lines.iter().map(|line| line.output.as_str()))?;
+        let sum: f64 = values.iter().sum();
+        Some(Self {
+            count: values.len(),
+            sum,
+            mean: sum / values.len() as f64,
+            min: values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            style,
+        })
+    }
+
+    fn format_mean(&self) -> String {
+        let decimals = if self.style.decimals == 0 && self.mean.fract() != 0.0 {
+            2
+        } else {
+            self.style.decimals
+        };
+        Quantity {
+            decimals,
+            ..self.style.clone()
+        }
+        .format_value(self.mean)
+    }
+
+    /// Format the statistics as aligned `name value` lines.
+    pub fn to_text(&self) -> String {
+        [
+            ("count", self.count.to_string()),
+            ("sum", self.style.format_value(self.sum)),
+            ("mean", self.format_mean()),
+            ("min", self.style.format_value(self.min)),
+            ("max", self.style.format_value(self.max)),
+        ]
+        .iter()
+        .map(|(name, value)| format!("{name:<5} {value}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+    }
+
+    pub fn to_json(&self) -> json::Value {
+        json::Value::object([
+            ("count", json::Value::from(self.count)),
+            ("sum", json::Value::from(self.sum)),
+            ("mean", json::Value::from(self.mean)),
+            ("min", json::Value::from(self.min)),
+            ("max", json::Value::from(self.max)),
+            ("prefix", json::Value::from(self.style.prefix.as_str())),
+            ("suffix", json::Value::from(self.style.suffix.as_str())),
+        ])
+    }
+}
+
+/// Calculate statistics for each `#` heading's section that has numeric results.
+pub fn by_heading(lines: &[Line]) -> Vec<(Option<&str>, Stats)> {
+    totals::sections(lines)
+        .into_iter()
+        .filter_map(|(heading, lines)| Some((heading, Stats::from_lines(lines)?)))
+        .collect()
+}
+
+/// Report statistics for a sheet, or each of its `#` headings, as text.
+pub fn report_text(lines: &[Line], by_heading: bool) -> String {
+    if !by_heading {
+        return Stats::from_lines(lines)
+            .map(|stats| stats.to_text())
+            .unwrap_or_default();
+    }
+    self::by_heading(lines)
+        .iter()
+        .map(|(heading, stats)| {
+            format!(
+                "# {}\n{}",
+                heading.unwrap_or("(no heading)"),
+                stats.to_text()
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Report statistics for a sheet as a JSON object, or each of its `#` headings as an array of
+/// objects with a `heading` key.
+pub fn report_json(lines: &[Line], by_heading: bool) -> json::Value {
+    if !by_heading {
+        return Stats::from_lines(lines).map(|stats| stats.to_json()).into();
+    }
+    let groups: Vec<json::Value> = self::by_heading(lines)
+        .iter()
+        .map(|(heading, stats)| {
+            let mut value = stats.to_json();
+            if let json::Value::Object(entries) = &mut value {
+                entries.insert(0, ("heading".to_owned(), json::Value::from(*heading)));
+            }
+            value
+        })
+        .collect();
+    groups.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_to_text() {
+        let lines = [
+            Line::new("# Food", ""),
+            Line::new("£1", "£1.00"),
+            Line::new("£2", "£2.00"),
+            Line::new("3 kg", "3 kg"),
+        ];
+        assert_eq!(
+            Stats::from_lines(&lines).unwrap().to_text(),
+            "count 2\nsum   £3.00\nmean  £1.50\nmin   £1.00\nmax   £2.00",
+        )
+    }
+
+    #[test]
+    fn test_stats_mean_decimals() {
+        let lines = [Line::new("1", "1"), Line::new("2", "2")];
+        assert_eq!(Stats::from_lines(&lines).unwrap().format_mean(), "1.50")
+    }
+
+    #[test]
+    fn test_stats_to_json() {
+        let lines = [Line::new("1", "1"), Line::new("3", "3")];
+        assert_eq!(
+            Stats::from_lines(&lines).unwrap().to_json().to_string(),
+            r#"{"count":2,"sum":4,"mean":2,"min":1,"max":3,"prefix":"","suffix":""}"#,
+        )
+    }
+
+    #[test]
+    fn test_stats_none() {
+        assert_eq!(Stats::from_lines(&[Line::new("# Food", "")]), None)
+    }
+
+    #[test]
+    fn test_by_heading() {
+        let lines = [
+            Line::new("# Food", ""),
+            Line::new("1", "1"),
+            Line::new("# Notes", ""),
+            Line::new("# Travel", ""),
+            Line::new("2", "2"),
+        ];
+        let headings: Vec<Option<&str>> = by_heading(&lines)
+            .into_iter()
+            .map(|(heading, _)| heading)
+            .collect();
+        assert_eq!(headings, vec![Some("Food"), Some("Travel")])
+    }
+
+    #[test]
+    fn test_report_json_by_heading() {
+        let lines = [Line::new("# Food", ""), Line::new("1", "1")];
+        assert_eq!(
+            report_json(&lines, true).to_string(),
+            r#"[{"heading":"Food","count":1,"sum":1,"mean":1,"min":1,"max":1,"prefix":"","suffix":""}]"#,
+        )
+    }
+}
@@ -0,0 +1,321 @@
+use std::cell::RefCell;
+use std::io::{self, Read};
+use std::net::TcpStream;
+use std::num::NonZeroUsize;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::process::{Child, Output};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{Result, anyhow};
+
+/// How often a task's child process is checked for having exited or the task being cancelled.
+const CHILD_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Cancels a task, killing any child process it is waiting for.
+#[derive(Clone, Default)]
+struct Cancellation(Arc<AtomicBool>);
+
+thread_local! {
+    /// The cancellation of the task running on this thread, if it is running one.
+    static CANCELLATION: RefCell<Option<Cancellation>> = const { RefCell::new(None) };
+}
+
+/// Work running on its own thread, which is cancelled by dropping it, discarding its result and
+/// killing any child process it is waiting for with [`wait_with_output`].
+pub struct Task<T> {
+    receiver: Receiver<Result<T>>,
+    cancellation: Cancellation,
+}
+
+/// Start running the work on another thread.
+pub fn spawn<T, F>(work: F) -> Task<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    let cancellation = Cancellation::default();
+    let task_cancellation = cancellation.clone();
+    thread::spawn(move || {
+        CANCELLATION.set(Some(task_cancellation));
+        // Nothing is waiting for the result once the task is cancelled
+        let _ = sender.send(work());
+    });
+    Task {
+        receiver,
+        cancellation,
+    }
+}
+
+impl<T> Drop for Task<T> {
+    fn drop(&mut self) {
+        self.cancellation.0.store(true, Ordering::Relaxed);
+    }
+}
+
+impl<T> Task<T> {
+    /// The work's result, or `None` if it did not finish within the timeout.
+    pub fn wait(&self, timeout: Duration) -> Option<Result<T>> {
+        match self.receiver.recv_timeout(timeout) {
+            Ok(result) => Some(result),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => Some(Err(anyhow!("the task panicked"))),
+        }
+    }
+}
+
+/// Wait for a child to exit and collect its output like [`Child::wait_with_output`], killing it
+/// instead if the task running on this thread is cancelled.
+pub fn wait_with_output(mut child: Child) -> io::Result<Output> {
+    let Some(cancellation) = CANCELLATION.with_borrow(Clone::clone) else {
+        return child.wait_with_output();
+    };
+    fn read_to_end(pipe: Option<impl Read>) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut bytes)?;
+        }
+        Ok(bytes)
+    }
+    let (stdout, stderr) = (child.stdout.take(), child.stderr.take());
+    thread::scope(|scope| {
+        let stdout = scope.spawn(|| read_to_end(stdout));
+        let stderr = scope.spawn(|| read_to_end(stderr));
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if cancellation.0.load(Ordering::Relaxed) {
+                // Killing the child closes its pipes, so the threads reading them finish
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+            }
+            thread::sleep(CHILD_POLL_INTERVAL);
+        };
+        Ok(Output {
+            status,
+            stdout: stdout.join().expect("reading stdout does not panic")?,
+            stderr: stderr.join().expect("reading stderr does not panic")?,
+        })
+    })
+}
+
+/// A connection whose reads and writes can time out.
+pub trait Connection: Send {
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()>;
+}
+
+impl Connection for TcpStream {
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+#[cfg(unix)]
+impl Connection for UnixStream {
+    fn set_timeout(&self, timeout: Duration) -> io::Result<()> {
+        self.set_read_timeout(Some(timeout))?;
+        self.set_write_timeout(Some(timeout))
+    }
+}
+
+/// How many connections are being handled, which finishing a connection wakes the accepting
+/// thread for.
+#[derive(Default)]
+struct InFlight {
+    count: Mutex<usize>,
+    finished: Condvar,
+}
+
+impl InFlight {
+    /// Wait until fewer than `max` connections are being handled, then count another.
+    fn start(&self, max: NonZeroUsize) {
+        let count = self.count.lock().unwrap();
+        let mut count = self
+            .finished
+            .wait_while(count, |count| *count >= max.get())
+            .unwrap();
+        *count += 1;
+    }
+
+    fn finish(&self) {
+        *self.count.lock().unwrap() -= 1;
+        self.finished.notify_one();
+    }
+}
+
+/// Counts a connection as finished when dropped, even if handling it panicked.
+struct Finished<'a>(&'a InFlight);
+
+impl Drop for Finished<'_> {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
+
+/// Handle each accepted connection on its own thread, with reads and writes that time out after
+/// the timeout so idle clients cannot hold a thread forever, until the listener stops accepting
+/// connections.
+///
+/// At most `max_connections` are handled at once. Further connections wait in the listener's
+/// backlog until one finishes, so clients cannot start threads, or processes, without end.
+pub fn serve_connections<C, I, F>(
+    mut incoming: I,
+    timeout: Duration,
+    max_connections: NonZeroUsize,
+    handle: F,
+) where
+    C: Connection,
+    I: Iterator<Item = io::Result<C>>,
+    F: Fn(C) + Sync,
+{
+    let handle = &handle;
+    let in_flight = &InFlight::default();
+    thread::scope(|scope| {
+        loop {
+            in_flight.start(max_connections);
+            let finished = Finished(in_flight);
+            let Some(connection) = incoming.next() else {
+                break;
+            };
+            let Ok(connection) = connection else {
+                continue;
+            };
+            if connection.set_timeout(timeout).is_err() {
+                continue;
+            }
+            scope.spawn(move || {
+                let _finished = finished;
+                handle(connection);
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::process::{Command, Stdio};
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_wait() {
+        let task = spawn(|| Ok(1 + 1));
+        assert_eq!(task.wait(Duration::from_secs(5)).unwrap().unwrap(), 2);
+
+        let task = spawn(|| {
+            thread::sleep(Duration::from_secs(1));
+            Ok(())
+        });
+        assert!(task.wait(Duration::from_millis(10)).is_none());
+
+        let task = spawn(|| -> Result<()> { panic!("broken") });
+        assert!(task.wait(Duration::from_secs(5)).unwrap().is_err());
+    }
+
+    #[test]
+    fn test_wait_with_output() {
+        let task = spawn(|| {
+            let child = Command::new("echo")
+                .arg("1 + 1")
+                .stdout(Stdio::piped())
+                .spawn()?;
+            Ok(wait_with_output(child)?.stdout)
+        });
+        let stdout = task.wait(Duration::from_secs(5)).unwrap().unwrap();
+        assert_eq!(stdout, b"1 + 1\n");
+    }
+
+    #[test]
+    fn test_drop_kills_child() {
+        let (sender, receiver) = mpsc::channel();
+        let task = spawn(move || {
+            let child = Command::new("sleep")
+                .arg("10")
+                .stdout(Stdio::piped())
+                .spawn()?;
+            let start = Instant::now();
+            let result = wait_with_output(child);
+            let _ = sender.send((result.is_err(), start.elapsed()));
+            Ok(())
+        });
+        assert!(task.wait(Duration::from_millis(100)).is_none());
+        drop(task);
+        let (cancelled, elapsed) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(cancelled);
+        assert!(elapsed < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_serve_connections() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            // Both connections are answered, even though the first never sends anything
+            let idle = TcpStream::connect(address).unwrap();
+            let mut stream = TcpStream::connect(address).unwrap();
+            stream.write_all(b"ping").unwrap();
+            stream.shutdown(std::net::Shutdown::Write).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            drop(idle);
+            response
+        });
+        serve_connections(
+            listener.incoming().take(2),
+            Duration::from_millis(500),
+            NonZeroUsize::new(2).unwrap(),
+            |mut stream| {
+                let mut request = String::new();
+                if stream.read_to_string(&mut request).is_ok() {
+                    stream.write_all(request.as_bytes()).unwrap();
+                }
+            },
+        );
+        assert_eq!(client.join().unwrap(), "ping");
+    }
+
+    #[test]
+    fn test_serve_connections_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let clients = thread::spawn(move || {
+            let streams: Vec<_> = (0..3)
+                .map(|_| TcpStream::connect(address).unwrap())
+                .collect();
+            for mut stream in streams {
+                let mut response = String::new();
+                stream.read_to_string(&mut response).unwrap();
+                assert_eq!(response, "done");
+            }
+        });
+        let (running, most_running) = (Mutex::new(0), Mutex::new(0));
+        serve_connections(
+            listener.incoming().take(3),
+            Duration::from_secs(5),
+            NonZeroUsize::new(1).unwrap(),
+            |mut stream| {
+                *running.lock().unwrap() += 1;
+                let current = *running.lock().unwrap();
+                let mut most = most_running.lock().unwrap();
+                *most = (*most).max(current);
+                drop(most);
+                thread::sleep(Duration::from_millis(50));
+                *running.lock().unwrap() -= 1;
+                stream.write_all(b"done").unwrap();
+            },
+        );
+        clients.join().unwrap();
+        assert_eq!(*most_running.lock().unwrap(), 1);
+    }
+}
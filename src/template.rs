@@ -0,0 +1,130 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::config;
+use crate::json;
+
+/// Values to substitute for the `{{NAME}}` placeholders of template sheets, falling back to
+/// environment variables.
+#[derive(Debug, Default)]
+pub struct Template {
+    values: Vec<(String, String)>,
+}
+
+impl Template {
+    pub fn new(values: Vec<(String, String)>) -> Self {
+        Self { values }
+    }
+
+    /// Read values from a JSON object, or a TOML file if the path ends in `.toml`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read template values {}", path.display()))?;
+        let values = if path
+            .extension()
+            .is_some_and(|extension| extension == "toml")
+        {
+            config::parse_toml_strings(&text)
+        } else {
+            parse_json_values(&text)
+        };
+        Ok(Self {
+            values: values
+                .with_context(|| format!("invalid template values {}", path.display()))?,
+        })
+    }
+
+    fn value(&self, name: &str) -> Option<String> {
+        self.values
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .or_else(|| env::var(name).ok())
+    }
+
+    /// Substitute the value of each `{{NAME}}` placeholder in a sheet.
+    pub fn render(&self, sheet: &str) -> Result<String> {
+        let mut rendered = String::with_capacity(sheet.len());
+        let mut rest = sheet;
+        while let Some(start) = rest.find("{{") {
+            let Some(length) = rest[start..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + length].trim();
+            let Some(value) = self.value(name) else {
+                bail!("no value for template placeholder {name:?}");
+            };
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(&value);
+            rest = &rest[start + length + 2..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
+}
+
+/// Parse a JSON object of string, number, or boolean values, converting the values to text.
+fn parse_json_values(text: &str) -> Result<Vec<(String, String)>> {
+    let json::Value::Object(entries) = json::parse(text)? else {
+        bail!("expected a JSON object");
+    };
+    entries
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match value {
+                json::Value::String(string) => string,
+                json::Value::Number(number) => number.to_string(),
+                json::Value::Bool(value) => value.to_string(),
+                _ => bail!("{key} must be a string, number, or boolean"),
+            };
+            Ok((key, value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let template = Template {
+            values: vec![
+                ("CLIENT".to_owned(), "Acme".to_owned()),
+                ("HOURS".to_owned(), "12.5".to_owned()),
+            ],
+        };
+        assert_eq!(
+            template
+                .render("# Invoice for {{CLIENT}}\n{{ HOURS }} * £40\n{ not a placeholder }")
+                .unwrap(),
+            "# Invoice for Acme\n12.5 * £40\n{ not a placeholder }",
+        )
+    }
+
+    #[test]
+    fn test_render_missing() {
+        let template = Template::default();
+        assert!(
+            template
+                .render("{{SOULVER_CLI_ZIPPER_TEST_MISSING}}")
+                .is_err()
+        )
+    }
+
+    #[test]
+    fn test_parse_json_values() {
+        assert_eq!(
+            parse_json_values(r#"{"HOURS": 12.5, "CLIENT": "Acme", "PAID": false}"#).unwrap(),
+            vec![
+                ("HOURS".to_owned(), "12.5".to_owned()),
+                ("CLIENT".to_owned(), "Acme".to_owned()),
+                ("PAID".to_owned(), "false".to_owned()),
+            ],
+        );
+        assert!(parse_json_values("[]").is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+use std::process::{Command, Stdio};
+
+use anyhow::Result;
+
+/// The `stty` settings for reading the terminal a key at a time, without echoing them, where
+/// Ctrl-C is read as a key instead of exiting without restoring the terminal.
+const KEY_MODE: &[&str] = &["-icanon", "-echo", "-isig", "min", "1"];
+
+fn stty(arguments: &[&str]) -> io::Result<String> {
+    let output = Command::new("stty")
+        .args(arguments)
+        .stdin(Stdio::inherit())
+        .output()?;
+    if !output.status.success() {
+        return Err(io::Error::other("stty failed"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// The terminal's rows and columns, or 24 by 80 if they are not known.
+pub fn size() -> (usize, usize) {
+    stty(&["size"])
+        .ok()
+        .and_then(|size| {
+            let (rows, columns) = size.split_once(' ')?;
+            Some((rows.parse().ok()?, columns.parse().ok()?))
+        })
+        .filter(|&(rows, columns)| rows > 0 && columns > 0)
+        .unwrap_or((24, 80))
+}
+
+/// Reads the terminal a key at a time until it is dropped, when the terminal is restored.
+pub struct KeyMode {
+    /// The terminal's settings before, to restore
+    saved: String,
+}
+
+impl KeyMode {
+    pub fn new() -> Result<Self> {
+        let saved = stty(&["-g"])?;
+        stty(KEY_MODE)?;
+        Ok(Self { saved })
+    }
+
+    /// Run a program with the terminal as it was before, e.g. an editor.
+    pub fn suspended<T>(&self, run: impl FnOnce() -> Result<T>) -> Result<T> {
+        stty(&[&self.saved])?;
+        let result = run();
+        stty(KEY_MODE)?;
+        result
+    }
+}
+
+impl Drop for KeyMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    /// A letter pressed with Ctrl, other than those with their own keys like Tab
+    Ctrl(char),
+    Enter,
+    Tab,
+    Backspace,
+    Escape,
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+}
+
+/// The key of an escape sequence's final byte, and its parameter for sequences ending in `~`.
+fn sequence_key(parameter: &[u8], last: u8) -> Option<Key> {
+    Some(match (parameter, last) {
+        (_, b'A') => Key::Up,
+        (_, b'B') => Key::Down,
+        (_, b'C') => Key::Right,
+        (_, b'D') => Key::Left,
+        (_, b'H') | (b"1" | b"7", b'~') => Key::Home,
+        (_, b'F') | (b"4" | b"8", b'~') => Key::End,
+        (b"5", b'~') => Key::PageUp,
+        (b"6", b'~') => Key::PageDown,
+        _ => return None,
+    })
+}
+
+/// Parse the keys in bytes read from the terminal, returning how many bytes were parsed, which is
+/// all of them unless they end partway through a character.
+///
+/// A terminal sends each key's bytes at once, so an escape at the end of the bytes is the escape
+/// key rather than the start of a sequence.
+fn parse_keys(bytes: &[u8], keys: &mut VecDeque<Key>) -> usize {
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        index += 1;
+        let key = match byte {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            0x7f | 8 => Key::Backspace,
+            0x1b if matches!(bytes.get(index), Some(b'[' | b'O')) => {
+                let start = index + 1;
+                let Some(length) = bytes[start..]
+                    .iter()
+                    .position(|byte| (0x40..=0x7e).contains(byte))
+                else {
+                    return bytes.len();
+                };
+                index = start + length + 1;
+                match sequence_key(&bytes[start..start + length], bytes[start + length]) {
+                    Some(key) => key,
+                    None => continue,
+                }
+            }
+            0x1b => Key::Escape,
+            1..=26 => Key::Ctrl(char::from(b'a' + byte - 1)),
+            byte if byte < 0x20 => continue,
+            _ => {
+                let length = match byte {
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => 1,
+                };
+                let start = index - 1;
+                if start + length > bytes.len() {
+                    return start;
+                }
+                index = start + length;
+                match std::str::from_utf8(&bytes[start..index]) {
+                    Ok(text) => Key::Char(text.chars().next().unwrap_or_default()),
+                    Err(_) => continue,
+                }
+            }
+        };
+        keys.push_back(key);
+    }
+    index
+}
+
+/// The keys pressed in the terminal, while in [`KeyMode`].
+#[derive(Default)]
+pub struct Keys {
+    keys: VecDeque<Key>,
+    /// The bytes of a character that has not been read completely
+    pending: Vec<u8>,
+}
+
+impl Keys {
+    /// Read the next key, or `None` at the end of input.
+    pub fn read(&mut self) -> io::Result<Option<Key>> {
+        loop {
+            if let Some(key) = self.keys.pop_front() {
+                return Ok(Some(key));
+            }
+            let mut stdin = io::stdin().lock();
+            let buffer = stdin.fill_buf()?;
+            if buffer.is_empty() {
+                return Ok(None);
+            }
+            let length = buffer.len();
+            self.pending.extend_from_slice(buffer);
+            stdin.consume(length);
+            let parsed = parse_keys(&self.pending, &mut self.keys);
+            self.pending.drain(..parsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_keys() {
+        let mut keys = VecDeque::new();
+        let parsed = parse_keys(b"a\x1b[A\x1b[6~\x03\x7f\r\x1b", &mut keys);
+        assert_eq!(parsed, 12);
+        assert_eq!(
+            Vec::from(keys),
+            [
+                Key::Char('a'),
+                Key::Up,
+                Key::PageDown,
+                Key::Ctrl('c'),
+                Key::Backspace,
+                Key::Enter,
+                Key::Escape,
+            ]
+        );
+
+        // A character split between reads is parsed once the rest of it is read
+        let mut keys = VecDeque::new();
+        assert_eq!(parse_keys("x£".as_bytes()[..2].as_ref(), &mut keys), 1);
+        assert_eq!(Vec::from(keys), [Key::Char('x')]);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::quantity;
+use crate::soulver::Line;
+
+/// The input of the row added with the total of a sheet.
+const TOTAL_LABEL: &str = "Total";
+
+/// A row of dashes as wide as the widest input and output of `lines` and `extra`.
+fn separator(lines: &[Line], extra: &[Line]) -> Line {
+    let width = |f: fn(&Line) -> &str| {
+        lines
+            .iter()
+            .chain(extra)
+            .map(|line| f(line).chars().count())
+            .max()
+            .unwrap_or(0)
+    };
+    Line {
+        input: "-".repeat(width(|line| &line.input)),
+        output: "-".repeat(width(|line| &line.output)),
+    }
+}
+
+/// A row with the total of the numeric results of `lines` in the most common unit.
+fn total_row(lines: &[Line], label: String) -> Option<Line> {
+    Some(Line {
+        input: label,
+        output: quantity::sum_dominant_unit(lines.iter().map(|line| line.output.as_str()))?,
+    })
+}
+
+/// Append a separator and a row with the total of the numeric results in the most common unit.
+pub fn append_total(lines: &mut Vec<Line>) {
+    let Some(total) = total_row(lines, TOTAL_LABEL.to_owned()) else {
+        return;
+    };
+    let separator = separator(lines, std::slice::from_ref(&total));
+    lines.push(separator);
+    lines.push(total);
+}
+
+/// The text of a `#` heading line.
+pub fn heading(line: &Line) -> Option<&str> {
+    line.input
+        .strip_prefix('#')
+        .map(|heading| heading.trim_start_matches('#').trim())
+}
+
+/// Split lines into sections starting at each `#` heading, along with the heading's text.
+///
+/// Lines before the first heading are in a section without a heading.
+pub fn sections(lines: &[Line]) -> Vec<(Option<&str>, &[Line])> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut current_heading = None;
+    for (index, line) in lines.iter().enumerate() {
+        if let Some(heading) = heading(line) {
+            if index > start {
+                sections.push((current_heading, &lines[start..index]));
+            }
+            start = index;
+            current_heading = Some(heading);
+        }
+    }
+    if lines.len() > start {
+        sections.push((current_heading, &lines[start..]));
+    }
+    sections
+}
+
+/// Insert a row with the subtotal of each `#` heading's section after its last line, then append
+/// the total of the whole sheet.
+pub fn insert_subtotals(lines: &mut Vec<Line>) {
+    let total = total_row(lines, TOTAL_LABEL.to_owned());
+
+    let mut out = Vec::with_capacity(lines.len());
+    let mut section: Option<(String, Vec<Line>)> = None;
+    let flush = |out: &mut Vec<Line>, section: Option<(String, Vec<Line>)>| {
+        let Some((heading, mut section_lines)) = section else {
+            return;
+        };
+        let trailing_blank_lines = section_lines
+            .iter()
+            .rev()
+            .take_while(|line| line.input.trim().is_empty())
+            .count();
+        let trailing = section_lines.split_off(section_lines.len() - trailing_blank_lines);
+        let subtotal = total_row(&section_lines, format!("{heading} subtotal"));
+        out.extend(section_lines);
+        out.extend(subtotal);
+        out.extend(trailing);
+    };
+    for line in lines.drain(..) {
+        match heading(&line) {
+            Some(heading) => {
+                flush(&mut out, section.take());
+                section = Some((heading.to_owned(), vec![line]));
+            }
+            None => match &mut section {
+                Some((_, section_lines)) => section_lines.push(line),
+                None => out.push(line),
+            },
+        }
+    }
+    flush(&mut out, section);
+    *lines = out;
+
+    if let Some(total) = total {
+        lines.push(separator(lines, std::slice::from_ref(&total)));
+        lines.push(total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_total() {
+        let mut lines = vec![
+            Line::new("# Food", ""),
+            Line::new("£1.50 + £2", "£3.50"),
+            Line::new("£4", "£4.00"),
+        ];
+        append_total(&mut lines);
+        assert_eq!(
+            lines[3..],
+            [
+                Line::new("----------", "-----"),
+                Line::new("Total", "£7.50")
+            ],
+        )
+    }
+
+    #[test]
+    fn test_append_total_no_numbers() {
+        let mut lines = vec![Line::new("# Food", "")];
+        append_total(&mut lines);
+        assert_eq!(lines, [Line::new("# Food", "")])
+    }
+
+    #[test]
+    fn test_insert_subtotals() {
+        let mut lines = vec![
+            Line::new("Rent = £1000", "£1,000.00"),
+            Line::new("# Food", ""),
+            Line::new("£1.50 + £2", "£3.50"),
+            Line::new("£4", "£4.00"),
+            Line::new("", ""),
+            Line::new("## Notes", ""),
+            Line::new("// Nothing", ""),
+            Line::new("# Travel", ""),
+            Line::new("£20", "£20.00"),
+        ];
+        insert_subtotals(&mut lines);
+        assert_eq!(
+            lines,
+            [
+                Line::new("Rent = £1000", "£1,000.00"),
+                Line::new("# Food", ""),
+                Line::new("£1.50 + £2", "£3.50"),
+                Line::new("£4", "£4.00"),
+                Line::new("Food subtotal", "£7.50"),
+                Line::new("", ""),
+                Line::new("## Notes", ""),
+                Line::new("// Nothing", ""),
+                Line::new("# Travel", ""),
+                Line::new("£20", "£20.00"),
+                Line::new("Travel subtotal", "£20.00"),
+                Line::new("---------------", "---------"),
+                Line::new("Total", "£1,027.50"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_sections() {
+        let lines = [
+            Line::new("1", "1"),
+            Line::new("# Foo", ""),
+            Line::new("2", "2"),
+            Line::new("# Bar", ""),
+        ];
+        assert_eq!(
+            sections(&lines),
+            vec![
+                (None, &lines[0..1]),
+                (Some("Foo"), &lines[1..3]),
+                (Some("Bar"), &lines[3..4]),
+            ],
+        )
+    }
+}
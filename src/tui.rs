@@ -0,0 +1,685 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+
+use crate::files;
+use crate::soulver::{self, Line};
+use crate::terminal::{self, Key, KeyMode, Keys};
+use crate::totals;
+
+const SEPARATOR: &str = " │ ";
+
+/// Uses the terminal's alternate screen, without the cursor, until it is dropped.
+struct Screen;
+
+impl Screen {
+    fn new() -> io::Result<Self> {
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(b"\x1b[?1049h\x1b[?25l")?;
+        stdout.flush()?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(b"\x1b[?25h\x1b[?1049l");
+        let _ = stdout.flush();
+    }
+}
+
+/// Cut text to a width, ending with `…` if it is cut, or pad it with spaces to the width.
+fn fit(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    if length <= width {
+        return format!("{text}{}", " ".repeat(width - length));
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    if width > 0 {
+        cut.push('…');
+    }
+    cut
+}
+
+/// The next line after `from`, or before it if not `forward`, whose input or result contains the
+/// query, ignoring case and wrapping around.
+fn find_match(lines: &[Line], query: &str, from: usize, forward: bool) -> Option<usize> {
+    let query = query.to_lowercase();
+    let count = lines.len();
+    (1..=count)
+        .map(|offset| {
+            if forward {
+                (from + offset) % count
+            } else {
+                (from + count - offset % count) % count
+            }
+        })
+        .find(|&index| {
+            let line = &lines[index];
+            line.input.to_lowercase().contains(&query)
+                || line.output.to_lowercase().contains(&query)
+        })
+}
+
+/// The next heading after `from`, or the previous one before it if not `forward`.
+fn next_heading(lines: &[Line], from: usize, forward: bool) -> Option<usize> {
+    let is_heading = |index: &usize| totals::heading(&lines[*index]).is_some();
+    if forward {
+        (from + 1..lines.len()).find(is_heading)
+    } else {
+        (0..from).rev().find(is_heading)
+    }
+}
+
+/// A line being edited, with the cursor's position in characters.
+#[derive(Debug, PartialEq, Eq)]
+struct LineEditor {
+    text: String,
+    cursor: usize,
+}
+
+impl LineEditor {
+    /// Edit a line with the cursor at its end.
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            cursor: text.chars().count(),
+        }
+    }
+
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+
+    fn key(&mut self, key: Key) {
+        let length = self.text.chars().count();
+        match key {
+            Key::Char(c) => {
+                self.text.insert(self.byte_index(self.cursor), c);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.text.remove(self.byte_index(self.cursor));
+            }
+            Key::Ctrl('u') => {
+                self.text.replace_range(..self.byte_index(self.cursor), "");
+                self.cursor = 0;
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(length),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = length,
+            _ => {}
+        }
+    }
+
+    /// The text fitted to a width with the cursor in reverse video, scrolled to keep it shown.
+    fn render(&self, width: usize) -> String {
+        if width == 0 {
+            return String::new();
+        }
+        let start = (self.cursor + 1).saturating_sub(width);
+        let mut shown: Vec<char> = self.text.chars().skip(start).take(width).collect();
+        shown.resize(width, ' ');
+        let cursor = self.cursor - start;
+        let before: String = shown[..cursor].iter().collect();
+        let after: String = shown[cursor + 1..].iter().collect();
+        format!("{before}\x1b[7m{}\x1b[27m{after}", shown[cursor])
+    }
+}
+
+/// The inputs of a sheet and the selected line, before or after an edit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    inputs: Vec<String>,
+    cursor: usize,
+}
+
+/// The sheets before the edits made, to undo them, and after the edits undone, to redo them.
+#[derive(Debug, Default)]
+struct History {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+impl History {
+    /// Record the sheet before an edit, which can no longer redo the edits undone before it.
+    fn record(&mut self, before: Snapshot) {
+        self.undo.push(before);
+        self.redo.clear();
+    }
+
+    fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+/// A calculated sheet shown with its inputs and results side by side, which always scroll
+/// together.
+struct View<'a> {
+    path: &'a Path,
+    lines: Vec<Line>,
+    /// The selected line
+    cursor: usize,
+    /// The first line shown
+    top: usize,
+    /// The last query searched for
+    query: String,
+    /// What is shown in the status line until the next key, instead of the position
+    message: String,
+    history: History,
+    /// The inputs when the sheet was last read or saved, to tell if it has unsaved edits
+    saved: Vec<String>,
+}
+
+impl View<'_> {
+    fn move_to(&mut self, index: usize) {
+        self.cursor = index.min(self.lines.len().saturating_sub(1));
+    }
+
+    /// Scroll so the selected line is shown.
+    fn scroll(&mut self, height: usize) {
+        if self.cursor < self.top {
+            self.top = self.cursor;
+        } else if self.cursor >= self.top + height {
+            self.top = self.cursor + 1 - height;
+        }
+    }
+
+    fn search(&mut self, forward: bool) {
+        if self.query.is_empty() {
+            return;
+        }
+        match find_match(&self.lines, &self.query, self.cursor, forward) {
+            Some(index) => self.move_to(index),
+            None => self.message = format!("no line contains {:?}", self.query),
+        }
+    }
+
+    fn jump_to_heading(&mut self, forward: bool) {
+        match next_heading(&self.lines, self.cursor, forward) {
+            Some(index) => self.move_to(index),
+            None => self.message = "no more headings".to_owned(),
+        }
+    }
+
+    fn inputs(&self) -> Vec<String> {
+        self.lines.iter().map(|line| line.input.clone()).collect()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            inputs: self.inputs(),
+            cursor: self.cursor,
+        }
+    }
+
+    fn is_modified(&self) -> bool {
+        self.inputs() != self.saved
+    }
+
+    /// Show a sheet's inputs with their results calculated again, or without results if it can't
+    /// be calculated.
+    fn restore(&mut self, snapshot: Snapshot) {
+        let calculated = match soulver::calculate_lines(&snapshot.inputs.join("\n")) {
+            Ok(lines) => lines,
+            Err(error) => {
+                self.message = format!("error: {error:#}");
+                Vec::new()
+            }
+        };
+        self.lines = with_results(snapshot.inputs, &calculated);
+        self.move_to(snapshot.cursor);
+    }
+
+    /// Change the sheet's inputs, which can be undone, and calculate it again.
+    fn edit(&mut self, inputs: Vec<String>, cursor: usize) {
+        self.history.record(self.snapshot());
+        self.restore(Snapshot { inputs, cursor });
+    }
+
+    /// Change a line's input, or add it after the last line if there is no line there, e.g. in an
+    /// empty sheet.
+    fn set_input(&mut self, index: usize, input: String) {
+        let mut inputs = self.inputs();
+        let index = match inputs.get_mut(index) {
+            Some(existing) if *existing == input => return,
+            Some(existing) => {
+                *existing = input;
+                index
+            }
+            None => {
+                inputs.push(input);
+                inputs.len() - 1
+            }
+        };
+        self.edit(inputs, index);
+    }
+
+    fn insert_line(&mut self, index: usize) {
+        let mut inputs = self.inputs();
+        let index = index.min(inputs.len());
+        inputs.insert(index, String::new());
+        self.edit(inputs, index);
+    }
+
+    /// Delete the selected line, or empty it if it is the only one.
+    fn delete_line(&mut self) {
+        let mut inputs = self.inputs();
+        if inputs.len() > 1 {
+            inputs.remove(self.cursor);
+        } else {
+            inputs = vec![String::new()];
+        }
+        self.edit(inputs, self.cursor);
+    }
+
+    fn undo(&mut self) {
+        match self.history.undo(self.snapshot()) {
+            Some(snapshot) => self.restore(snapshot),
+            None => self.message = "nothing to undo".to_owned(),
+        }
+    }
+
+    fn redo(&mut self) {
+        match self.history.redo(self.snapshot()) {
+            Some(snapshot) => self.restore(snapshot),
+            None => self.message = "nothing to redo".to_owned(),
+        }
+    }
+
+    /// Read the sheet again after it was edited elsewhere, which can be undone to get back any
+    /// edits made here.
+    fn reload(&mut self) {
+        match calculate(self.path) {
+            Ok(lines) => {
+                self.history.record(self.snapshot());
+                self.lines = lines;
+                self.saved = self.inputs();
+                self.move_to(self.cursor);
+            }
+            Err(error) => self.message = format!("error: {error:#}"),
+        }
+    }
+
+    fn save(&mut self) {
+        let inputs = self.inputs();
+        match files::write_atomic(self.path, format!("{}\n", inputs.join("\n"))) {
+            Ok(()) => {
+                self.saved = inputs;
+                self.message = format!("Saved {}", self.path.display());
+            }
+            Err(error) => self.message = format!("error: {error:#}"),
+        }
+    }
+
+    /// Draw the lines shown, with the line being edited, and the status line, or the search being
+    /// typed.
+    fn render(&self, rows: usize, columns: usize, mode: &Mode) -> String {
+        let height = rows.saturating_sub(1);
+        let editor = match mode {
+            Mode::Edit(editor) => Some(editor),
+            _ => None,
+        };
+        let longest_input = self
+            .lines
+            .iter()
+            .map(|line| line.input.chars().count())
+            .chain(editor.map(|editor| editor.text.chars().count() + 1))
+            .max()
+            .unwrap_or(0);
+        let available = columns.saturating_sub(SEPARATOR.chars().count());
+        let input_width = longest_input.min(available * 2 / 3);
+        let result_width = available - input_width;
+
+        let mut screen = String::new();
+        for row in 0..height {
+            screen.push_str(&format!("\x1b[{};1H", row + 1));
+            let index = self.top + row;
+            if let Some(line) = self.lines.get(index) {
+                if let Some(editor) = editor.filter(|_| index == self.cursor) {
+                    screen.push_str(&format!(
+                        "{}{SEPARATOR}{}",
+                        editor.render(input_width),
+                        fit(&line.output, result_width),
+                    ));
+                    screen.push_str("\x1b[K");
+                    continue;
+                }
+                let text = format!(
+                    "{}{SEPARATOR}{}",
+                    fit(&line.input, input_width),
+                    fit(&line.output, result_width),
+                );
+                if index == self.cursor {
+                    screen.push_str(&format!("\x1b[7m{text}\x1b[0m"));
+                } else {
+                    screen.push_str(&text);
+                }
+            }
+            screen.push_str("\x1b[K");
+        }
+        let status = match mode {
+            Mode::Search(query) => format!("/{query}"),
+            Mode::Edit(_) => "Enter calculate  Esc cancel".to_owned(),
+            Mode::Browse if !self.message.is_empty() => self.message.clone(),
+            Mode::Browse => format!(
+                "{}{}  line {}/{}  ↑↓ move  [ ] headings  / search  n N next  e edit  o add  \
+                 d delete  u undo  ^R redo  w save  r reload  q quit",
+                self.path.display(),
+                if self.is_modified() {
+                    " [modified]"
+                } else {
+                    ""
+                },
+                self.cursor + 1,
+                self.lines.len(),
+            ),
+        };
+        screen.push_str(&format!(
+            "\x1b[{rows};1H\x1b[7m{}\x1b[0m",
+            fit(&status, columns)
+        ));
+        screen
+    }
+}
+
+/// What keys do.
+enum Mode {
+    Browse,
+    /// Typing a search
+    Search(String),
+    /// Editing the selected line
+    Edit(LineEditor),
+}
+
+/// Pair each input with its calculated result, keeping a line for every input, even the blank lines
+/// at the end that calculating drops.
+fn with_results(inputs: Vec<String>, calculated: &[Line]) -> Vec<Line> {
+    inputs
+        .into_iter()
+        .enumerate()
+        .map(|(index, input)| Line {
+            input,
+            output: calculated
+                .get(index)
+                .map(|line| line.output.clone())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn calculate(path: &Path) -> Result<Vec<Line>> {
+    let sheet =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let calculated = soulver::calculate_lines(&sheet)?;
+    Ok(with_results(
+        sheet.lines().map(str::to_owned).collect(),
+        &calculated,
+    ))
+}
+
+/// Show a calculated sheet full screen, with its inputs and results scrolling together, searching
+/// both with `/`, and jumping between headings with `[` and `]`.
+///
+/// Lines can be edited in place, calculating the sheet again as each edit is made, and edits can be
+/// undone and redone until the sheet is saved with `w`.
+pub fn tui(path: &Path) -> Result<()> {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        bail!("tui needs a terminal");
+    }
+    let lines = calculate(path)?;
+    let mut view = View {
+        path,
+        saved: lines.iter().map(|line| line.input.clone()).collect(),
+        lines,
+        cursor: 0,
+        top: 0,
+        query: String::new(),
+        message: String::new(),
+        history: History::default(),
+    };
+    let _key_mode = KeyMode::new()?;
+    let _screen = Screen::new()?;
+    let mut keys = Keys::default();
+    let mut mode = Mode::Browse;
+    // Whether quitting was refused because of unsaved edits, so quitting again discards them
+    let mut quitting = false;
+    loop {
+        let (rows, columns) = terminal::size();
+        let height = rows.saturating_sub(1).max(1);
+        view.scroll(height);
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(view.render(rows, columns, &mode).as_bytes())?;
+        stdout.flush()?;
+        drop(stdout);
+
+        let Some(key) = keys.read()? else {
+            return Ok(());
+        };
+        match &mut mode {
+            Mode::Browse => {}
+            Mode::Search(query) => {
+                match key {
+                    Key::Enter => {
+                        view.query = std::mem::take(query);
+                        mode = Mode::Browse;
+                        view.search(true);
+                    }
+                    Key::Escape | Key::Ctrl('c') => mode = Mode::Browse,
+                    Key::Backspace => {
+                        query.pop();
+                    }
+                    Key::Char(c) => query.push(c),
+                    _ => {}
+                }
+                continue;
+            }
+            Mode::Edit(editor) => {
+                match key {
+                    Key::Enter => {
+                        let input = std::mem::take(&mut editor.text);
+                        mode = Mode::Browse;
+                        view.message.clear();
+                        view.set_input(view.cursor, input);
+                    }
+                    Key::Escape | Key::Ctrl('c') => mode = Mode::Browse,
+                    key => editor.key(key),
+                }
+                continue;
+            }
+        }
+        view.message.clear();
+        let quit_again = std::mem::take(&mut quitting);
+        match key {
+            Key::Char('q') | Key::Ctrl('c') if quit_again || !view.is_modified() => return Ok(()),
+            Key::Char('q') | Key::Ctrl('c') => {
+                view.message =
+                    "the sheet has unsaved edits, w saves them or q quits without saving"
+                        .to_owned();
+                quitting = true;
+            }
+            Key::Char('j') | Key::Down => view.move_to(view.cursor + 1),
+            Key::Char('k') | Key::Up => view.move_to(view.cursor.saturating_sub(1)),
+            Key::Char(' ') | Key::PageDown | Key::Ctrl('f') => view.move_to(view.cursor + height),
+            Key::Char('b') | Key::PageUp | Key::Ctrl('b') => {
+                view.move_to(view.cursor.saturating_sub(height));
+            }
+            Key::Char('g') | Key::Home => view.move_to(0),
+            Key::Char('G') | Key::End => view.move_to(usize::MAX),
+            Key::Char(']') => view.jump_to_heading(true),
+            Key::Char('[') => view.jump_to_heading(false),
+            Key::Char('/') => mode = Mode::Search(String::new()),
+            Key::Char('n') => view.search(true),
+            Key::Char('N') => view.search(false),
+            Key::Char('e') | Key::Enter => {
+                let input = view.lines.get(view.cursor).map_or("", |line| &line.input);
+                mode = Mode::Edit(LineEditor::new(input));
+            }
+            Key::Char('o') => {
+                view.insert_line(view.cursor + 1);
+                mode = Mode::Edit(LineEditor::new(""));
+            }
+            Key::Char('O') => {
+                view.insert_line(view.cursor);
+                mode = Mode::Edit(LineEditor::new(""));
+            }
+            Key::Char('d') => view.delete_line(),
+            Key::Char('u') => view.undo(),
+            Key::Ctrl('r') => view.redo(),
+            Key::Char('w') => view.save(),
+            Key::Char('r') => view.reload(),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(inputs: &[&str]) -> Vec<Line> {
+        inputs
+            .iter()
+            .map(|input| Line {
+                input: (*input).to_owned(),
+                output: if input.starts_with('#') {
+                    String::new()
+                } else {
+                    input.to_uppercase()
+                },
+            })
+            .collect()
+    }
+
+    fn view_of(inputs: &[&str]) -> View<'static> {
+        let lines = lines(inputs);
+        View {
+            path: Path::new("test.soulver"),
+            saved: lines.iter().map(|line| line.input.clone()).collect(),
+            lines,
+            cursor: 0,
+            top: 0,
+            query: String::new(),
+            message: String::new(),
+            history: History::default(),
+        }
+    }
+
+    #[test]
+    fn test_append_line() {
+        let mut view = view_of(&["a = 1", "b = 2"]);
+        view.move_to(1);
+        view.insert_line(view.cursor + 1);
+        assert_eq!(view.cursor, 2);
+        view.set_input(view.cursor, "c = 3".to_owned());
+        assert_eq!(view.inputs(), ["a = 1", "b = 2", "c = 3"]);
+        view.undo();
+        assert_eq!(view.inputs(), ["a = 1", "b = 2", ""]);
+    }
+
+    #[test]
+    fn test_edit_empty_sheet() {
+        let mut view = view_of(&[]);
+        view.insert_line(view.cursor + 1);
+        view.set_input(view.cursor, "c = 3".to_owned());
+        assert_eq!(view.inputs(), ["c = 3"]);
+
+        let mut view = view_of(&[]);
+        view.set_input(0, "c = 3".to_owned());
+        assert_eq!(view.inputs(), ["c = 3"]);
+    }
+
+    #[test]
+    fn test_fit() {
+        assert_eq!(fit("£10", 5), "£10  ");
+        assert_eq!(fit("Monthly rent", 6), "Month…");
+        assert_eq!(fit("a", 0), "");
+    }
+
+    #[test]
+    fn test_line_editor() {
+        let mut editor = LineEditor::new("rent * 2");
+        editor.key(Key::Home);
+        editor.key(Key::Char('£'));
+        editor.key(Key::Right);
+        editor.key(Key::Backspace);
+        assert_eq!(
+            editor,
+            LineEditor {
+                text: "£ent * 2".to_owned(),
+                cursor: 1
+            }
+        );
+        editor.key(Key::Right);
+        editor.key(Key::Ctrl('u'));
+        assert_eq!(
+            editor,
+            LineEditor {
+                text: "nt * 2".to_owned(),
+                cursor: 0
+            }
+        );
+
+        // The cursor stays shown when the line is wider than its cell
+        let editor = LineEditor::new("salary");
+        assert_eq!(editor.render(4), "ary\x1b[7m \x1b[27m");
+        assert_eq!(editor.render(8), "salary\x1b[7m \x1b[27m ");
+    }
+
+    #[test]
+    fn test_history() {
+        let snapshot = |input: &str| Snapshot {
+            inputs: vec![input.to_owned()],
+            cursor: 0,
+        };
+        let mut history = History::default();
+        history.record(snapshot("a"));
+        history.record(snapshot("b"));
+        assert_eq!(history.undo(snapshot("c")), Some(snapshot("b")));
+        assert_eq!(history.undo(snapshot("b")), Some(snapshot("a")));
+        assert_eq!(history.undo(snapshot("a")), None);
+        assert_eq!(history.redo(snapshot("a")), Some(snapshot("b")));
+
+        // An edit after undoing can't redo what was undone
+        history.record(snapshot("b"));
+        assert_eq!(history.redo(snapshot("d")), None);
+        assert_eq!(history.undo(snapshot("d")), Some(snapshot("b")));
+    }
+
+    #[test]
+    fn test_find_match() {
+        let lines = lines(&["rent = 1", "bills = 2", "rent * 2"]);
+        assert_eq!(find_match(&lines, "RENT", 0, true), Some(2));
+        assert_eq!(find_match(&lines, "rent", 2, true), Some(0));
+        assert_eq!(find_match(&lines, "rent", 0, false), Some(2));
+        // Results are searched too
+        assert_eq!(find_match(&lines, "BILLS", 0, true), Some(1));
+        assert_eq!(find_match(&lines, "food", 0, true), None);
+        assert_eq!(find_match(&[], "rent", 0, true), None);
+    }
+
+    #[test]
+    fn test_next_heading() {
+        let lines = lines(&["# Income", "a", "# Costs", "b"]);
+        assert_eq!(next_heading(&lines, 0, true), Some(2));
+        assert_eq!(next_heading(&lines, 2, true), None);
+        assert_eq!(next_heading(&lines, 3, false), Some(2));
+        assert_eq!(next_heading(&lines, 2, false), Some(0));
+    }
+}
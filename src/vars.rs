@@ -0,0 +1,511 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{Error, Result, bail};
+
+use crate::files;
+use crate::json;
+use crate::line_range::LineRange;
+use crate::soulver::{self, Line};
+
+/// Whether a character can be part of a variable name, other than the spaces between words.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Parse a `Name = expression` variable assignment, returning the name and expression.
+///
+/// Names can contain multiple words, e.g. `Monthly rent = $1,000`.
+pub fn parse_assignment(line: &str) -> Option<(&str, &str)> {
+    if soulver::is_comment(line.trim_start()) {
+        return None;
+    }
+    let (name, expression) = line.split_once('=')?;
+    let name = name.trim();
+    if expression.starts_with('=')
+        || !name.starts_with(|c: char| c.is_alphabetic())
+        || !name.chars().all(|c| is_name_char(c) || c == ' ')
+    {
+        return None;
+    }
+    Some((name, expression.trim()))
+}
+
+/// Parse a `Label: expression` line, returning the label and expression.
+///
+/// Labels start with a letter and the colon must be followed by whitespace, so times like `9:30`
+/// and URLs are not labels.
+pub fn parse_label(line: &str) -> Option<(&str, &str)> {
+    let trimmed = line.trim_start();
+    if soulver::is_comment(trimmed) {
+        return None;
+    }
+    let (label, expression) = trimmed.split_once(':')?;
+    let label = label.trim_end();
+    if !label.starts_with(|c: char| c.is_alphabetic())
+        || label.contains('=')
+        || !expression.is_empty() && !expression.starts_with(char::is_whitespace)
+    {
+        return None;
+    }
+    Some((label, expression.trim()))
+}
+
+/// Find the byte offsets of whole-word occurrences of a variable name in text.
+pub fn find_name(text: &str, name: &str) -> Vec<usize> {
+    text.match_indices(name)
+        .filter(|(index, _)| {
+            let before = text[..*index].chars().next_back();
+            let after = text[index + name.len()..].chars().next();
+            !before.is_some_and(is_name_char) && !after.is_some_and(is_name_char)
+        })
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// The index and name of each variable assignment.
+fn assignments<'a>(lines: &[&'a str]) -> Vec<(usize, &'a str)> {
+    lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| Some((index, parse_assignment(line)?.0)))
+        .collect()
+}
+
+/// The assignments used by a line, which are the most recent assignments before `before` of the
+/// variables it references.
+fn used_assignments<'a>(
+    line: &str,
+    assignments: &[(usize, &'a str)],
+    before: usize,
+) -> Vec<(usize, &'a str)> {
+    let expression = match parse_assignment(line) {
+        Some((_, expression)) => expression,
+        None => line,
+    };
+    let mut used: Vec<(usize, &str)> = Vec::new();
+    for &(_, name) in assignments {
+        if used.iter().any(|(_, other)| *other == name) || find_name(expression, name).is_empty() {
+            continue;
+        }
+        if let Some(&assignment) = assignments
+            .iter()
+            .rev()
+            .find(|(definition, other)| *other == name && *definition < before)
+        {
+            used.push(assignment);
+        }
+    }
+    used
+}
+
+/// The indices of the lines before `range` that assign variables the range depends on, directly
+/// or through other variables, in order.
+pub fn dependencies(lines: &[&str], range: LineRange) -> Vec<usize> {
+    let assignments = assignments(lines);
+    let start = range.start - 1;
+    let mut needed = BTreeSet::new();
+    let mut to_visit: Vec<usize> = (start..range.end.min(lines.len())).collect();
+    while let Some(index) = to_visit.pop() {
+        for (definition, _) in used_assignments(lines[index], &assignments, index.min(start)) {
+            if needed.insert(definition) {
+                to_visit.push(definition);
+            }
+        }
+    }
+    needed.into_iter().collect()
+}
+
+/// A line that references a variable.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Reference<'a> {
+    pub name: &'a str,
+    /// The 1-based line number of the assignment the line uses
+    pub definition_line_number: usize,
+    pub line_number: usize,
+}
+
+/// Every reference to a variable in a sheet, in order of the lines referencing them.
+pub fn references<'a>(lines: &[&'a str]) -> Vec<Reference<'a>> {
+    let assignments = assignments(lines);
+    lines
+        .iter()
+        .enumerate()
+        .flat_map(|(index, line)| {
+            used_assignments(line, &assignments, index).into_iter().map(
+                move |(definition, name)| Reference {
+                    name,
+                    definition_line_number: definition + 1,
+                    line_number: index + 1,
+                },
+            )
+        })
+        .collect()
+}
+
+/// Format the lines referencing each variable assignment as a tree.
+pub fn dependency_tree(lines: &[&str]) -> String {
+    let references = references(lines);
+    let mut out = Vec::new();
+    for (index, name) in assignments(lines) {
+        out.push(format!("{name} (line {})", index + 1));
+        let used_by: Vec<&Reference> = references
+            .iter()
+            .filter(|reference| reference.definition_line_number == index + 1)
+            .collect();
+        for (position, reference) in used_by.iter().enumerate() {
+            let branch = if position + 1 == used_by.len() {
+                "└──"
+            } else {
+                "├──"
+            };
+            out.push(format!(
+                "{branch} line {}: {}",
+                reference.line_number,
+                lines[reference.line_number - 1].trim(),
+            ));
+        }
+    }
+    out.join("\n")
+}
+
+/// Quote a string for Graphviz DOT.
+fn dot_quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Format the references to variables as a Graphviz DOT graph from each assignment to the lines
+/// using it.
+pub fn dependency_dot(lines: &[&str]) -> String {
+    let references = references(lines);
+    let mut nodes = BTreeSet::new();
+    for reference in &references {
+        nodes.insert(reference.definition_line_number);
+        nodes.insert(reference.line_number);
+    }
+
+    let mut out = vec!["digraph dependencies {".to_owned()];
+    for line_number in nodes {
+        out.push(format!(
+            "    line{line_number} [label={}];",
+            dot_quote(&format!("{line_number}: {}", lines[line_number - 1].trim())),
+        ));
+    }
+    for reference in &references {
+        out.push(format!(
+            "    line{} -> line{} [label={}];",
+            reference.definition_line_number,
+            reference.line_number,
+            dot_quote(reference.name),
+        ));
+    }
+    out.push("}".to_owned());
+    out.join("\n")
+}
+
+/// Whether text is a valid variable name.
+fn is_valid_name(name: &str) -> bool {
+    parse_assignment(&format!("{name} = 1")) == Some((name, "1")) && name.trim() == name
+}
+
+/// A variable assignment given as `NAME=VALUE`, e.g. on the command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub name: String,
+    pub value: String,
+}
+
+impl Definition {
+    /// The definition as an assignment line of a sheet.
+    pub fn to_line(&self) -> String {
+        format!("{} = {}", self.name, self.value)
+    }
+}
+
+impl FromStr for Definition {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let Some((name, value)) = s.split_once('=') else {
+            bail!("expected NAME=VALUE, got {s:?}");
+        };
+        let name = name.trim();
+        if !is_valid_name(name) {
+            bail!("{name:?} is not a valid variable name");
+        }
+        Ok(Self {
+            name: name.to_owned(),
+            value: value.trim().to_owned(),
+        })
+    }
+}
+
+/// Rename whole-word occurrences of a variable outside of comments and headings, returning the
+/// renamed sheet and the number of occurrences renamed.
+pub fn rename(sheet: &str, old: &str, new: &str) -> Result<(String, usize)> {
+    if !is_valid_name(new) {
+        bail!("{new:?} is not a valid variable name");
+    }
+    let lines: Vec<&str> = sheet.lines().collect();
+    if !assignments(&lines).iter().any(|(_, name)| *name == old) {
+        bail!("{old:?} is not assigned in the sheet");
+    }
+    if assignments(&lines).iter().any(|(_, name)| *name == new) {
+        bail!("{new:?} is already assigned in the sheet");
+    }
+
+    let mut renamed = String::with_capacity(sheet.len());
+    let mut count = 0;
+    for line in sheet.split_inclusive('\n') {
+        if soulver::is_comment(line.trim_start()) {
+            renamed.push_str(line);
+            continue;
+        }
+        let mut last = 0;
+        for index in find_name(line, old) {
+            renamed.push_str(&line[last..index]);
+            renamed.push_str(new);
+            last = index + old.len();
+            count += 1;
+        }
+        renamed.push_str(&line[last..]);
+    }
+    Ok((renamed, count))
+}
+
+/// Rename a variable in a sheet file, only writing it if every result is unchanged afterwards.
+///
+/// Returns the number of occurrences renamed.
+pub fn rename_in_file(file: &Path, old: &str, new: &str) -> Result<usize> {
+    let sheet = fs::read_to_string(file)?;
+    let (renamed, count) = rename(&sheet, old, new)?;
+    let before = soulver::calculate_lines(&sheet)?;
+    let after = soulver::calculate_lines(&renamed)?;
+    if let Some((index, (before, after))) = before
+        .iter()
+        .zip(&after)
+        .enumerate()
+        .find(|(_, (before, after))| before.output != after.output)
+    {
+        bail!(
+            "renaming changed the result of line {} from {:?} to {:?}",
+            index + 1,
+            before.output,
+            after.output,
+        );
+    }
+    files::write_atomic(file, &renamed)?;
+    Ok(count)
+}
+
+/// A variable assigned in a sheet, along with its final value.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Variable {
+    pub name: String,
+    /// The 1-based line number of the last assignment
+    pub line_number: usize,
+    pub value: String,
+}
+
+impl Variable {
+    pub fn to_json(&self) -> json::Value {
+        json::Value::object([
+            ("name", json::Value::from(self.name.as_str())),
+            ("line", json::Value::from(self.line_number)),
+            ("value", json::Value::from(self.value.as_str())),
+        ])
+    }
+}
+
+/// The variables assigned in a calculated sheet in the order they are first assigned, each with
+/// the result of its last assignment.
+pub fn variables(lines: &[Line]) -> Vec<Variable> {
+    let mut variables: Vec<Variable> = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let Some((name, _)) = parse_assignment(&line.input) else {
+            continue;
+        };
+        let variable = Variable {
+            name: name.to_owned(),
+            line_number: index + 1,
+            value: line.output.clone(),
+        };
+        match variables.iter_mut().find(|other| other.name == name) {
+            Some(existing) => *existing = variable,
+            None => variables.push(variable),
+        }
+    }
+    variables
+}
+
+/// Format variables as aligned `name value` lines.
+pub fn to_text(variables: &[Variable]) -> String {
+    let width = variables
+        .iter()
+        .map(|variable| variable.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    variables
+        .iter()
+        .map(|variable| format!("{:<width$} {}", variable.name, variable.value))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(parse_assignment("Foo = 1 + 2"), Some(("Foo", "1 + 2")));
+        assert_eq!(
+            parse_assignment("Monthly rent = $1,000"),
+            Some(("Monthly rent", "$1,000")),
+        );
+    }
+
+    #[test]
+    fn test_parse_assignment_none() {
+        assert_eq!(parse_assignment("1 + 2"), None);
+        assert_eq!(parse_assignment("Foo == 1"), None);
+        assert_eq!(parse_assignment("# Foo = 1"), None);
+        assert_eq!(parse_assignment("Rent: 1 = 1"), None);
+        assert_eq!(parse_assignment("= 1"), None);
+    }
+
+    #[test]
+    fn test_parse_label() {
+        assert_eq!(
+            parse_label("Rent: 1200 + 150"),
+            Some(("Rent", "1200 + 150"))
+        );
+        assert_eq!(parse_label("  Phone bill:"), Some(("Phone bill", "")));
+        assert_eq!(parse_label("9:30 + 1 hour"), None);
+        assert_eq!(parse_label("Meeting at 9:30"), None);
+        assert_eq!(parse_label("# Notes: 1 + 2"), None);
+        assert_eq!(parse_label("Rent = 1: 2"), None);
+    }
+
+    #[test]
+    fn test_references() {
+        let lines = ["Price = 2", "Tax = Price * 0.2", "Price + Tax + Price"];
+        assert_eq!(
+            references(&lines),
+            vec![
+                Reference {
+                    name: "Price",
+                    definition_line_number: 1,
+                    line_number: 2,
+                },
+                Reference {
+                    name: "Price",
+                    definition_line_number: 1,
+                    line_number: 3,
+                },
+                Reference {
+                    name: "Tax",
+                    definition_line_number: 2,
+                    line_number: 3,
+                },
+            ],
+        )
+    }
+
+    #[test]
+    fn test_dependency_tree() {
+        let lines = ["Price = 2", "Tax = Price * 0.2", "Price + Tax"];
+        assert_eq!(
+            dependency_tree(&lines),
+            "Price (line 1)\n├── line 2: Tax = Price * 0.2\n└── line 3: Price + Tax\nTax (line 2)\n└── line 3: Price + Tax",
+        )
+    }
+
+    #[test]
+    fn test_dependency_dot() {
+        let lines = ["Name = \"a\"", "Name"];
+        assert_eq!(
+            dependency_dot(&lines),
+            "digraph dependencies {\n    line1 [label=\"1: Name = \\\"a\\\"\"];\n    line2 [label=\"2: Name\"];\n    line1 -> line2 [label=\"Name\"];\n}",
+        )
+    }
+
+    #[test]
+    fn test_variables() {
+        let line = |input: &str, output: &str| Line {
+            input: input.to_owned(),
+            output: output.to_owned(),
+        };
+        let variables = variables(&[
+            line("Foo = 1", "1"),
+            line("Monthly rent = £1000", "£1,000.00"),
+            line("Foo = 2", "2"),
+        ]);
+        assert_eq!(
+            to_text(&variables),
+            "Foo          2\nMonthly rent £1,000.00",
+        );
+        assert_eq!(variables[0].line_number, 3);
+    }
+
+    #[test]
+    fn test_parse_definition() {
+        let definition: Definition = "Tax rate=4.5%".parse().unwrap();
+        assert_eq!(definition.to_line(), "Tax rate = 4.5%");
+        assert!("rate".parse::<Definition>().is_err());
+        assert!("1rate=2".parse::<Definition>().is_err());
+    }
+
+    #[test]
+    fn test_rename() {
+        let sheet = "Foo = 1\n# Foo\nFoobar = Foo + 1\nFoo_2 = Foo\n";
+        assert_eq!(
+            rename(sheet, "Foo", "Total cost").unwrap(),
+            (
+                "Total cost = 1\n# Foo\nFoobar = Total cost + 1\nFoo_2 = Total cost\n".to_owned(),
+                3,
+            ),
+        )
+    }
+
+    #[test]
+    fn test_rename_invalid() {
+        assert!(rename("Foo = 1", "Foo", "1a").is_err());
+        assert!(rename("Foo = 1", "Bar", "Baz").is_err());
+        assert!(rename("Foo = 1\nBar = 2", "Foo", "Bar").is_err());
+    }
+
+    #[test]
+    fn test_find_name() {
+        assert_eq!(find_name("Foo + Foobar + Foo", "Foo"), vec![0, 15]);
+        assert_eq!(find_name("rent + Monthly rent", "Monthly rent"), vec![7]);
+        assert_eq!(find_name("Foo_1", "Foo"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let lines = [
+            "Rate = 5%",
+            "Unused = 2",
+            "Price = $100",
+            "Tax = Price * Rate",
+            "# Range",
+            "Price + Tax",
+        ];
+        assert_eq!(
+            dependencies(&lines, LineRange { start: 5, end: 6 }),
+            vec![0, 2, 3],
+        )
+    }
+
+    #[test]
+    fn test_dependencies_latest_definition() {
+        let lines = ["Foo = 1", "Bar = 2", "Foo = Bar", "Foo + 1"];
+        assert_eq!(
+            dependencies(&lines, LineRange { start: 4, end: 4 }),
+            vec![1, 2],
+        )
+    }
+}
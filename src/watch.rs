@@ -0,0 +1,350 @@
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use anyhow::{Context, Result, bail};
+use clap::ValueEnum;
+
+use crate::files;
+use crate::output;
+use crate::soulver::{self, Line};
+use crate::task::{self, Task};
+
+/// How often the sheet is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How results that changed since the sheet was last calculated are highlighted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Highlight {
+    /// In colour when stdout is a terminal and `NO_COLOR` is not set
+    Auto,
+    /// In colour
+    Color,
+    /// With a `*` after them
+    Marker,
+    /// Not at all
+    Never,
+}
+
+impl Highlight {
+    /// Choose whether to highlight with colour for `auto`.
+    fn resolve(self) -> Self {
+        match self {
+            Self::Auto
+                if io::stdout().is_terminal()
+                    && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) =>
+            {
+                Self::Color
+            }
+            Self::Auto => Self::Never,
+            highlight => highlight,
+        }
+    }
+}
+
+/// Which lines have a result that changed since the previous calculation.
+///
+/// Lines are matched with the previous lines with the same input, so inserting or removing a line
+/// does not count as every line after it changing, while editing a line does count as its result
+/// changing.
+fn changed_lines(previous: &[Line], lines: &[Line]) -> Vec<bool> {
+    let mut previous_outputs: HashMap<&str, VecDeque<&str>> = HashMap::new();
+    for line in previous {
+        previous_outputs
+            .entry(&line.input)
+            .or_default()
+            .push_back(&line.output);
+    }
+    lines
+        .iter()
+        .map(|line| {
+            let previous_output = previous_outputs
+                .get_mut(line.input.as_str())
+                .and_then(VecDeque::pop_front);
+            !line.output.is_empty() && previous_output != Some(line.output.as_str())
+        })
+        .collect()
+}
+
+/// Zip the lines, highlighting the results that changed since the previous calculation.
+fn render(lines: &[Line], previous: Option<&[Line]>, highlight: Highlight) -> String {
+    let changed = match previous {
+        Some(previous) if highlight != Highlight::Never => changed_lines(previous, lines),
+        _ => return soulver::zip(lines),
+    };
+    if highlight == Highlight::Marker {
+        let marked: Vec<Line> = lines
+            .iter()
+            .zip(&changed)
+            .map(|(line, &changed)| Line {
+                input: line.input.clone(),
+                output: if changed {
+                    format!("{} *", line.output)
+                } else {
+                    line.output.clone()
+                },
+            })
+            .collect();
+        return soulver::zip(&marked);
+    }
+    // Results start after the longest input and ` | `
+    let result_column = lines
+        .iter()
+        .map(|line| line.input.chars().count())
+        .max()
+        .unwrap_or(0)
+        + " | ".len();
+    soulver::zip(lines)
+        .split('\n')
+        .zip(&changed)
+        .map(
+            |(zipped, &changed)| match zipped.char_indices().nth(result_column) {
+                Some((index, _)) if changed => {
+                    format!("{}\x1b[1;33m{}\x1b[0m", &zipped[..index], &zipped[index..])
+                }
+                _ => zipped.to_owned(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The sheet's metadata when it was last checked, which changes whenever it is saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Stamp {
+    modified: Option<SystemTime>,
+    length: u64,
+}
+
+/// The sheet's stamp, or `None` while it does not exist, e.g. during an editor's atomic save.
+fn stamp(path: &Path) -> Option<Stamp> {
+    let metadata = fs::metadata(path).ok()?;
+    Some(Stamp {
+        modified: metadata.modified().ok(),
+        length: metadata.len(),
+    })
+}
+
+/// Decides when to calculate the sheet, coalescing changes until none have been seen for the
+/// debounce, and only calculating it once at a time.
+#[derive(Debug)]
+struct Debouncer {
+    debounce: Duration,
+    /// When the sheet can be calculated, if it has changed since it last started being
+    ready_at: Option<Instant>,
+    running: bool,
+}
+
+impl Debouncer {
+    /// A debouncer that calculates the sheet straight away.
+    fn new(debounce: Duration, now: Instant) -> Self {
+        Self {
+            debounce,
+            ready_at: Some(now),
+            running: false,
+        }
+    }
+
+    fn changed(&mut self, now: Instant) {
+        self.ready_at = Some(now + self.debounce);
+    }
+
+    /// Whether to start calculating the sheet, once it has not changed for the debounce and no
+    /// other calculation is running.
+    fn start(&mut self, now: Instant) -> bool {
+        if self.running || self.ready_at.is_none_or(|ready_at| now < ready_at) {
+            return false;
+        }
+        self.ready_at = None;
+        self.running = true;
+        true
+    }
+
+    /// Finish calculating the sheet, returning whether the result is current, and was not
+    /// superseded by the sheet changing while it was calculated.
+    fn finish(&mut self) -> bool {
+        self.running = false;
+        self.ready_at.is_none()
+    }
+}
+
+fn calculate(path: &Path) -> Result<Vec<Line>> {
+    let sheet =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    soulver::calculate_lines(&sheet)
+}
+
+/// Print the result of a calculation all at once, clearing the terminal first, or after a blank
+/// line separating it from the previous result when stdout is not a terminal.
+fn show(path: &Path, result: Result<String>, first: bool) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    if stdout.is_terminal() {
+        stdout.write_all(b"\x1b[2J\x1b[H")?;
+    } else if !first {
+        stdout.write_all(b"\n")?;
+    }
+    match result {
+        Ok(text) => {
+            if !output::is_output_silenced() {
+                stdout.write_all(format!("{text}\n").as_bytes())?;
+            }
+        }
+        Err(error) => output::log_error(Some(&path.display().to_string()), &error),
+    }
+    stdout.flush()
+}
+
+/// Replace the output file with the result of a calculation, keeping the last result if the sheet
+/// could not be calculated.
+fn write(path: &Path, output_path: &Path, result: Result<Vec<Line>>) -> Result<()> {
+    match result {
+        Ok(lines) => files::write_atomic(output_path, format!("{}\n", soulver::zip(&lines)))?,
+        Err(error) => output::log_error(Some(&path.display().to_string()), &error),
+    }
+    Ok(())
+}
+
+/// Calculate a sheet and print its zipped results, then again whenever it is saved, until
+/// interrupted.
+///
+/// Bursts of changes are calculated once they stop for the debounce, and a result is discarded
+/// if the sheet changed while it was being calculated, so only the latest is printed. Results that
+/// changed since the previous one are highlighted, unless they are written to an output file
+/// instead, which is replaced atomically so other programs never read half a result.
+pub fn watch(
+    path: &Path,
+    debounce: Duration,
+    highlight: Highlight,
+    output_path: Option<&Path>,
+) -> Result<()> {
+    if let Some(output_path) = output_path
+        && fs::canonicalize(output_path).ok() == Some(fs::canonicalize(path)?)
+    {
+        bail!("the output file cannot be the sheet being watched");
+    }
+    let highlight = highlight.resolve();
+    let mut running: Option<Task<Vec<Line>>> = None;
+    let mut previous: Option<Vec<Line>> = None;
+    let mut debouncer = Debouncer::new(debounce, Instant::now());
+    let mut last_stamp = stamp(path);
+    let mut first = true;
+    loop {
+        let result = match &running {
+            Some(task) => task.wait(POLL_INTERVAL),
+            None => {
+                thread::sleep(POLL_INTERVAL);
+                None
+            }
+        };
+        if result.is_some() {
+            running = None;
+        }
+        match result {
+            Some(result) if debouncer.finish() => match output_path {
+                Some(output_path) => write(path, output_path, result)?,
+                None => {
+                    let result = result.map(|lines| {
+                        let text = render(&lines, previous.as_deref(), highlight);
+                        previous = Some(lines);
+                        text
+                    });
+                    show(path, result, first)?;
+                    first = false;
+                }
+            },
+            Some(_) => output::trace(
+                "watch_superseded",
+                "watch: discarding a result superseded by a newer change",
+                vec![],
+            ),
+            None => {}
+        }
+        let now = Instant::now();
+        let current_stamp = stamp(path);
+        if current_stamp != last_stamp {
+            last_stamp = current_stamp;
+            debouncer.changed(now);
+        }
+        if debouncer.start(now) {
+            let path = path.to_owned();
+            running = Some(task::spawn(move || calculate(&path)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[(&str, &str)]) -> Vec<Line> {
+        lines
+            .iter()
+            .map(|(input, output)| Line {
+                input: (*input).to_owned(),
+                output: (*output).to_owned(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_changed_lines() {
+        let previous = lines(&[("Rent = 1000", "1,000"), ("Rent * 12", "12,000"), ("", "")]);
+        let current = lines(&[
+            ("# Costs", ""),
+            ("Rent = 1200", "1,200"),
+            ("Rent * 12", "14,400"),
+            ("", ""),
+        ]);
+        assert_eq!(
+            changed_lines(&previous, &current),
+            [false, true, true, false]
+        );
+        assert_eq!(changed_lines(&current, &current), [false; 4]);
+    }
+
+    #[test]
+    fn test_render() {
+        let previous = lines(&[("a = 1", "1"), ("a + 1", "2")]);
+        let current = lines(&[("a = 1", "1"), ("a + 10", "11")]);
+        assert_eq!(
+            render(&current, Some(&previous), Highlight::Marker),
+            "a = 1  | 1\na + 10 | 11 *"
+        );
+        assert_eq!(
+            render(&current, Some(&previous), Highlight::Color),
+            "a = 1  | 1\na + 10 | \x1b[1;33m11\x1b[0m"
+        );
+        assert_eq!(
+            render(&current, None, Highlight::Color),
+            "a = 1  | 1\na + 10 | 11"
+        );
+    }
+
+    #[test]
+    fn test_debouncer() {
+        let debounce = Duration::from_millis(100);
+        let start = Instant::now();
+        let at = |milliseconds| start + Duration::from_millis(milliseconds);
+        let mut debouncer = Debouncer::new(debounce, start);
+        assert!(debouncer.start(start));
+        assert!(debouncer.finish());
+
+        // A burst of changes is coalesced into one calculation
+        debouncer.changed(at(10));
+        debouncer.changed(at(60));
+        assert!(!debouncer.start(at(150)));
+        assert!(debouncer.start(at(160)));
+
+        // A change while calculating supersedes its result, and waits for it to finish
+        debouncer.changed(at(170));
+        assert!(!debouncer.start(at(300)));
+        assert!(!debouncer.finish());
+        assert!(debouncer.start(at(300)));
+        assert!(debouncer.finish());
+        assert!(!debouncer.start(at(1000)));
+    }
+}
@@ -0,0 +1,245 @@
+use crate::archive;
+use crate::soulver::Line;
+use crate::totals;
+
+const SPREADSHEET_NAMESPACE: &str = "http://schemas.openxmlformats.org/spreadsheetml/2006/main";
+const RELATIONSHIP_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const XML_DECLARATION: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+
+/// The longest name Excel allows for a worksheet.
+const MAX_WORKSHEET_NAME_LENGTH: usize = 31;
+
+/// The index of the bold cell style in `styles.xml`, used for the header and `#` heading rows.
+const BOLD_STYLE: usize = 1;
+
+const STYLES: &str = concat!(
+    r#"<fonts count="2"><font><sz val="11"/><name val="Calibri"/></font>"#,
+    r#"<font><b/><sz val="11"/><name val="Calibri"/></font></fonts>"#,
+    r#"<fills count="2"><fill><patternFill patternType="none"/></fill>"#,
+    r#"<fill><patternFill patternType="gray125"/></fill></fills>"#,
+    r#"<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>"#,
+    r#"<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>"#,
+    r#"<cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>"#,
+    r#"<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs>"#,
+);
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // Other control characters are not allowed in XML at all
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A worksheet name Excel accepts, without the characters it reserves and at most
+/// [`MAX_WORKSHEET_NAME_LENGTH`] characters long.
+fn worksheet_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .filter(|c| !matches!(c, '[' | ']' | ':' | '*' | '?' | '/' | '\\'))
+        .take(MAX_WORKSHEET_NAME_LENGTH)
+        .collect();
+    let name = name.trim_matches('\'').trim();
+    if name.is_empty() {
+        "Sheet".to_owned()
+    } else {
+        name.to_owned()
+    }
+}
+
+/// Name each worksheet, numbering names that are the same ignoring case, which Excel rejects.
+fn worksheet_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut worksheet_names: Vec<String> = Vec::new();
+    for name in names {
+        let base = worksheet_name(name);
+        let mut name = base.clone();
+        let mut number = 2;
+        while worksheet_names
+            .iter()
+            .any(|existing| existing.eq_ignore_ascii_case(&name))
+        {
+            let suffix = format!(" ({number})");
+            let base: String = base
+                .chars()
+                .take(MAX_WORKSHEET_NAME_LENGTH - suffix.len())
+                .collect();
+            name = format!("{base}{suffix}");
+            number += 1;
+        }
+        worksheet_names.push(name);
+    }
+    worksheet_names
+}
+
+/// A cell with a number if the text is a plain number, so spreadsheets can calculate with it, or
+/// otherwise the text.
+fn cell(reference: &str, text: &str, style: usize) -> String {
+    let style = if style == 0 {
+        String::new()
+    } else {
+        format!(r#" s="{style}""#)
+    };
+    if text.parse::<f64>().is_ok_and(f64::is_finite) {
+        format!(r#"<c r="{reference}"{style}><v>{text}</v></c>"#)
+    } else {
+        format!(
+            r#"<c r="{reference}"{style} t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+            escape(text),
+        )
+    }
+}
+
+/// A worksheet with a header row and a row of each line's input and result.
+fn worksheet(lines: &[Line]) -> String {
+    let header = Line {
+        input: "Input".to_owned(),
+        output: "Result".to_owned(),
+    };
+    let rows: String = std::iter::once((&header, BOLD_STYLE))
+        .chain(lines.iter().map(|line| {
+            let style = if totals::heading(line).is_some() {
+                BOLD_STYLE
+            } else {
+                0
+            };
+            (line, style)
+        }))
+        .enumerate()
+        .map(|(index, (line, style))| {
+            let number = index + 1;
+            let mut row = format!(r#"<row r="{number}">"#);
+            for (column, text) in [("A", &line.input), ("B", &line.output)] {
+                if !text.is_empty() {
+                    row.push_str(&cell(&format!("{column}{number}"), text, style));
+                }
+            }
+            row.push_str("</row>");
+            row
+        })
+        .collect();
+    format!(
+        r#"{XML_DECLARATION}<worksheet xmlns="{SPREADSHEET_NAMESPACE}"><cols><col min="1" max="2" width="40" customWidth="1"/></cols><sheetData>{rows}</sheetData></worksheet>"#
+    )
+}
+
+/// Build an `.xlsx` workbook with a two-column worksheet of inputs and results for each sheet,
+/// given as names and lines.
+pub fn workbook(sheets: &[(&str, &[Line])]) -> Vec<u8> {
+    let names = worksheet_names(sheets.iter().map(|(name, _)| *name));
+    let mut overrides = String::new();
+    let mut workbook_sheets = String::new();
+    let mut relationships = String::new();
+    for (index, name) in names.iter().enumerate() {
+        let number = index + 1;
+        overrides.push_str(&format!(
+            r#"<Override PartName="/xl/worksheets/sheet{number}.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
+        ));
+        workbook_sheets.push_str(&format!(
+            r#"<sheet name="{}" sheetId="{number}" r:id="rId{number}"/>"#,
+            escape(name),
+        ));
+        relationships.push_str(&format!(
+            r#"<Relationship Id="rId{number}" Type="{RELATIONSHIP_NAMESPACE}/worksheet" Target="worksheets/sheet{number}.xml"/>"#
+        ));
+    }
+    relationships.push_str(&format!(
+        r#"<Relationship Id="rId{}" Type="{RELATIONSHIP_NAMESPACE}/styles" Target="styles.xml"/>"#,
+        sheets.len() + 1,
+    ));
+
+    let content_types = format!(
+        r#"{XML_DECLARATION}<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>{overrides}</Types>"#
+    );
+    let package_relationships = format!(
+        r#"{XML_DECLARATION}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="{RELATIONSHIP_NAMESPACE}/officeDocument" Target="xl/workbook.xml"/></Relationships>"#
+    );
+    let workbook = format!(
+        r#"{XML_DECLARATION}<workbook xmlns="{SPREADSHEET_NAMESPACE}" xmlns:r="{RELATIONSHIP_NAMESPACE}"><sheets>{workbook_sheets}</sheets></workbook>"#
+    );
+    let workbook_relationships = format!(
+        r#"{XML_DECLARATION}<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">{relationships}</Relationships>"#
+    );
+    let styles = format!(
+        r#"{XML_DECLARATION}<styleSheet xmlns="{SPREADSHEET_NAMESPACE}">{STYLES}</styleSheet>"#
+    );
+    let worksheets: Vec<(String, String)> = sheets
+        .iter()
+        .enumerate()
+        .map(|(index, (_, lines))| {
+            (
+                format!("xl/worksheets/sheet{}.xml", index + 1),
+                worksheet(lines),
+            )
+        })
+        .collect();
+
+    let mut files: Vec<(&str, &[u8])> = vec![
+        ("[Content_Types].xml", content_types.as_bytes()),
+        ("_rels/.rels", package_relationships.as_bytes()),
+        ("xl/workbook.xml", workbook.as_bytes()),
+        (
+            "xl/_rels/workbook.xml.rels",
+            workbook_relationships.as_bytes(),
+        ),
+        ("xl/styles.xml", styles.as_bytes()),
+    ];
+    files.extend(
+        worksheets
+            .iter()
+            .map(|(path, worksheet)| (path.as_str(), worksheet.as_bytes())),
+    );
+    archive::zip(&files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worksheet_names() {
+        assert_eq!(worksheet_name("budget: 2024/25?"), "budget 202425");
+        assert_eq!(worksheet_name("[]"), "Sheet");
+        assert_eq!(worksheet_name(&"a".repeat(40)).len(), 31);
+        assert_eq!(
+            worksheet_names(["Budget", "budget", "Budget"]),
+            vec!["Budget", "budget (2)", "Budget (3)"],
+        );
+    }
+
+    #[test]
+    fn test_worksheet() {
+        let worksheet = worksheet(&[
+            Line::new("# Food", ""),
+            Line::new("Rent: $1000 & more", "$1,000.00"),
+            Line::new("2 + 2", "4"),
+        ]);
+        assert!(worksheet.contains(
+            r#"<row r="1"><c r="A1" s="1" t="inlineStr"><is><t xml:space="preserve">Input</t></is></c>"#
+        ));
+        assert!(worksheet.contains(
+            r#"<row r="2"><c r="A2" s="1" t="inlineStr"><is><t xml:space="preserve"># Food</t></is></c></row>"#
+        ));
+        assert!(worksheet.contains("Rent: $1000 &amp; more"));
+        assert!(worksheet.contains(r#"<c r="B4"><v>4</v></c>"#));
+    }
+
+    #[test]
+    fn test_workbook() {
+        let lines = [Line::new("1 + 1", "2")];
+        let workbook = workbook(&[("a", &lines), ("b", &lines)]);
+        let text = String::from_utf8_lossy(&workbook);
+        assert!(text.contains("xl/worksheets/sheet2.xml"));
+        assert!(text.contains(r#"<sheet name="b" sheetId="2" r:id="rId2"/>"#));
+        assert!(text.contains(r#"<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles""#));
+    }
+}